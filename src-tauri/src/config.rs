@@ -2,8 +2,10 @@ use crate::errors::Error;
 use crate::util::{default_runner_name, platform_label};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
@@ -12,6 +14,9 @@ use uuid::Uuid;
 
 const SCHEMA_VERSION: u32 = 3;
 
+pub const DEFAULT_GITHUB_BASE_URL: &str = "https://github.com";
+pub const DEFAULT_API_BASE_URL: &str = "https://api.github.com";
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum AdoptionDefault {
@@ -25,10 +30,19 @@ impl Default for AdoptionDefault {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum AuthMethod {
     Pat,
+    App { app_id: u64, installation_id: u64 },
 }
 
 impl Default for AuthMethod {
@@ -70,12 +84,33 @@ impl RunnerScope {
         }
     }
 
-    pub fn url(&self) -> String {
+    pub fn api_runners_endpoint(&self) -> String {
+        match self {
+            RunnerScope::Repo { owner, repo } => format!("/repos/{owner}/{repo}/actions/runners"),
+            RunnerScope::Org { org } => format!("/orgs/{org}/actions/runners"),
+            RunnerScope::Enterprise { enterprise } => {
+                format!("/enterprises/{enterprise}/actions/runners")
+            }
+        }
+    }
+
+    pub fn api_runner_groups_endpoint(&self) -> Option<String> {
+        match self {
+            RunnerScope::Repo { .. } => None,
+            RunnerScope::Org { org } => Some(format!("/orgs/{org}/actions/runner-groups")),
+            RunnerScope::Enterprise { enterprise } => Some(format!(
+                "/enterprises/{enterprise}/actions/runner-groups"
+            )),
+        }
+    }
+
+    pub fn url(&self, github_base_url: &str) -> String {
+        let base = github_base_url.trim_end_matches('/');
         match self {
-            RunnerScope::Repo { owner, repo } => format!("https://github.com/{owner}/{repo}"),
-            RunnerScope::Org { org } => format!("https://github.com/{org}"),
+            RunnerScope::Repo { owner, repo } => format!("{base}/{owner}/{repo}"),
+            RunnerScope::Org { org } => format!("{base}/{org}"),
             RunnerScope::Enterprise { enterprise } => {
-                format!("https://github.com/enterprises/{enterprise}")
+                format!("{base}/enterprises/{enterprise}")
             }
         }
     }
@@ -140,6 +175,92 @@ pub struct RunnerServiceConfig {
     pub external_path: Option<String>,
     #[serde(default)]
     pub external_restore: Option<ExternalServiceInfo>,
+    /// Install system-wide so the runner starts at boot: a unit under
+    /// /etc/systemd/system instead of a `--user` unit on Linux, a LaunchDaemon
+    /// instead of a LaunchAgent on macOS.
+    #[serde(default)]
+    pub system_scope: bool,
+    /// Account the system-wide service runs as; defaults to the current user.
+    #[serde(default)]
+    pub system_user: Option<String>,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default = "default_restart_sec")]
+    pub restart_sec: u64,
+    #[serde(default)]
+    pub start_limit_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub start_limit_burst: Option<u32>,
+    /// systemd `MemoryMax=`, e.g. `4G` or `50%`.
+    #[serde(default)]
+    pub memory_max: Option<String>,
+    /// systemd `CPUQuota=`, e.g. `200%`.
+    #[serde(default)]
+    pub cpu_quota: Option<String>,
+    /// launchd `KeepAlive` for RunnerBuddy-managed macOS services.
+    #[serde(default)]
+    pub keep_alive: KeepAlive,
+    /// launchd `ThrottleInterval`: minimum seconds between launches; launchd's own
+    /// default (10s) applies when unset.
+    #[serde(default)]
+    pub throttle_interval_secs: Option<u64>,
+    /// launchd `Nice` (-20..=19); unset leaves the runner at normal priority.
+    #[serde(default)]
+    pub nice: Option<i32>,
+    /// launchd `ProcessType`, e.g. `Background` to let macOS throttle the runner.
+    #[serde(default)]
+    pub process_type: ProcessType,
+}
+
+/// systemd `Restart=` for RunnerBuddy-managed units.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Always,
+    OnFailure,
+    No,
+}
+
+impl RestartPolicy {
+    pub fn as_systemd(self) -> &'static str {
+        match self {
+            RestartPolicy::Always => "always",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::No => "no",
+        }
+    }
+}
+
+/// launchd `KeepAlive`: when a RunnerBuddy-managed LaunchAgent/Daemon is relaunched.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeepAlive {
+    /// `KeepAlive = true`: relaunch whenever the runner exits.
+    #[default]
+    Always,
+    /// `SuccessfulExit = false`: relaunch only after a non-zero exit.
+    OnFailure,
+    /// `Crashed = true`: relaunch only after the runner dies from a signal.
+    OnCrash,
+    /// `KeepAlive = false`.
+    Never,
+}
+
+/// launchd `ProcessType`; `Default` leaves the key out of the plist.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProcessType {
+    #[default]
+    Default,
+    Background,
+    Standard,
+    Adaptive,
+    Interactive,
+}
+
+pub fn default_restart_sec() -> u64 {
+    5
 }
 
 impl Default for RunnerServiceConfig {
@@ -151,6 +272,18 @@ impl Default for RunnerServiceConfig {
             external_id: None,
             external_path: None,
             external_restore: None,
+            system_scope: false,
+            system_user: None,
+            restart_policy: RestartPolicy::Always,
+            restart_sec: default_restart_sec(),
+            start_limit_interval_secs: None,
+            start_limit_burst: None,
+            memory_max: None,
+            cpu_quota: None,
+            keep_alive: KeepAlive::Always,
+            throttle_interval_secs: None,
+            nice: None,
+            process_type: ProcessType::Default,
         }
     }
 }
@@ -165,6 +298,28 @@ pub struct RunnerProfile {
     pub work_dir: String,
     pub install: InstallConfig,
     pub runner_version: Option<String>,
+    #[serde(default)]
+    pub runner_group: Option<String>,
+    #[serde(default)]
+    pub ephemeral: bool,
+    /// Re-register and relaunch an ephemeral runner after each job it completes.
+    #[serde(default)]
+    pub ephemeral_restart: bool,
+    /// Registered on each start via generate-jitconfig instead of `config.sh`.
+    #[serde(default)]
+    pub jit: bool,
+    /// Relaunch the runner process if it exits without a stop request.
+    #[serde(default)]
+    pub auto_restart: bool,
+    /// Launch the runner process whenever RunnerBuddy starts (non-service runners).
+    #[serde(default)]
+    pub start_on_app_launch: bool,
+    /// Keep `runner_version` as is: update checks report "pinned" and updates are refused.
+    #[serde(default)]
+    pub version_pinned: bool,
+    /// Extra environment for the runner process and its service definition.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
     pub pat_alias: String,
     pub service: RunnerServiceConfig,
     pub created_at: String,
@@ -192,6 +347,79 @@ pub struct SettingsConfig {
     pub auto_updates_enabled: bool,
     pub auto_check_updates_on_launch: bool,
     pub adoption_default: AdoptionDefault,
+    #[serde(default = "default_github_base_url")]
+    pub github_base_url: String,
+    #[serde(default = "default_api_base_url")]
+    pub api_base_url: String,
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    #[serde(default)]
+    pub ca_bundle_path: Option<String>,
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+    #[serde(default = "default_download_timeout_secs")]
+    pub download_timeout_secs: u64,
+    #[serde(default = "default_runner_stop_grace_secs")]
+    pub runner_stop_grace_secs: u64,
+    /// Runner stdout/stderr logs are rotated to `.1` past this size; 0 disables rotation.
+    #[serde(default = "default_runner_log_max_bytes")]
+    pub runner_log_max_bytes: u64,
+    #[serde(default = "default_status_poll_interval_secs")]
+    pub status_poll_interval_secs: u64,
+    /// Extra regexes redacted from logs and exports alongside the built-in token patterns.
+    #[serde(default)]
+    pub scrub_patterns: Vec<String>,
+    /// Refuse runner downloads that have no SHA-256 to check against.
+    #[serde(default)]
+    pub require_checksum: bool,
+    /// Mirror serving runner archives by file name; `{version}` is substituted.
+    #[serde(default)]
+    pub runner_download_base: Option<String>,
+    /// Runner version to fetch from the mirror without asking GitHub for the latest.
+    #[serde(default)]
+    pub runner_download_version: Option<String>,
+    /// Extra directories searched by discovery, in addition to the managed dir and home.
+    #[serde(default)]
+    pub scan_paths: Vec<String>,
+    /// How many directory levels discovery descends below each scan root.
+    #[serde(default = "default_scan_depth")]
+    pub scan_depth: u32,
+    /// Runner archives kept in the downloads dir after an install; `0` keeps all.
+    #[serde(default)]
+    pub downloads_keep: u32,
+    /// Upper bound on runners running at once (processes and services); `0` is unlimited.
+    #[serde(default)]
+    pub max_concurrent_runners: u32,
+    /// Desktop notification when an idle runner picks up a job.
+    #[serde(default)]
+    pub notify_job_started: bool,
+    #[serde(default)]
+    pub notify_job_finished: bool,
+    /// Desktop notification when a runner fails to start or exits with an error.
+    #[serde(default)]
+    pub notify_runner_errors: bool,
+    /// Serve Prometheus metrics on `127.0.0.1:<metrics_port>/metrics`.
+    #[serde(default)]
+    pub metrics_enabled: bool,
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
+    /// Format of `runnerbuddy.log`, applied at the next launch; `RUNNERBUDDY_LOG_FORMAT`
+    /// overrides it.
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// `runnerbuddy.log` is rotated past this size (and at midnight UTC); 0 rotates daily only.
+    #[serde(default = "default_app_log_max_bytes")]
+    pub app_log_max_bytes: u64,
+    /// Rotated app logs kept; 0 keeps all.
+    #[serde(default = "default_app_log_keep_files")]
+    pub app_log_keep_files: u32,
+    /// Rotated app logs older than this many days are deleted; 0 disables the age limit.
+    #[serde(default = "default_app_log_keep_days")]
+    pub app_log_keep_days: u32,
 }
 
 impl Default for SettingsConfig {
@@ -200,10 +428,117 @@ impl Default for SettingsConfig {
             auto_updates_enabled: true,
             auto_check_updates_on_launch: true,
             adoption_default: AdoptionDefault::Adopt,
+            github_base_url: default_github_base_url(),
+            api_base_url: default_api_base_url(),
+            proxy_url: None,
+            ca_bundle_path: None,
+            danger_accept_invalid_certs: false,
+            connect_timeout_secs: default_connect_timeout_secs(),
+            request_timeout_secs: default_request_timeout_secs(),
+            download_timeout_secs: default_download_timeout_secs(),
+            runner_stop_grace_secs: default_runner_stop_grace_secs(),
+            runner_log_max_bytes: default_runner_log_max_bytes(),
+            status_poll_interval_secs: default_status_poll_interval_secs(),
+            scrub_patterns: Vec::new(),
+            require_checksum: false,
+            runner_download_base: None,
+            runner_download_version: None,
+            scan_paths: Vec::new(),
+            scan_depth: default_scan_depth(),
+            downloads_keep: 0,
+            max_concurrent_runners: 0,
+            notify_job_started: false,
+            notify_job_finished: false,
+            notify_runner_errors: false,
+            metrics_enabled: false,
+            metrics_port: default_metrics_port(),
+            log_format: LogFormat::Text,
+            app_log_max_bytes: default_app_log_max_bytes(),
+            app_log_keep_files: default_app_log_keep_files(),
+            app_log_keep_days: default_app_log_keep_days(),
         }
     }
 }
 
+fn default_connect_timeout_secs() -> u64 {
+    30
+}
+
+fn default_request_timeout_secs() -> u64 {
+    30
+}
+
+fn default_download_timeout_secs() -> u64 {
+    30 * 60
+}
+
+fn default_runner_stop_grace_secs() -> u64 {
+    30
+}
+
+fn default_runner_log_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_app_log_max_bytes() -> u64 {
+    20 * 1024 * 1024
+}
+
+fn default_app_log_keep_files() -> u32 {
+    10
+}
+
+fn default_app_log_keep_days() -> u32 {
+    14
+}
+
+fn default_status_poll_interval_secs() -> u64 {
+    5
+}
+
+fn default_scan_depth() -> u32 {
+    1
+}
+
+fn default_metrics_port() -> u16 {
+    9464
+}
+
+fn default_github_base_url() -> String {
+    DEFAULT_GITHUB_BASE_URL.to_string()
+}
+
+fn default_api_base_url() -> String {
+    DEFAULT_API_BASE_URL.to_string()
+}
+
+pub fn normalize_github_base_url(value: &str) -> Result<String, Error> {
+    let trimmed = value.trim().trim_end_matches('/');
+    if trimmed.is_empty() {
+        return Ok(default_github_base_url());
+    }
+    let Some((scheme, rest)) = trimmed.split_once("://") else {
+        return Err(Error::Config(format!(
+            "GitHub URL must start with https:// or http://: {trimmed}"
+        )));
+    };
+    if !matches!(scheme, "https" | "http") || rest.is_empty() || rest.contains('/') {
+        return Err(Error::Config(format!(
+            "GitHub URL must be a bare host such as https://github.example.com: {trimmed}"
+        )));
+    }
+    Ok(format!("{scheme}://{}", rest.to_ascii_lowercase()))
+}
+
+/// GitHub.com serves its API from a separate host; GHES serves it under `/api/v3`.
+pub fn derive_api_base_url(github_base_url: &str) -> String {
+    let base = github_base_url.trim_end_matches('/');
+    if base == DEFAULT_GITHUB_BASE_URL || base == "https://www.github.com" {
+        return DEFAULT_API_BASE_URL.to_string();
+    }
+    format!("{base}/api/v3")
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     #[serde(default = "default_schema_version")]
@@ -212,6 +547,12 @@ pub struct Config {
     pub selected_runner_id: Option<String>,
     #[serde(default = "default_pat_alias")]
     pub pat_default_alias: String,
+    /// How each credential alias authenticates; aliases missing here use a PAT.
+    #[serde(default)]
+    pub auth_methods: BTreeMap<String, AuthMethod>,
+    /// Aliases with a credential in the OS store; the keyring cannot be enumerated.
+    #[serde(default)]
+    pub pat_aliases: BTreeSet<String>,
     #[serde(default = "default_onboarding")]
     pub onboarding: OnboardingConfig,
     #[serde(default = "default_settings")]
@@ -245,6 +586,8 @@ impl Default for Config {
             schema_version: SCHEMA_VERSION,
             selected_runner_id: None,
             pat_default_alias: default_pat_alias(),
+            auth_methods: BTreeMap::new(),
+            pat_aliases: BTreeSet::new(),
             onboarding: default_onboarding(),
             settings: default_settings(),
             runners: Vec::new(),
@@ -253,6 +596,70 @@ impl Default for Config {
 }
 
 impl Config {
+    pub fn auth_method(&self, alias: &str) -> AuthMethod {
+        self.auth_methods.get(alias).cloned().unwrap_or_default()
+    }
+
+    /// Every alias the config knows about: indexed credentials, the default alias
+    /// and aliases referenced by runners or auth settings.
+    pub fn known_aliases(&self) -> BTreeSet<String> {
+        let mut aliases = self.pat_aliases.clone();
+        aliases.insert(self.pat_default_alias.clone());
+        aliases.extend(self.auth_methods.keys().cloned());
+        aliases.extend(self.runners.iter().map(|runner| runner.pat_alias.clone()));
+        aliases
+    }
+
+    /// Brings in runners and auth settings from an export. Replacing keeps only the
+    /// local credential index, since secrets are never part of an export; merging
+    /// adds runners whose id and install path are both new. Returns how many
+    /// runners were added.
+    pub fn import(&mut self, imported: Config, merge: bool) -> usize {
+        if !merge {
+            let stored = std::mem::take(&mut self.pat_aliases);
+            let count = imported.runners.len();
+            *self = Config {
+                schema_version: SCHEMA_VERSION,
+                pat_aliases: stored,
+                ..imported
+            };
+            sanitize_selected_runner_id(self);
+            return count;
+        }
+        let mut added = 0;
+        for runner in imported.runners {
+            let exists = self.runners.iter().any(|existing| {
+                existing.runner_id == runner.runner_id
+                    || existing.install.install_path == runner.install.install_path
+            });
+            if !exists {
+                self.runners.push(runner);
+                added += 1;
+            }
+        }
+        for (alias, method) in imported.auth_methods {
+            self.auth_methods.entry(alias).or_insert(method);
+        }
+        sanitize_selected_runner_id(self);
+        added
+    }
+
+    /// Points every reference to `old` at `new`. The keyring entry is moved separately.
+    pub fn rename_alias(&mut self, old: &str, new: &str) {
+        if self.pat_aliases.remove(old) {
+            self.pat_aliases.insert(new.to_string());
+        }
+        if let Some(method) = self.auth_methods.remove(old) {
+            self.auth_methods.insert(new.to_string(), method);
+        }
+        if self.pat_default_alias == old {
+            self.pat_default_alias = new.to_string();
+        }
+        for runner in self.runners.iter_mut().filter(|runner| runner.pat_alias == old) {
+            runner.pat_alias = new.to_string();
+        }
+    }
+
     fn migrate_from_legacy(legacy: LegacyConfig) -> Result<Self, Error> {
         let runner_id = new_runner_id();
         let install_path = if legacy.install_path.is_empty() {
@@ -279,6 +686,14 @@ impl Config {
                 migration_status: MigrationStatus::None,
             },
             runner_version: legacy.runner_version,
+            runner_group: None,
+            ephemeral: false,
+            ephemeral_restart: false,
+            jit: false,
+            auto_restart: false,
+            start_on_app_launch: false,
+            version_pinned: false,
+            env: Default::default(),
             pat_alias: default_pat_alias(),
             service: RunnerServiceConfig {
                 installed: legacy.service.installed,
@@ -291,6 +706,18 @@ impl Config {
                 external_id: None,
                 external_path: None,
                 external_restore: None,
+                system_scope: false,
+                system_user: None,
+                restart_policy: RestartPolicy::Always,
+                restart_sec: default_restart_sec(),
+                start_limit_interval_secs: None,
+                start_limit_burst: None,
+                memory_max: None,
+                cpu_quota: None,
+                keep_alive: KeepAlive::Always,
+                throttle_interval_secs: None,
+                nice: None,
+                process_type: ProcessType::Default,
             },
             created_at: now_iso8601(),
             last_seen_at: None,
@@ -299,6 +726,8 @@ impl Config {
             schema_version: SCHEMA_VERSION,
             selected_runner_id: Some(runner_id),
             pat_default_alias: default_pat_alias(),
+            auth_methods: BTreeMap::new(),
+            pat_aliases: BTreeSet::new(),
             onboarding: OnboardingConfig::completed_for_upgrade(),
             settings: SettingsConfig::default(),
             runners: vec![profile],
@@ -329,9 +758,8 @@ struct LegacyServiceConfig {
     pub run_on_boot: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 struct LegacyGithubConfig {
-    pub auth_method: AuthMethod,
     pub token_expires: Option<String>,
 }
 
@@ -376,15 +804,6 @@ impl Default for LegacyServiceConfig {
     }
 }
 
-impl Default for LegacyGithubConfig {
-    fn default() -> Self {
-        Self {
-            auth_method: AuthMethod::Pat,
-            token_expires: None,
-        }
-    }
-}
-
 pub struct ConfigStore {
     path: PathBuf,
     inner: Mutex<Config>,
@@ -392,37 +811,21 @@ pub struct ConfigStore {
 
 impl ConfigStore {
     pub fn load() -> Result<Self, Error> {
-        let path = config_path()?;
+        Self::load_from(config_path()?)
+    }
+
+    pub(crate) fn load_from(path: PathBuf) -> Result<Self, Error> {
         let mut needs_save = false;
         let config = if path.exists() {
-            let data = fs::read_to_string(&path)?;
-            let value: serde_json::Value = serde_json::from_str(&data)?;
-            let schema_version = value
-                .get("schema_version")
-                .and_then(|val| val.as_u64())
-                .unwrap_or(1) as u32;
-            let mut config = if schema_version == 1 {
-                let legacy: LegacyConfig = serde_json::from_value(value.clone())?;
-                needs_save = true;
-                Config::migrate_from_legacy(legacy)?
-            } else if schema_version < SCHEMA_VERSION {
-                let mut config: Config = serde_json::from_value(value.clone())?;
-                migrate_external_hint(&mut config, &value);
-                config.schema_version = SCHEMA_VERSION;
-                needs_save = true;
-                config
-            } else {
-                serde_json::from_value(value.clone())?
+            let loaded = match parse_config_file(&path) {
+                Ok(loaded) => loaded,
+                Err(err) => recover_from_backup(&path, err)?,
             };
-            if schema_version != 1 {
-                if apply_missing_fields(&mut config, &value) {
-                    needs_save = true;
-                }
-            }
-            if sanitize_selected_runner_id(&mut config) {
-                needs_save = true;
+            if let Some(schema_version) = loaded.migrated_from {
+                backup_config(&path, schema_version)?;
             }
-            config
+            needs_save = loaded.needs_save;
+            loaded.config
         } else {
             Config::default()
         };
@@ -476,9 +879,155 @@ impl ConfigStore {
             fs::create_dir_all(parent)?;
         }
         let data = serde_json::to_string_pretty(config)?;
-        fs::write(&self.path, data)?;
+        write_atomic(&self.path, data.as_bytes())
+    }
+}
+
+/// Writes a sibling temp file and renames it over `path`, so a crash mid-write
+/// leaves either the old or the new file, never a truncated one.
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> Result<(), Error> {
+    let tmp = path.with_extension("json.tmp");
+    let result = (|| -> Result<(), Error> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(data)?;
+        #[cfg(unix)]
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&tmp, path)?;
+        #[cfg(unix)]
+        if let Some(parent) = path.parent() {
+            fs::File::open(parent)?.sync_all()?;
+        }
         Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+struct LoadedConfig {
+    config: Config,
+    needs_save: bool,
+    /// Schema the file was migrated from, when it was older than `SCHEMA_VERSION`.
+    migrated_from: Option<u32>,
+}
+
+fn parse_config_file(path: &Path) -> Result<LoadedConfig, Error> {
+    parse_config_str(&fs::read_to_string(path)?)
+}
+
+/// Parses an exported config through the same migrations as `load`.
+pub fn parse_config_export(data: &str) -> Result<Config, Error> {
+    let value: serde_json::Value = serde_json::from_str(data)?;
+    if !value.is_object() || value.get("runners").is_none() {
+        return Err(Error::Config("file is not a RunnerBuddy config export".into()));
+    }
+    let schema_version = value.get("schema_version").and_then(|val| val.as_u64());
+    if schema_version.is_some_and(|version| version > SCHEMA_VERSION as u64) {
+        return Err(Error::Config(
+            "config export was written by a newer RunnerBuddy; update before importing".into(),
+        ));
+    }
+    Ok(parse_config_str(data)?.config)
+}
+
+fn parse_config_str(data: &str) -> Result<LoadedConfig, Error> {
+    let value: serde_json::Value = serde_json::from_str(data)?;
+    let schema_version = value
+        .get("schema_version")
+        .and_then(|val| val.as_u64())
+        .unwrap_or(1) as u32;
+    let mut needs_save = false;
+    let mut config = if schema_version == 1 {
+        let legacy: LegacyConfig = serde_json::from_value(value.clone())?;
+        needs_save = true;
+        Config::migrate_from_legacy(legacy)?
+    } else if schema_version < SCHEMA_VERSION {
+        let mut config: Config = serde_json::from_value(value.clone())?;
+        migrate_external_hint(&mut config, &value);
+        config.schema_version = SCHEMA_VERSION;
+        needs_save = true;
+        config
+    } else {
+        serde_json::from_value(value.clone())?
+    };
+    if schema_version != 1 && apply_missing_fields(&mut config, &value) {
+        needs_save = true;
+    }
+    if sanitize_selected_runner_id(&mut config) {
+        needs_save = true;
     }
+    Ok(LoadedConfig {
+        config,
+        needs_save,
+        migrated_from: (schema_version < SCHEMA_VERSION).then_some(schema_version),
+    })
+}
+
+/// Keeps the pre-migration file as `config.json.bak.<schema>`; an existing backup
+/// for that schema is left alone so repeated failed upgrades keep the original.
+fn backup_config(path: &Path, schema_version: u32) -> Result<(), Error> {
+    let backup = backup_path(path, schema_version);
+    if !backup.exists() {
+        fs::copy(path, &backup)?;
+    }
+    Ok(())
+}
+
+fn backup_path(path: &Path, schema_version: u32) -> PathBuf {
+    path.with_file_name(format!("{}{schema_version}", backup_prefix(path)))
+}
+
+fn backup_prefix(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "config.json".to_string());
+    format!("{name}.bak.")
+}
+
+/// Backups next to `path`, newest first.
+fn config_backups(path: &Path) -> Vec<PathBuf> {
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let prefix = backup_prefix(path);
+    let mut backups: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+                .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort_by(|a, b| b.cmp(a));
+    backups.into_iter().map(|(_, path)| path).collect()
+}
+
+fn recover_from_backup(path: &Path, err: Error) -> Result<LoadedConfig, Error> {
+    for backup in config_backups(path) {
+        let Ok(mut loaded) = parse_config_file(&backup) else {
+            continue;
+        };
+        let corrupt = path.with_extension("json.corrupt");
+        fs::rename(path, &corrupt)?;
+        warn!(
+            "{} could not be read ({err}); recovered settings from {} and kept the damaged file as {}",
+            path.display(),
+            backup.display(),
+            corrupt.display()
+        );
+        // The recovered config must be written back even if the backup was current.
+        loaded.needs_save = true;
+        loaded.migrated_from = None;
+        return Ok(loaded);
+    }
+    Err(Error::Config(format!(
+        "{} could not be read ({err}) and no usable backup was found; fix or remove the file to start fresh",
+        path.display()
+    )))
 }
 
 pub fn config_path() -> Result<PathBuf, Error> {
@@ -598,6 +1147,39 @@ fn project_dirs() -> Result<ProjectDirs, Error> {
         .ok_or_else(|| Error::Config("failed to resolve project directories".into()))
 }
 
+/// Baseline profile for tests; each test overrides only the fields it checks
+/// with struct-update syntax.
+#[cfg(test)]
+pub(crate) fn test_profile() -> RunnerProfile {
+    RunnerProfile {
+        runner_id: "abc".to_string(),
+        display_name: "Test".to_string(),
+        scope: None,
+        runner_name: "runner".to_string(),
+        labels: vec!["self-hosted".to_string()],
+        work_dir: "/tmp".to_string(),
+        install: InstallConfig {
+            mode: InstallMode::Managed,
+            install_path: "/tmp/runner".to_string(),
+            adopted_from_path: None,
+            migration_status: MigrationStatus::None,
+        },
+        runner_version: None,
+        runner_group: None,
+        ephemeral: false,
+        ephemeral_restart: false,
+        jit: false,
+        auto_restart: false,
+        start_on_app_launch: false,
+        version_pinned: false,
+        env: Default::default(),
+        pat_alias: "default".to_string(),
+        service: RunnerServiceConfig::default(),
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+        last_seen_at: None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -694,4 +1276,127 @@ mod tests {
             "/enterprises/umbrella/actions/runners/remove-token"
         );
     }
+
+    #[test]
+    fn derives_api_base_for_dotcom_and_ghes() {
+        assert_eq!(derive_api_base_url("https://github.com/"), DEFAULT_API_BASE_URL);
+        assert_eq!(
+            derive_api_base_url("https://ghe.example.com"),
+            "https://ghe.example.com/api/v3"
+        );
+        let normalized = normalize_github_base_url(" https://GHE.example.com/ ").expect("url");
+        assert_eq!(normalized, "https://ghe.example.com");
+        assert!(normalize_github_base_url("ghe.example.com").is_err());
+        let scope = RunnerScope::Org {
+            org: "acme".to_string(),
+        };
+        assert_eq!(scope.url(&normalized), "https://ghe.example.com/acme");
+    }
+
+    #[test]
+    fn auth_method_defaults_to_pat_per_alias() {
+        let json = r#"{"auth_methods":{"fleet":{"app":{"app_id":12,"installation_id":34}}}}"#;
+        let config: Config = serde_json::from_str(json).expect("parse config");
+        assert!(matches!(config.auth_method("default"), AuthMethod::Pat));
+        assert!(matches!(
+            config.auth_method("fleet"),
+            AuthMethod::App {
+                app_id: 12,
+                installation_id: 34
+            }
+        ));
+    }
+
+    #[test]
+    fn rename_alias_rewrites_every_reference() {
+        let json = r#"{"pat_default_alias":"work","pat_aliases":["work","home"],"auth_methods":{"work":{"app":{"app_id":1,"installation_id":2}}}}"#;
+        let mut config: Config = serde_json::from_str(json).expect("parse config");
+        config.rename_alias("work", "fleet");
+        assert_eq!(config.pat_default_alias, "fleet");
+        assert!(config.pat_aliases.contains("fleet") && !config.pat_aliases.contains("work"));
+        assert!(matches!(config.auth_method("fleet"), AuthMethod::App { .. }));
+        assert!(config.known_aliases().contains("home"));
+    }
+
+    #[test]
+    fn migration_keeps_backup_and_recovers_from_it() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("config.json");
+        let mut value = serde_json::to_value(Config::default()).expect("serialize config");
+        value["schema_version"] = serde_json::json!(2);
+        fs::write(&path, serde_json::to_string(&value).unwrap()).expect("write v2 config");
+
+        let store = ConfigStore::load_from(path.clone()).expect("load v2 config");
+        assert_eq!(store.get().schema_version, SCHEMA_VERSION);
+        let backup = dir.path().join("config.json.bak.2");
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&backup).expect("read backup")).unwrap();
+        assert_eq!(saved["schema_version"], 2);
+
+        fs::write(&path, "{\"schema_version\": 3, \"runners\": [").expect("truncate config");
+        let store = ConfigStore::load_from(path.clone()).expect("recover from backup");
+        assert_eq!(store.get().schema_version, SCHEMA_VERSION);
+        assert!(dir.path().join("config.json.corrupt").exists());
+        let rewritten: Config =
+            serde_json::from_str(&fs::read_to_string(&path).expect("read config")).unwrap();
+        assert_eq!(rewritten.schema_version, SCHEMA_VERSION);
+
+        fs::write(&path, "not json").expect("corrupt config");
+        fs::remove_file(&backup).expect("remove backup");
+        let err = ConfigStore::load_from(path).err().expect("no backup left");
+        assert!(err.to_string().contains("no usable backup"));
+    }
+
+    #[test]
+    fn save_writes_config_without_leaving_temp_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("config.json");
+        let store = ConfigStore::load_from(path.clone()).expect("load");
+        store
+            .update(|config| config.pat_default_alias = "fleet".to_string())
+            .expect("save config");
+        let saved: Config =
+            serde_json::from_str(&fs::read_to_string(&path).expect("read config")).unwrap();
+        assert_eq!(saved.pat_default_alias, "fleet");
+        assert!(!dir.path().join("config.json.tmp").exists());
+    }
+
+    #[test]
+    fn import_merges_new_runners_and_keeps_local_credentials() {
+        let runner_with = |runner_id: &str, install_path: &str, pat_alias: &str| RunnerProfile {
+            runner_id: runner_id.to_string(),
+            install: InstallConfig {
+                install_path: install_path.to_string(),
+                ..test_profile().install
+            },
+            pat_alias: pat_alias.to_string(),
+            ..test_profile()
+        };
+        let mut local = Config::default();
+        local.pat_aliases.insert("local".to_string());
+        local.runners.push(runner_with("a", "/opt/runners/a", "local"));
+
+        let mut exported = Config::default();
+        exported.pat_aliases.insert("remote".to_string());
+        exported.runners.push(runner_with("a", "/opt/runners/a", "remote"));
+        exported.runners.push(runner_with("b", "/opt/runners/a", "remote"));
+        exported.runners.push(runner_with("c", "/opt/runners/c", "remote"));
+        let data = serde_json::to_string(&exported).unwrap();
+        let parsed = parse_config_export(&data).expect("parse export");
+
+        let mut merged = local.clone();
+        assert_eq!(merged.import(parsed.clone(), true), 1);
+        let ids: Vec<_> = merged.runners.iter().map(|r| r.runner_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c"]);
+        assert_eq!(merged.runners[0].pat_alias, "local");
+
+        let mut replaced = local;
+        assert_eq!(replaced.import(parsed, false), 3);
+        assert_eq!(replaced.runners.len(), 3);
+        assert!(replaced.pat_aliases.contains("local"));
+        assert!(!replaced.pat_aliases.contains("remote"));
+
+        assert!(parse_config_export("{\"schema_version\": 99, \"runners\": []}").is_err());
+        assert!(parse_config_export("[]").is_err());
+    }
 }
@@ -1,23 +1,32 @@
 use crate::config::{
-    default_install_path, default_runner_labels, default_work_dir, now_iso8601, Config, ConfigStore,
-    ExternalServiceInfo, InstallConfig, InstallMode, RunnerProfile, RunnerScope,
-    RunnerServiceConfig, ServiceProvider,
+    default_install_path, default_restart_sec, default_runner_labels, default_work_dir,
+    now_iso8601, Config, ConfigStore, ExternalServiceInfo, InstallConfig, InstallMode,
+    KeepAlive, ProcessType, RestartPolicy, RunnerProfile, RunnerScope, RunnerServiceConfig,
+    ServiceProvider,
 };
 use crate::errors::Error;
 use crate::runner_mgmt::latest_log_file;
 use crate::service_mgmt;
-use crate::util::{default_runner_name, read_file_tail, LOG_TAIL_BYTES};
+use crate::util::{default_runner_name, dir_size, expand_path, read_file_tail, LOG_TAIL_BYTES};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::VecDeque;
 use std::collections::HashSet;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 #[cfg(target_os = "windows")]
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
-use tracing::info;
+use tracing::{info, warn};
+
+pub const MAX_SCAN_DEPTH: u32 = 6;
+const SCAN_WORKERS: usize = 8;
+const SIZE_SCAN_DEPTH: u32 = 32;
 
 #[derive(Debug, Serialize, Clone)]
 pub struct DiscoveryCandidate {
@@ -31,6 +40,16 @@ pub struct DiscoveryCandidate {
     pub service_id: Option<String>,
     pub service_path: Option<String>,
     pub last_log_time: Option<String>,
+    /// Only filled in when the scan was asked to measure disk usage.
+    pub size_bytes: Option<u64>,
+}
+
+/// A runner service left behind after its install directory was deleted.
+#[derive(Debug, Serialize, Clone)]
+pub struct OrphanedService {
+    pub service_id: String,
+    pub artifact_path: Option<String>,
+    pub missing_path: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -41,15 +60,25 @@ pub struct ImportOptions {
     pub verify_after_move: bool,
     #[serde(default)]
     pub delete_original_after_verify: bool,
+    /// Compare SHA-256 of every copied file; defaults to `verify_after_move`.
+    #[serde(default)]
+    pub verify_hashes: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ServiceMigrationStrategy {
     ReplaceWithRunnerbuddy,
+    /// Drop the external service and leave the runner to be started by hand.
+    RemoveExternal,
 }
 
-pub fn scan(config: &Config) -> Result<Vec<DiscoveryCandidate>, Error> {
+/// `on_progress(scanned, total)` is called from the worker threads as each path finishes.
+pub fn scan(
+    config: &Config,
+    include_sizes: bool,
+    on_progress: impl Fn(usize, usize) + Sync,
+) -> Result<Vec<DiscoveryCandidate>, Error> {
     let mut paths = Vec::new();
     let mut seen = HashSet::new();
     let mut add_path = |path: PathBuf| {
@@ -75,52 +104,106 @@ pub fn scan(config: &Config) -> Result<Vec<DiscoveryCandidate>, Error> {
         }
     }
 
+    let depth = config.settings.scan_depth.clamp(1, MAX_SCAN_DEPTH);
     if let Some(user_dirs) = directories::UserDirs::new() {
         let home = user_dirs.home_dir();
         let downloads = home.join("Downloads");
         for root in [home.to_path_buf(), downloads] {
-            for path in scan_prefixes(&root, &["actions-runner", "runner"]) {
+            for path in scan_prefixes(&root, &["actions-runner", "runner"], depth) {
                 add_path(path);
             }
         }
     }
 
-    let mut candidates = Vec::new();
-    for path in paths {
-        if !looks_like_runner_install(&path) {
+    // Custom roots are searched without a name filter; the root itself may be a runner.
+    for root in config.settings.scan_paths.iter().map(|path| expand_path(path)) {
+        if !root.is_dir() {
             continue;
         }
-        if config
-            .runners
-            .iter()
-            .any(|runner| PathBuf::from(&runner.install.install_path) == path)
-        {
-            continue;
+        let nested = scan_prefixes(&root, &[], depth);
+        add_path(root);
+        for path in nested {
+            add_path(path);
         }
-        let metadata = parse_runner_metadata(&path);
-        let detected_service = detect_external_service(&path);
-        let (service_present, service_id, service_path) = match detected_service {
-            Some(service) => (true, service.id, service.path),
-            None => (false, None, None),
-        };
-        let last_log_time = last_log_timestamp(&path);
-        candidates.push(DiscoveryCandidate {
-            candidate_id: crate::config::new_runner_id(),
-            install_path: path.to_string_lossy().to_string(),
-            runner_name: metadata.runner_name,
-            labels: metadata.labels,
-            scope: metadata.scope,
-            work_dir: metadata.work_dir,
-            service_present,
-            service_id,
-            service_path,
-            last_log_time,
-        });
     }
 
+    let total = paths.len();
+    on_progress(0, total);
+    let next = AtomicUsize::new(0);
+    let scanned = AtomicUsize::new(0);
+    let workers = thread::available_parallelism()
+        .map(|count| count.get())
+        .unwrap_or(4)
+        .min(SCAN_WORKERS)
+        .min(total.max(1));
+    let mut candidates: Vec<DiscoveryCandidate> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut found = Vec::new();
+                    while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        if let Some(candidate) = inspect_candidate(config, path, include_sizes) {
+                            found.push(candidate);
+                        }
+                        on_progress(scanned.fetch_add(1, Ordering::Relaxed) + 1, total);
+                    }
+                    found
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle.join() {
+                Ok(found) => found,
+                Err(_) => {
+                    warn!("discovery scan worker panicked; its results are dropped");
+                    Vec::new()
+                }
+            })
+            .collect()
+    });
+    candidates.sort_by(|a, b| a.install_path.cmp(&b.install_path));
+
     Ok(candidates)
 }
 
+fn inspect_candidate(
+    config: &Config,
+    path: &Path,
+    include_sizes: bool,
+) -> Option<DiscoveryCandidate> {
+    if !looks_like_runner_install(path) {
+        return None;
+    }
+    if config
+        .runners
+        .iter()
+        .any(|runner| Path::new(&runner.install.install_path) == path)
+    {
+        return None;
+    }
+    let metadata = parse_runner_metadata(path);
+    let detected_service = detect_external_service(path);
+    let (service_present, service_id, service_path) = match detected_service {
+        Some(service) => (true, service.id, service.path),
+        None => (false, None, None),
+    };
+    let last_log_time = last_log_timestamp(path);
+    Some(DiscoveryCandidate {
+        candidate_id: crate::config::new_runner_id(),
+        install_path: path.to_string_lossy().to_string(),
+        runner_name: metadata.runner_name,
+        labels: metadata.labels,
+        scope: metadata.scope,
+        work_dir: metadata.work_dir,
+        service_present,
+        service_id,
+        service_path,
+        last_log_time,
+        size_bytes: include_sizes.then(|| dir_size(path, SIZE_SCAN_DEPTH)),
+    })
+}
+
 pub fn import_candidate(
     config_store: &ConfigStore,
     candidate: &DiscoveryCandidate,
@@ -163,6 +246,14 @@ pub fn import_candidate(
             migration_status: crate::config::MigrationStatus::None,
         },
         runner_version: None,
+        runner_group: None,
+        ephemeral: false,
+        ephemeral_restart: false,
+        jit: false,
+        auto_restart: false,
+        start_on_app_launch: false,
+        version_pinned: false,
+        env: Default::default(),
         pat_alias: config.pat_default_alias.clone(),
         service: RunnerServiceConfig {
             installed: candidate.service_present,
@@ -175,6 +266,18 @@ pub fn import_candidate(
             external_id: candidate.service_id.clone(),
             external_path: candidate.service_path.clone(),
             external_restore: None,
+            system_scope: false,
+            system_user: None,
+            restart_policy: RestartPolicy::Always,
+            restart_sec: default_restart_sec(),
+            start_limit_interval_secs: None,
+            start_limit_burst: None,
+            memory_max: None,
+            cpu_quota: None,
+            keep_alive: KeepAlive::Always,
+            throttle_interval_secs: None,
+            nice: None,
+            process_type: ProcessType::Default,
         },
         created_at: now_iso8601(),
         last_seen_at: candidate.last_log_time.clone(),
@@ -197,7 +300,8 @@ pub fn import_candidate(
     }
 
     if options.move_install {
-        let moved = move_install(config_store, &imported.runner_id, None)?;
+        let verify_hashes = options.verify_hashes.unwrap_or(options.verify_after_move);
+        let moved = move_install(config_store, &imported.runner_id, None, verify_hashes)?;
         imported = moved;
     }
 
@@ -227,26 +331,68 @@ pub fn migrate_external_service(
             profile.service.external_path = None;
             Ok(())
         }
+        ServiceMigrationStrategy::RemoveExternal => remove_external_artifacts(profile),
     }
 }
 
+/// Undoes `ReplaceWithRunnerbuddy`: drops the managed service and brings back the
+/// external one saved in `external_restore`.
+pub fn restore_external_service(profile: &mut RunnerProfile) -> Result<(), Error> {
+    let restore = profile
+        .service
+        .external_restore
+        .clone()
+        .ok_or_else(|| Error::Service("no external service saved to restore".into()))?;
+    let original = profile.clone();
+    let managed_installed =
+        profile.service.provider == ServiceProvider::Runnerbuddy && profile.service.installed;
+    if managed_installed {
+        let _ = service_mgmt::stop(profile);
+        service_mgmt::uninstall(profile)?;
+    }
+    profile.service.provider = ServiceProvider::External;
+    profile.service.external_id = restore.id;
+    profile.service.external_path = restore.path;
+    if let Err(err) = service_mgmt::external_enable(profile) {
+        *profile = original;
+        if managed_installed {
+            if let Err(reinstall_err) = service_mgmt::install(profile) {
+                warn!(
+                    "failed to reinstall managed service for {} after restore failed: {reinstall_err}",
+                    profile.runner_id
+                );
+            }
+        }
+        return Err(err);
+    }
+    profile.service.installed = true;
+    profile.service.run_on_boot = true;
+    profile.service.external_restore = None;
+    Ok(())
+}
+
 pub fn remove_external_artifacts(profile: &mut RunnerProfile) -> Result<(), Error> {
     if profile.service.provider != ServiceProvider::External {
         return Ok(());
     }
     service_mgmt::external_remove_artifacts(profile)?;
+    clear_external_service(profile);
+    Ok(())
+}
+
+pub(crate) fn clear_external_service(profile: &mut RunnerProfile) {
     profile.service.installed = false;
     profile.service.run_on_boot = false;
     profile.service.provider = ServiceProvider::Unknown;
     profile.service.external_id = None;
     profile.service.external_path = None;
-    Ok(())
 }
 
 pub fn move_install(
     config_store: &ConfigStore,
     runner_id: &str,
     destination: Option<String>,
+    verify_hashes: bool,
 ) -> Result<RunnerProfile, Error> {
     let config = config_store.get();
     let profile = crate::config::find_runner(&config, runner_id)?;
@@ -262,39 +408,99 @@ pub fn move_install(
         return Err(Error::Runner("destination already exists".into()));
     }
     info!("Moving runner install {runner_id} -> {:?}", dest_path);
-    copy_dir_recursive(&src_path, &dest_path)?;
-    verify_copy(&src_path, &dest_path)?;
+    if let Some(parent) = dest_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // An external service still points at the source, so it must stay in place.
+    let renamed = profile.service.provider != ServiceProvider::External
+        && fs::rename(&src_path, &dest_path).is_ok();
+    let result = finish_move(
+        config_store,
+        &profile,
+        &src_path,
+        &dest_path,
+        renamed,
+        verify_hashes,
+    );
+    if result.is_err() {
+        if renamed {
+            if let Err(err) = fs::rename(&dest_path, &src_path) {
+                warn!("failed to move {:?} back to {:?}: {err}", dest_path, src_path);
+            }
+        } else if dest_path.exists() {
+            if let Err(err) = fs::remove_dir_all(&dest_path) {
+                warn!("failed to remove partial copy at {:?}: {err}", dest_path);
+            }
+        }
+    }
+    result
+}
 
-    let updated_profile = config_store.update_runner(runner_id, |runner| {
+/// Everything after the files are in place; on error the caller undoes the move
+/// and the profile is left as it was.
+fn finish_move(
+    config_store: &ConfigStore,
+    profile: &RunnerProfile,
+    src_path: &Path,
+    dest_path: &Path,
+    renamed: bool,
+    verify_hashes: bool,
+) -> Result<RunnerProfile, Error> {
+    if !renamed {
+        copy_dir_recursive(src_path, dest_path)?;
+        verify_copy(src_path, dest_path, verify_hashes)?;
+    }
+    // After a rename there is no original left to roll back to or delete.
+    let adopted_from_path = (!renamed).then(|| src_path.to_string_lossy().to_string());
+    let updated_profile = config_store.update_runner(&profile.runner_id, |runner| {
         runner.install.mode = InstallMode::Managed;
         runner.install.install_path = dest_path.to_string_lossy().to_string();
-        runner.install.adopted_from_path = Some(src_path.to_string_lossy().to_string());
+        runner.install.adopted_from_path = adopted_from_path;
         runner.install.migration_status = crate::config::MigrationStatus::Moved;
     })?;
 
     if updated_profile.service.provider == ServiceProvider::Runnerbuddy {
-        service_mgmt::install(&updated_profile)?;
+        if let Err(err) = service_mgmt::install(&updated_profile) {
+            let original = profile.install.clone();
+            config_store.update_runner(&profile.runner_id, |runner| runner.install = original)?;
+            return Err(err);
+        }
     }
 
     Ok(updated_profile)
 }
 
-fn scan_prefixes(root: &Path, prefixes: &[&str]) -> Vec<PathBuf> {
+/// Directories under `root` (up to `depth` levels) whose name starts with one of
+/// `prefixes`; an empty prefix list matches every directory.
+fn scan_prefixes(root: &Path, prefixes: &[&str], depth: u32) -> Vec<PathBuf> {
     let mut results = Vec::new();
-    if let Ok(entries) = fs::read_dir(root) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
-            }
-            let name = entry.file_name();
-            let name = name.to_string_lossy();
-            if prefixes.iter().any(|prefix| name.starts_with(prefix)) {
-                results.push(path);
-            }
+    collect_prefixed_dirs(root, prefixes, depth, &mut results);
+    results
+}
+
+fn collect_prefixed_dirs(root: &Path, prefixes: &[&str], depth: u32, results: &mut Vec<PathBuf>) {
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if prefixes.is_empty() || prefixes.iter().any(|prefix| name.starts_with(prefix)) {
+            results.push(path.clone());
+        }
+        // Don't follow symlinked or hidden directories while descending.
+        let is_real_dir = entry.file_type().map(|kind| kind.is_dir()).unwrap_or(false);
+        if depth > 1 && is_real_dir && !name.starts_with('.') {
+            collect_prefixed_dirs(&path, prefixes, depth - 1, results);
         }
     }
-    results
 }
 
 pub fn looks_like_runner_install(path: &Path) -> bool {
@@ -541,20 +747,41 @@ fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), Error> {
         let path = entry.path();
         let dest = dst.join(entry.file_name());
         let file_type = entry.file_type()?;
-        if file_type.is_dir() {
+        if file_type.is_symlink() {
+            copy_symlink(&path, &dest)?;
+        } else if file_type.is_dir() {
             copy_dir_recursive(&path, &dest)?;
         } else if file_type.is_file() {
             fs::copy(&path, &dest)?;
+            fs::set_permissions(&dest, fs::metadata(&path)?.permissions())?;
+        }
+    }
+    Ok(())
+}
+
+/// Recreates the link itself (relative targets stay relative) rather than
+/// copying what it points at.
+fn copy_symlink(src: &Path, dst: &Path) -> Result<(), Error> {
+    let target = fs::read_link(src)?;
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&target, dst)?;
+    #[cfg(windows)]
+    {
+        let points_to_dir = fs::metadata(src).map(|meta| meta.is_dir()).unwrap_or(false);
+        if points_to_dir {
+            std::os::windows::fs::symlink_dir(&target, dst)?;
+        } else {
+            std::os::windows::fs::symlink_file(&target, dst)?;
         }
     }
     Ok(())
 }
 
-fn verify_copy(src: &Path, dst: &Path) -> Result<(), Error> {
+fn verify_copy(src: &Path, dst: &Path, verify_hashes: bool) -> Result<(), Error> {
     let checks = if cfg!(target_os = "windows") {
-        vec!["config.cmd", "run.cmd", ".runner"]
+        vec!["config.cmd", "run.cmd", ".runner", ".env"]
     } else {
-        vec!["config.sh", "run.sh", ".runner"]
+        vec!["config.sh", "run.sh", ".runner", ".env"]
     };
     for file in checks {
         let src_file = src.join(file);
@@ -570,10 +797,55 @@ fn verify_copy(src: &Path, dst: &Path) -> Result<(), Error> {
         if src_size != dst_size {
             return Err(Error::Runner(format!("size mismatch for {file}")));
         }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let src_mode = fs::metadata(&src_file)?.permissions().mode();
+            let dst_mode = fs::metadata(&dst_file)?.permissions().mode();
+            if src_mode & 0o111 != 0 && dst_mode & 0o111 == 0 {
+                return Err(Error::Runner(format!("{file} lost its executable bit after copy")));
+            }
+        }
+    }
+    if verify_hashes {
+        verify_tree_hashes(src, dst, Path::new(""))?;
+    }
+    Ok(())
+}
+
+fn verify_tree_hashes(src: &Path, dst: &Path, relative: &Path) -> Result<(), Error> {
+    for entry in fs::read_dir(src.join(relative))? {
+        let entry = entry?;
+        let relative = relative.join(entry.file_name());
+        let dst_path = dst.join(&relative);
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            if fs::read_link(&dst_path).ok() != Some(fs::read_link(entry.path())?) {
+                return Err(Error::Runner(format!(
+                    "symlink mismatch for {} after copy",
+                    relative.display()
+                )));
+            }
+        } else if file_type.is_dir() {
+            verify_tree_hashes(src, dst, &relative)?;
+        } else if file_type.is_file()
+            && (!dst_path.is_file() || file_sha256(&entry.path())? != file_sha256(&dst_path)?)
+        {
+            return Err(Error::Runner(format!(
+                "content mismatch for {} after copy",
+                relative.display()
+            )));
+        }
     }
     Ok(())
 }
 
+fn file_sha256(path: &Path) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
 #[cfg(target_os = "macos")]
 fn parse_launchd_label_for_run_script(plist_path: &Path, run_script: &str) -> Option<String> {
     let plist = plist::Value::from_file(plist_path).ok()?;
@@ -701,8 +973,196 @@ fn detect_external_service(_install_path: &Path) -> Option<ExternalServiceInfo>
     None
 }
 
+pub fn remove_orphaned_service(service_id: &str) -> Result<(), Error> {
+    let orphan = scan_orphaned_services()
+        .into_iter()
+        .find(|orphan| orphan.service_id == service_id)
+        .ok_or_else(|| Error::Service(format!("no orphaned service named {service_id}")))?;
+    info!(
+        "Removing orphaned service {} (missing {})",
+        orphan.service_id, orphan.missing_path
+    );
+    service_mgmt::remove_orphaned_service(
+        &orphan.service_id,
+        orphan.artifact_path.as_deref().map(Path::new),
+    )
+}
+
+/// Picks the runner entry script (`run.sh`, or `runsvc.sh` as written by `svc.sh`)
+/// out of a service's command line.
+#[cfg(any(target_os = "macos", target_os = "linux", test))]
+fn runner_script_arg<'a>(args: impl IntoIterator<Item = &'a str>) -> Option<PathBuf> {
+    args.into_iter()
+        .map(|arg| arg.trim_matches(|c| c == '"' || c == '\''))
+        .map(PathBuf::from)
+        .find(|path| {
+            path.is_absolute()
+                && matches!(
+                    path.file_name().and_then(|name| name.to_str()),
+                    Some("run.sh" | "runsvc.sh")
+                )
+        })
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn unit_runner_script(contents: &str) -> Option<PathBuf> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| key.trim() == "ExecStart")
+        .find_map(|(_, value)| {
+            // Strip systemd's `-`, `@`, `+`, `!` and `:` command prefixes.
+            let value = value.trim().trim_start_matches(['-', '@', '+', '!', ':']);
+            runner_script_arg(value.split_whitespace())
+        })
+}
+
+#[cfg(target_os = "linux")]
+pub fn scan_orphaned_services() -> Vec<OrphanedService> {
+    let Some(user_dirs) = directories::UserDirs::new() else {
+        return Vec::new();
+    };
+    let systemd_dir = user_dirs
+        .home_dir()
+        .join(".config")
+        .join("systemd")
+        .join("user");
+    let mut orphans = Vec::new();
+    let Ok(entries) = fs::read_dir(systemd_dir) else {
+        return orphans;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("service") {
+            continue;
+        }
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        let Some(script) = unit_runner_script(&content) else {
+            continue;
+        };
+        if script.exists() {
+            continue;
+        }
+        orphans.push(OrphanedService {
+            service_id: entry.file_name().to_string_lossy().to_string(),
+            artifact_path: Some(path.to_string_lossy().to_string()),
+            missing_path: script.to_string_lossy().to_string(),
+        });
+    }
+    orphans.sort_by(|a, b| a.service_id.cmp(&b.service_id));
+    orphans
+}
+
+#[cfg(target_os = "macos")]
+pub fn scan_orphaned_services() -> Vec<OrphanedService> {
+    let Some(user_dirs) = directories::UserDirs::new() else {
+        return Vec::new();
+    };
+    let launch_agents = user_dirs
+        .home_dir()
+        .join("Library")
+        .join("LaunchAgents");
+    let mut orphans = Vec::new();
+    let Ok(entries) = fs::read_dir(launch_agents) else {
+        return orphans;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("plist") {
+            continue;
+        }
+        let Ok(plist) = plist::Value::from_file(&path) else {
+            continue;
+        };
+        let Some(dict) = plist.as_dictionary() else {
+            continue;
+        };
+        let Some(label) = dict.get("Label").and_then(|value| value.as_string()) else {
+            continue;
+        };
+        let program = dict.get("Program").and_then(|value| value.as_string());
+        let program_args = dict
+            .get("ProgramArguments")
+            .and_then(|value| value.as_array())
+            .map(|array| array.iter().filter_map(|item| item.as_string()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let Some(script) = runner_script_arg(program.into_iter().chain(program_args)) else {
+            continue;
+        };
+        if script.exists() {
+            continue;
+        }
+        orphans.push(OrphanedService {
+            service_id: label.to_string(),
+            artifact_path: Some(path.to_string_lossy().to_string()),
+            missing_path: script.to_string_lossy().to_string(),
+        });
+    }
+    orphans.sort_by(|a, b| a.service_id.cmp(&b.service_id));
+    orphans
+}
+
+#[cfg(target_os = "windows")]
+pub fn scan_orphaned_services() -> Vec<OrphanedService> {
+    let Ok(output) = Command::new("sc")
+        .args(["query", "type=", "service", "state=", "all"])
+        .output()
+    else {
+        return Vec::new();
+    };
+    let listing = String::from_utf8_lossy(&output.stdout);
+    let mut orphans = Vec::new();
+    for name in listing.lines().filter_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        let value = value.trim();
+        (key.trim().eq_ignore_ascii_case("SERVICE_NAME") && value.starts_with("actions.runner."))
+            .then(|| value.to_string())
+    }) {
+        let Ok(config) = Command::new("sc").args(["qc", &name]).output() else {
+            continue;
+        };
+        let config = String::from_utf8_lossy(&config.stdout);
+        let Some(binary) = parse_windows_binary_path(&config) else {
+            continue;
+        };
+        if binary.exists() {
+            continue;
+        }
+        orphans.push(OrphanedService {
+            service_id: name,
+            artifact_path: None,
+            missing_path: binary.to_string_lossy().to_string(),
+        });
+    }
+    orphans.sort_by(|a, b| a.service_id.cmp(&b.service_id));
+    orphans
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+pub fn scan_orphaned_services() -> Vec<OrphanedService> {
+    Vec::new()
+}
+
+/// Executable path from `sc qc` output, e.g.
+/// `BINARY_PATH_NAME   : "C:\actions-runner\bin\RunnerService.exe"`.
+#[cfg(any(target_os = "windows", test))]
+fn parse_windows_binary_path(output: &str) -> Option<PathBuf> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if !key.trim().eq_ignore_ascii_case("BINARY_PATH_NAME") {
+            return None;
+        }
+        let value = value.trim();
+        let path = match value.strip_prefix('"') {
+            Some(rest) => rest.split('"').next()?,
+            None => value.split_whitespace().next()?,
+        };
+        Some(PathBuf::from(path))
+    })
+}
+
 #[cfg(any(target_os = "windows", test))]
-fn parse_windows_service_name(output: &str) -> Option<String> {
+pub(crate) fn parse_windows_service_name(output: &str) -> Option<String> {
     for line in output.lines() {
         let trimmed = line.trim();
         let lower = trimmed.to_ascii_lowercase();
@@ -729,6 +1189,100 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[cfg(unix)]
+    #[test]
+    fn copy_keeps_symlinks_and_executable_bits() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().expect("tempdir");
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("bin")).expect("create bin");
+        fs::write(src.join("run.sh"), "#!/bin/sh\n").expect("write run.sh");
+        fs::set_permissions(src.join("run.sh"), fs::Permissions::from_mode(0o755))
+            .expect("chmod run.sh");
+        fs::write(src.join("bin").join("libfoo.so.1"), "lib").expect("write lib");
+        std::os::unix::fs::symlink("libfoo.so.1", src.join("bin").join("libfoo.so"))
+            .expect("create symlink");
+
+        let dst = dir.path().join("dst");
+        copy_dir_recursive(&src, &dst).expect("copy tree");
+        verify_copy(&src, &dst, true).expect("verify copy");
+
+        let link = dst.join("bin").join("libfoo.so");
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("libfoo.so.1"));
+        assert_eq!(fs::read_to_string(&link).unwrap(), "lib");
+        let mode = fs::metadata(dst.join("run.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn finds_runner_script_in_service_definitions() {
+        let unit = "[Service]\nExecStart=-/home/ci/actions-runner/runsvc.sh --once\n";
+        assert_eq!(
+            unit_runner_script(unit),
+            Some(PathBuf::from("/home/ci/actions-runner/runsvc.sh"))
+        );
+        assert_eq!(
+            unit_runner_script("ExecStart=\"/opt/runner/run.sh\"\n"),
+            Some(PathBuf::from("/opt/runner/run.sh"))
+        );
+        assert!(unit_runner_script("ExecStart=/usr/bin/node server.js\n").is_none());
+        assert_eq!(
+            runner_script_arg(["/bin/bash", "/Users/ci/runner/run.sh"]),
+            Some(PathBuf::from("/Users/ci/runner/run.sh"))
+        );
+
+        let qc = "        BINARY_PATH_NAME   : \"C:\\actions-runner\\bin\\RunnerService.exe\"\n";
+        assert_eq!(
+            parse_windows_binary_path(qc),
+            Some(PathBuf::from("C:\\actions-runner\\bin\\RunnerService.exe"))
+        );
+    }
+
+    #[test]
+    fn scan_prefixes_respects_depth() {
+        let dir = tempdir().expect("tempdir");
+        fs::create_dir_all(dir.path().join("actions-runner")).expect("create top");
+        fs::create_dir_all(dir.path().join("ci").join("runner-2")).expect("create nested");
+        fs::create_dir_all(dir.path().join(".cache").join("runner-3")).expect("create hidden");
+
+        let shallow = scan_prefixes(dir.path(), &["actions-runner", "runner"], 1);
+        assert_eq!(shallow, vec![dir.path().join("actions-runner")]);
+
+        let mut deep = scan_prefixes(dir.path(), &["actions-runner", "runner"], 2);
+        deep.sort();
+        assert_eq!(
+            deep,
+            vec![dir.path().join("actions-runner"), dir.path().join("ci").join("runner-2")]
+        );
+
+        let mut all = scan_prefixes(dir.path(), &[], 2);
+        all.sort();
+        assert_eq!(all.len(), 4);
+    }
+
+    #[test]
+    fn hash_verification_catches_same_size_corruption() {
+        let dir = tempdir().expect("tempdir");
+        let src = dir.path().join("src");
+        fs::create_dir_all(src.join("bin")).expect("create bin");
+        fs::write(src.join(".runner"), "{}").expect("write .runner");
+        fs::write(src.join("bin").join("Runner.Listener.dll"), "abcdef").expect("write dll");
+
+        let dst = dir.path().join("dst");
+        copy_dir_recursive(&src, &dst).expect("copy tree");
+        verify_copy(&src, &dst, true).expect("verify identical copy");
+
+        fs::write(dst.join("bin").join("Runner.Listener.dll"), "abcxyz").expect("corrupt dll");
+        verify_copy(&src, &dst, false).expect("size-only check passes");
+        let err = verify_copy(&src, &dst, true).expect_err("hash check fails");
+        assert!(err.to_string().contains("Runner.Listener.dll"));
+
+        fs::remove_file(dst.join("bin").join("Runner.Listener.dll")).expect("remove dll");
+        assert!(verify_copy(&src, &dst, true).is_err());
+    }
+
     #[test]
     fn scope_from_repo_url() {
         let scope = scope_from_url("https://github.com/org/repo").expect("scope");
@@ -1,9 +1,13 @@
 mod config;
+mod diagnostics;
 mod discovery;
 mod errors;
 mod github_api;
+mod http;
 mod logging;
 mod logs;
+mod metrics;
+mod notifications;
 mod runner_mgmt;
 mod secrets;
 mod service_mgmt;
@@ -12,25 +16,27 @@ mod util;
 
 use crate::config::{
     default_install_path, default_runner_labels, default_work_dir, now_iso8601, AdoptionDefault,
-    InstallMode, OnboardingConfig, RunnerProfile, RunnerScope, SettingsConfig,
+    Config, InstallMode, LogFormat, OnboardingConfig, RunnerProfile, RunnerScope, SettingsConfig,
 };
 use crate::errors::{AppError, AppResult, Error};
 use crate::service_mgmt::ServiceStatus;
-use crate::state::{AppSnapshot, AppState, RunnerStatus, RuntimeState};
+use crate::state::{AppSnapshot, AppState, CurrentJob, RunnerStatus, RuntimeState};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{
-    menu::MenuBuilder,
-    menu::MenuItem,
+    menu::{Menu, MenuBuilder, MenuItem, SubmenuBuilder},
     tray::TrayIconBuilder,
     AppHandle,
     Emitter,
     Manager,
     State,
 };
+use tauri_plugin_opener::OpenerExt;
 use tracing::{error, info, warn};
 
 fn now_ts() -> u64 {
@@ -41,6 +47,18 @@ fn now_ts() -> u64 {
 }
 
 const LAST_SEEN_DEBOUNCE_SECS: u64 = 60;
+// GitHub-hosted jobs are capped at 6 hours; self-hosted jobs rarely run longer.
+const DRAIN_TIMEOUT_SECS: u64 = 6 * 60 * 60;
+const DRAIN_POLL_SECS: u64 = 5;
+const EPHEMERAL_POLL_SECS: u64 = 5;
+// Polling the UI at a few Hz should not fork systemctl/launchctl every time.
+const LOG_FOLLOW_POLL: Duration = Duration::from_millis(500);
+const SERVICE_STATUS_TTL: Duration = Duration::from_secs(2);
+const AUTO_RESTART_MAX_ATTEMPTS: u32 = 5;
+const AUTO_RESTART_BASE_DELAY_SECS: u64 = 5;
+const AUTO_RESTART_MAX_DELAY_SECS: u64 = 300;
+// A runner that stays up this long after a restart is considered healthy again.
+const AUTO_RESTART_STABLE_SECS: u64 = 600;
 
 #[derive(serde::Serialize, Clone)]
 struct RunnerStatusPayload {
@@ -48,6 +66,40 @@ struct RunnerStatusPayload {
     status: RunnerStatus,
     pid: Option<u32>,
     last_heartbeat: Option<u64>,
+    current_job: Option<CurrentJob>,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ServiceStatusPayload {
+    runner_id: String,
+    status: ServiceStatus,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct LogLinePayload {
+    follow_id: String,
+    runner_id: String,
+    source: String,
+    line: logs::LogLine,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct QuitRequestedPayload {
+    /// Runners running as RunnerBuddy child processes, which die with the app.
+    runner_ids: Vec<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct DiscoveryProgressPayload {
+    scanned: usize,
+    total: usize,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct DrainPayload {
+    runner_id: String,
+    phase: String,
+    elapsed_secs: u64,
 }
 
 #[derive(serde::Serialize)]
@@ -56,6 +108,135 @@ struct VerifyResult {
     reason: Option<String>,
 }
 
+#[derive(serde::Serialize, Default)]
+struct StatusCounts {
+    offline: usize,
+    idle: usize,
+    running: usize,
+}
+
+#[derive(serde::Serialize, Default)]
+struct ProviderCounts {
+    runnerbuddy: usize,
+    external: usize,
+    unknown: usize,
+}
+
+#[derive(serde::Serialize)]
+struct DashboardSummary {
+    total: usize,
+    by_status: StatusCounts,
+    by_provider: ProviderCounts,
+    /// Runners older than `latest_version`; `None` until an update check has run.
+    outdated: Option<usize>,
+    latest_version: Option<String>,
+    selected_runner_id: Option<String>,
+    /// Offline runners whose last start or run ended in an error.
+    errored: usize,
+    /// Jobs completed across all runners, as counted from their diag logs.
+    jobs_completed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrayHealth {
+    Offline,
+    Idle,
+    Running,
+    Error,
+}
+
+impl TrayHealth {
+    /// Badge drawn over the app icon; `None` greys the whole icon out.
+    fn badge_color(self) -> Option<[u8; 3]> {
+        match self {
+            TrayHealth::Offline => None,
+            TrayHealth::Idle => Some([34, 197, 94]),
+            TrayHealth::Running => Some([59, 130, 246]),
+            TrayHealth::Error => Some([239, 68, 68]),
+        }
+    }
+}
+
+impl DashboardSummary {
+    fn health(&self) -> TrayHealth {
+        if self.errored > 0 {
+            TrayHealth::Error
+        } else if self.by_status.running > 0 {
+            TrayHealth::Running
+        } else if self.by_status.idle > 0 {
+            TrayHealth::Idle
+        } else {
+            TrayHealth::Offline
+        }
+    }
+
+    fn tooltip(&self) -> String {
+        if self.total == 0 {
+            return "RunnerBuddy: no runners".to_string();
+        }
+        format!(
+            "RunnerBuddy: {} running, {} idle, {} offline",
+            self.by_status.running, self.by_status.idle, self.by_status.offline
+        )
+    }
+}
+
+#[derive(serde::Serialize)]
+struct BulkOutcome {
+    ok: bool,
+    /// True when the runner was already in the requested state.
+    skipped: bool,
+    error: Option<String>,
+}
+
+impl BulkOutcome {
+    fn from_result<T>(result: AppResult<T>) -> Self {
+        match result {
+            Ok(_) => Self {
+                ok: true,
+                skipped: false,
+                error: None,
+            },
+            Err(err) => Self {
+                ok: false,
+                skipped: false,
+                error: Some(err.to_string()),
+            },
+        }
+    }
+
+    fn skipped() -> Self {
+        Self {
+            ok: true,
+            skipped: true,
+            error: None,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ConfigImportResult {
+    imported_runners: usize,
+    /// Aliases used by runners that have no credential on this machine yet.
+    missing_aliases: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+struct AliasInfo {
+    alias: String,
+    auth_method: config::AuthMethod,
+    stored: bool,
+    is_default: bool,
+    runner_ids: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+struct PatCheckResult {
+    ok: bool,
+    granted: Vec<String>,
+    missing: Vec<String>,
+}
+
 #[derive(serde::Serialize)]
 struct SettingsSnapshot {
     onboarding: OnboardingConfig,
@@ -76,6 +257,34 @@ struct SettingsPatch {
     auto_updates_enabled: Option<bool>,
     auto_check_updates_on_launch: Option<bool>,
     adoption_default: Option<AdoptionDefault>,
+    github_base_url: Option<String>,
+    api_base_url: Option<String>,
+    proxy_url: Option<String>,
+    ca_bundle_path: Option<String>,
+    danger_accept_invalid_certs: Option<bool>,
+    connect_timeout_secs: Option<u64>,
+    request_timeout_secs: Option<u64>,
+    download_timeout_secs: Option<u64>,
+    runner_stop_grace_secs: Option<u64>,
+    runner_log_max_bytes: Option<u64>,
+    status_poll_interval_secs: Option<u64>,
+    scrub_patterns: Option<Vec<String>>,
+    require_checksum: Option<bool>,
+    runner_download_base: Option<String>,
+    runner_download_version: Option<String>,
+    scan_paths: Option<Vec<String>>,
+    scan_depth: Option<u32>,
+    downloads_keep: Option<u32>,
+    max_concurrent_runners: Option<u32>,
+    notify_job_started: Option<bool>,
+    notify_job_finished: Option<bool>,
+    notify_runner_errors: Option<bool>,
+    metrics_enabled: Option<bool>,
+    metrics_port: Option<u16>,
+    log_format: Option<LogFormat>,
+    app_log_max_bytes: Option<u64>,
+    app_log_keep_files: Option<u32>,
+    app_log_keep_days: Option<u32>,
 }
 
 fn update_runtime(
@@ -90,18 +299,143 @@ fn update_runtime(
     let runtime = runtime_map
         .entry(runner_id.to_string())
         .or_insert_with(RuntimeState::default);
+    let previous = notifications::PreviousRuntime::of(runtime);
+    set_runtime_status(runtime, status, pid, last_error);
+    publish_runtime(app, state, runner_id, runtime_map, &previous)
+}
+
+fn set_runtime_status(
+    runtime: &mut RuntimeState,
+    status: RunnerStatus,
+    pid: Option<u32>,
+    last_error: Option<String>,
+) {
     runtime.status = status;
     runtime.pid = pid;
     runtime.last_heartbeat = Some(now_ts());
     runtime.last_error = last_error;
+    if status != RunnerStatus::Running {
+        runtime.current_job = None;
+    }
+}
+
+/// Emits, persists and notifies a runtime change already written to `runtime_map`.
+fn publish_runtime(
+    app: &AppHandle,
+    state: &AppState,
+    runner_id: &str,
+    runtime_map: std::sync::MutexGuard<'_, HashMap<String, RuntimeState>>,
+    previous: &notifications::PreviousRuntime,
+) -> RuntimeState {
+    let runtime = runtime_map.get(runner_id).cloned().unwrap_or_default();
     let payload = RunnerStatusPayload {
         runner_id: runner_id.to_string(),
-        status,
-        pid,
+        status: runtime.status,
+        pid: runtime.pid,
         last_heartbeat: runtime.last_heartbeat,
+        current_job: runtime.current_job.clone(),
     };
     let _ = app.emit("runner_status", payload);
-    runtime.clone()
+    state.persist_runtime(&runtime_map);
+    drop(runtime_map);
+    notify_transition(app, state, runner_id, previous, &runtime);
+    runtime
+}
+
+/// What the status monitor saw for one runner on a poll.
+struct Observation {
+    status: RunnerStatus,
+    pid: Option<u32>,
+    job: Option<CurrentJob>,
+    crash_loop: Option<String>,
+}
+
+/// Folds a monitor observation into `runtime_map`. When anything changed, returns
+/// the runtime as it was before, taken before the job is overwritten so a
+/// "Job finished" notification still knows which job it was. A crash-loop error
+/// is cleared as soon as the loop is over.
+fn apply_observation(
+    runtime_map: &mut HashMap<String, RuntimeState>,
+    runner_id: &str,
+    observation: Observation,
+) -> Option<notifications::PreviousRuntime> {
+    let Observation {
+        status,
+        pid,
+        job,
+        crash_loop,
+    } = observation;
+    let changed = match runtime_map.get(runner_id) {
+        Some(current) => {
+            current.status != status
+                || current.pid != pid
+                || current.current_job != job
+                || (crash_loop.is_some() && current.last_error != crash_loop)
+                || (crash_loop.is_none()
+                    && current
+                        .last_error
+                        .as_deref()
+                        .is_some_and(service_mgmt::is_crash_loop_error))
+        }
+        None => status != RunnerStatus::Offline || crash_loop.is_some(),
+    };
+    if !changed {
+        return None;
+    }
+    let runtime = runtime_map.entry(runner_id.to_string()).or_default();
+    let previous = notifications::PreviousRuntime::of(runtime);
+    runtime.current_job = job;
+    set_runtime_status(runtime, status, pid, crash_loop);
+    Some(previous)
+}
+
+fn notify_transition(
+    app: &AppHandle,
+    state: &AppState,
+    runner_id: &str,
+    previous: &notifications::PreviousRuntime,
+    current: &RuntimeState,
+) {
+    let config = state.config.get();
+    let Some(runner) = config.runners.iter().find(|runner| runner.runner_id == runner_id) else {
+        return;
+    };
+    let now = std::time::Instant::now();
+    for notification in notifications::transition_notifications(&runner.display_name, previous, current) {
+        if !notification.kind.enabled(&config.settings) {
+            continue;
+        }
+        let allowed = state
+            .notify_limiter
+            .lock()
+            .expect("notify limiter poisoned")
+            .allow(runner_id, notification.kind, now);
+        if allowed {
+            notifications::show(app, &notification);
+        }
+    }
+}
+
+/// Aligns the runtime restored from disk with what is actually running now,
+/// keeping `last_error` and `last_heartbeat` from the previous session.
+fn reconcile_runtime(state: &AppState) {
+    let config = state.config.get();
+    let observed: Vec<(String, RunnerStatus, Option<u32>)> = config
+        .runners
+        .iter()
+        .map(|runner| {
+            let (status, pid) = compute_runner_status(state, runner);
+            (runner.runner_id.clone(), status, pid)
+        })
+        .collect();
+    let mut runtime_map = state.runtime.lock().expect("runtime mutex poisoned");
+    runtime_map.retain(|runner_id, _| observed.iter().any(|(id, _, _)| id == runner_id));
+    for (runner_id, status, pid) in observed {
+        let runtime = runtime_map.entry(runner_id).or_default();
+        runtime.status = status;
+        runtime.pid = pid;
+    }
+    state.persist_runtime(&runtime_map);
 }
 
 fn external_conflict_message(profile: &RunnerProfile, status: &ServiceStatus) -> Option<String> {
@@ -146,16 +480,56 @@ fn ensure_no_external_conflict(profile: &RunnerProfile) -> Result<(), AppError>
     Ok(())
 }
 
-fn start_runner_control(
+fn concurrency_limit_error(running: usize, max: u32) -> Option<Error> {
+    if max == 0 || running < max as usize {
+        return None;
+    }
+    Some(Error::Runner(format!(
+        "concurrent runner limit reached ({running} of {max} running); stop a runner or raise the limit in settings"
+    )))
+}
+
+/// Refuses to start `runner_id` when `max_concurrent_runners` other runners, whether
+/// child processes or services, are already running. `started` counts runners the
+/// caller just started, whose status may still read offline.
+fn ensure_runner_capacity(
+    state: &AppState,
+    runner_id: &str,
+    started: &BTreeSet<String>,
+) -> Result<(), Error> {
+    let config = state.config.get();
+    let max = config.settings.max_concurrent_runners;
+    if max == 0 {
+        return Ok(());
+    }
+    let running = config
+        .runners
+        .iter()
+        .filter(|runner| runner.runner_id != runner_id)
+        .filter(|runner| {
+            started.contains(&runner.runner_id)
+                || compute_runner_status(state, runner).0 != RunnerStatus::Offline
+        })
+        .count();
+    match concurrency_limit_error(running, max) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+async fn start_runner_control(
     app: &AppHandle,
     state: &AppState,
     runner_id: &str,
 ) -> AppResult<RuntimeState> {
     let profile = config::find_runner(&state.config.get(), runner_id).map_err(AppError::from)?;
+    ensure_runner_capacity(state, runner_id, &BTreeSet::new()).map_err(AppError::from)?;
+    clear_restart_backoff(state, runner_id);
     if profile.service.provider == crate::config::ServiceProvider::Runnerbuddy
         && profile.service.installed
     {
         service_mgmt::start(&profile).map_err(AppError::from)?;
+        invalidate_service_status(state, runner_id);
         info!("Service start requested for {runner_id}");
         return Ok(update_runtime(
             app,
@@ -172,9 +546,18 @@ fn start_runner_control(
             "external service is managing this runner; start it externally or replace the service",
         ));
     }
-    let pid = runner_mgmt::start_runner(&state.config, runner_id, &state.runner_children)
+    state
+        .runner_exits
+        .lock()
+        .expect("runner exits mutex poisoned")
+        .remove(runner_id);
+    let pid = launch_runner(state, &profile)
+        .await
         .map_err(AppError::from)?;
     info!("Runner {runner_id} started with pid {pid}");
+    if profile.ephemeral {
+        watch_ephemeral_runner(app.clone(), runner_id.to_string());
+    }
     Ok(update_runtime(
         app,
         state,
@@ -185,16 +568,155 @@ fn start_runner_control(
     ))
 }
 
-fn stop_runner_control(
+/// Starts runners flagged `start_on_app_launch` that no service manages. Runs once
+/// after the runtime state has been reconciled at launch.
+async fn start_runners_on_launch(app: &AppHandle, state: &AppState) {
+    let config = state.config.get();
+    for runner in config.runners.iter().filter(|runner| runner.start_on_app_launch) {
+        let runner_id = runner.runner_id.as_str();
+        if runner.service.installed
+            || runner.service.provider == crate::config::ServiceProvider::External
+        {
+            info!("Skipping launch start for {runner_id}: managed by a service");
+            continue;
+        }
+        if let Err(err) = ensure_no_external_conflict(runner) {
+            warn!("Skipping launch start for {runner_id}: {err}");
+            continue;
+        }
+        if compute_runner_status(state, runner).0 != RunnerStatus::Offline {
+            continue;
+        }
+        match start_runner_control(app, state, runner_id).await {
+            Ok(_) => info!("Runner {runner_id} started on app launch"),
+            Err(err) => {
+                error!("Failed to start runner {runner_id} on app launch: {err}");
+                update_runtime(
+                    app,
+                    state,
+                    runner_id,
+                    RunnerStatus::Offline,
+                    None,
+                    Some(err.to_string()),
+                );
+            }
+        }
+    }
+}
+
+// Ephemeral runners deregister and exit after one job. A clean exit is expected, and
+// with `ephemeral_restart` the runner is registered again and relaunched.
+fn watch_ephemeral_runner(app: AppHandle, runner_id: String) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(EPHEMERAL_POLL_SECS)).await;
+            let state = app.state::<AppState>();
+            if check_runner_process(&state, &runner_id).0 {
+                continue;
+            }
+            // No recorded exit means the runner was stopped (or restarted) deliberately.
+            let Some(exit) = state
+                .runner_exits
+                .lock()
+                .expect("runner exits mutex poisoned")
+                .remove(&runner_id)
+            else {
+                return;
+            };
+            if !exit.success() {
+                warn!("Ephemeral runner {runner_id} exited with {exit}");
+                update_runtime(
+                    &app,
+                    &state,
+                    &runner_id,
+                    RunnerStatus::Offline,
+                    None,
+                    Some(format!("runner exited with {exit}")),
+                );
+                return;
+            }
+            let profile = match config::find_runner(&state.config.get(), &runner_id) {
+                Ok(profile) => profile,
+                Err(_) => return,
+            };
+            if !profile.ephemeral_restart {
+                info!("Ephemeral runner {runner_id} finished its job");
+                update_runtime(&app, &state, &runner_id, RunnerStatus::Offline, None, None);
+                return;
+            }
+            if let Err(err) = restart_ephemeral_runner(&app, &state, &profile).await {
+                error!("Failed to relaunch ephemeral runner {runner_id}: {err}");
+                update_runtime(
+                    &app,
+                    &state,
+                    &runner_id,
+                    RunnerStatus::Offline,
+                    None,
+                    Some(err.to_string()),
+                );
+                return;
+            }
+            let (_, pid) = check_runner_process(&state, &runner_id);
+            update_runtime(&app, &state, &runner_id, RunnerStatus::Idle, pid, None);
+        }
+    });
+}
+
+async fn launch_runner(state: &AppState, profile: &RunnerProfile) -> Result<u32, Error> {
+    if profile.jit {
+        let jit = runner_mgmt::request_jit_config(&state.config, &profile.runner_id).await?;
+        return runner_mgmt::start_runner_jit(
+            &state.config,
+            &profile.runner_id,
+            &state.runner_children,
+            &jit.encoded_jit_config,
+        );
+    }
+    runner_mgmt::start_runner(&state.config, &profile.runner_id, &state.runner_children)
+}
+
+async fn restart_ephemeral_runner(
+    app: &AppHandle,
+    state: &AppState,
+    profile: &RunnerProfile,
+) -> Result<(), Error> {
+    // JIT runners register themselves on launch; others need config.sh again.
+    if !profile.jit {
+        let options = runner_mgmt::ConfigureOptions::from_profile(profile)
+            .ok_or_else(|| Error::Runner("runner scope is missing; cannot re-register".into()))?;
+        runner_mgmt::configure_runner(
+            app,
+            &state.config,
+            &state.registration_tokens,
+            &profile.runner_id,
+            options,
+        )
+        .await?;
+    }
+    let pid = launch_runner(state, profile).await?;
+    info!(
+        "Ephemeral runner {} re-registered and relaunched with pid {pid}",
+        profile.runner_id
+    );
+    Ok(())
+}
+
+fn stop_grace(state: &AppState) -> Duration {
+    Duration::from_secs(state.config.get().settings.runner_stop_grace_secs)
+}
+
+async fn stop_runner_control(
     app: &AppHandle,
     state: &AppState,
     runner_id: &str,
 ) -> AppResult<RuntimeState> {
     let profile = config::find_runner(&state.config.get(), runner_id).map_err(AppError::from)?;
+    clear_restart_backoff(state, runner_id);
     if profile.service.provider == crate::config::ServiceProvider::Runnerbuddy
         && profile.service.installed
     {
         service_mgmt::stop(&profile).map_err(AppError::from)?;
+        invalidate_service_status(state, runner_id);
         info!("Service stop requested for {runner_id}");
         return Ok(update_runtime(
             app,
@@ -211,7 +733,9 @@ fn stop_runner_control(
             "external service is managing this runner; stop it externally or replace the service",
         ));
     }
-    runner_mgmt::stop_runner(runner_id, &state.runner_children).map_err(AppError::from)?;
+    runner_mgmt::stop_runner(runner_id, &state.runner_children, stop_grace(state))
+        .await
+        .map_err(AppError::from)?;
     info!("Runner {runner_id} stopped");
     Ok(update_runtime(
         app,
@@ -245,120 +769,634 @@ fn update_last_seen_if_active(state: &AppState, runner_id: &str, status: RunnerS
     });
 }
 
-fn compute_runner_status(
-    state: &AppState,
-    runner: &RunnerProfile,
-) -> (RunnerStatus, Option<u32>) {
-    let runner_id = runner.runner_id.as_str();
-    let (running, pid) = check_runner_process(state, runner_id);
-    let service_running = match service_mgmt::status(runner) {
-        Ok(status) => status.running,
-        Err(err) => {
-            warn!("service status check failed for {runner_id}: {err}");
-            false
+/// Built from the statuses the monitor last recorded, so it never blocks on probes.
+fn dashboard_summary(state: &AppState) -> DashboardSummary {
+    let config = state.config.get();
+    let runtime = state.runtime.lock().expect("runtime mutex poisoned").clone();
+    let latest_version = state
+        .latest_runner_version
+        .lock()
+        .expect("latest version mutex poisoned")
+        .clone();
+    let mut by_status = StatusCounts::default();
+    let mut by_provider = ProviderCounts::default();
+    let mut errored = 0;
+    let mut jobs_completed = 0;
+    for runner in config.runners.iter() {
+        let current = runtime.get(&runner.runner_id);
+        jobs_completed += current.map_or(0, |runtime| runtime.jobs_completed);
+        let status = current
+            .map(|runtime| runtime.status)
+            .unwrap_or(RunnerStatus::Offline);
+        if status == RunnerStatus::Offline
+            && current.is_some_and(|runtime| runtime.last_error.is_some())
+        {
+            errored += 1;
+        }
+        match status {
+            RunnerStatus::Offline => by_status.offline += 1,
+            RunnerStatus::Idle => by_status.idle += 1,
+            RunnerStatus::Running => by_status.running += 1,
+        }
+        match runner.service.provider {
+            crate::config::ServiceProvider::Runnerbuddy => by_provider.runnerbuddy += 1,
+            crate::config::ServiceProvider::External => by_provider.external += 1,
+            crate::config::ServiceProvider::Unknown => by_provider.unknown += 1,
+        }
+    }
+    let outdated = latest_version.as_deref().map(|latest| {
+        config
+            .runners
+            .iter()
+            .filter(|runner| !runner.version_pinned)
+            .filter_map(|runner| runner.runner_version.as_deref())
+            .filter(|current| runner_mgmt::version_is_newer(latest, current.trim_start_matches('v')))
+            .count()
+    });
+    DashboardSummary {
+        total: config.runners.len(),
+        by_status,
+        by_provider,
+        outdated,
+        latest_version,
+        selected_runner_id: config.selected_runner_id,
+        errored,
+        jobs_completed,
+    }
+}
+
+/// Greys out the icon, or paints a status dot in its bottom-right corner.
+fn paint_status_badge(rgba: &mut [u8], width: u32, height: u32, color: Option<[u8; 3]>) {
+    let Some(color) = color else {
+        for pixel in rgba.chunks_exact_mut(4) {
+            let luma = (u32::from(pixel[0]) * 30 + u32::from(pixel[1]) * 59 + u32::from(pixel[2]) * 11)
+                / 100;
+            let grey = (luma / 2 + 64) as u8;
+            pixel[..3].fill(grey);
         }
+        return;
     };
-    let running = running || service_running;
-    let status = if running {
-        match runner_mgmt::classify_runner_status(&runner_mgmt::runner_log_dir(runner)) {
-            Ok(status) => status,
-            Err(err) => {
-                warn!("runner status classification failed for {runner_id}: {err}");
-                RunnerStatus::Idle
+    let radius = (width.min(height) / 4).max(2) as i64;
+    let center_x = i64::from(width) - radius - 1;
+    let center_y = i64::from(height) - radius - 1;
+    for y in 0..i64::from(height) {
+        for x in 0..i64::from(width) {
+            let (dx, dy) = (x - center_x, y - center_y);
+            if dx * dx + dy * dy > radius * radius {
+                continue;
             }
+            let offset = ((y * i64::from(width) + x) * 4) as usize;
+            rgba[offset..offset + 3].copy_from_slice(&color);
+            rgba[offset + 3] = 255;
         }
-    } else {
-        RunnerStatus::Offline
+    }
+}
+
+fn tray_icon_for(app: &AppHandle, health: TrayHealth) -> Option<tauri::image::Image<'static>> {
+    let base = app.default_window_icon()?;
+    let mut rgba = base.rgba().to_vec();
+    paint_status_badge(&mut rgba, base.width(), base.height(), health.badge_color());
+    Some(tauri::image::Image::new_owned(rgba, base.width(), base.height()))
+}
+
+fn update_tray_icon(app: &AppHandle, health: TrayHealth) {
+    let (Some(tray), Some(icon)) = (app.tray_by_id("runnerbuddy"), tray_icon_for(app, health))
+    else {
+        return;
     };
-    (status, pid)
+    if let Err(err) = tray.set_icon(Some(icon)) {
+        warn!("failed to update tray icon: {err}");
+    }
 }
 
-fn service_status_or_fallback(profile: &RunnerProfile) -> ServiceStatus {
-    match service_mgmt::status(profile) {
-        Ok(status) => status,
-        Err(err) => {
-            warn!(
-                "service status failed for {}: {}",
-                profile.runner_id, err
-            );
-            ServiceStatus {
-                installed: profile.service.installed,
-                running: false,
-                enabled: profile.service.run_on_boot,
-            }
+fn update_tray_tooltip(app: &AppHandle, summary: &DashboardSummary) {
+    if let Some(tray) = app.tray_by_id("runnerbuddy") {
+        if let Err(err) = tray.set_tooltip(Some(summary.tooltip())) {
+            warn!("failed to update tray tooltip: {err}");
         }
     }
 }
 
-fn handle_tray_menu(app: &AppHandle, menu_id: &str) {
-    match menu_id {
-        "open" => {
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.show();
-                let _ = window.set_focus();
+// Single owner of liveness detection: emits `runner_status`/`service_status` only when
+// something changed, so the UI can rely on events instead of polling.
+fn spawn_status_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut last_service: HashMap<String, ServiceStatus> = HashMap::new();
+        let mut crash_loops = service_mgmt::CrashLoopDetector::default();
+        let mut last_tooltip = String::new();
+        reconcile_runtime(&app.state::<AppState>());
+        start_runners_on_launch(&app, &app.state::<AppState>()).await;
+        let mut last_menu = tray_menu_key(&app.state::<AppState>());
+        update_tray_menu(&app, &last_menu);
+        let mut last_health = dashboard_summary(&app.state::<AppState>()).health();
+        update_tray_icon(&app, last_health);
+        loop {
+            let state = app.state::<AppState>();
+            let interval = state.config.get().settings.status_poll_interval_secs.max(1);
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            if state.status_monitor_paused.load(Ordering::Relaxed) {
+                continue;
             }
-        }
-        "start" => {
-            let app_handle = app.clone();
-            tauri::async_runtime::spawn(async move {
-                let state = app_handle.state::<AppState>();
-                let config = state.config.get();
-                let selected = match config.selected_runner_id {
-                    Some(id) => id,
-                    None => {
-                        warn!("tray start requested but no runner selected");
-                        return;
+            let config = state.config.get();
+            for runner in config.runners.iter() {
+                let runner_id = runner.runner_id.as_str();
+                let (mut status, mut pid, mut job) = compute_runner_activity(&state, runner);
+                if status == RunnerStatus::Offline {
+                    // The pre-restart reading is stale once the watchdog relaunches the
+                    // runner; publishing it would flip the runner back to offline.
+                    if run_watchdog(&app, &state, runner).await {
+                        (status, pid, job) = compute_runner_activity(&state, runner);
                     }
+                } else {
+                    clear_restart_backoff_if_stable(&state, runner_id);
+                }
+                update_last_seen_if_active(&state, runner_id, status);
+                record_completed_jobs(&state, runner);
+                if let Err(err) =
+                    runner_mgmt::rotate_runner_logs(runner_id, config.settings.runner_log_max_bytes)
+                {
+                    warn!("failed to rotate logs for runner {runner_id}: {err}");
+                }
+                let service = service_status_or_fallback(&state, runner, false);
+                // A flapping service only shows up as status churn; the restart
+                // counters turn it into an error the user can act on.
+                let crash_loop = service.restarts.and_then(|restarts| {
+                    crash_loops.observe(runner_id, restarts, std::time::Instant::now())
+                });
+                let observation = Observation {
+                    status,
+                    pid,
+                    job,
+                    crash_loop,
                 };
-                match start_runner_control(&app_handle, &state, &selected) {
-                    Ok(_) => info!("Runner {selected} started from tray"),
-                    Err(err) => error!("Runner start from tray failed: {err}"),
-                };
-            });
-        }
-        "stop" => {
-            let app_handle = app.clone();
-            tauri::async_runtime::spawn(async move {
-                let state = app_handle.state::<AppState>();
-                let config = state.config.get();
-                let selected = match config.selected_runner_id {
-                    Some(id) => id,
-                    None => {
-                        warn!("tray stop requested but no runner selected");
-                        return;
+                let mut runtime = state.runtime.lock().expect("runtime mutex poisoned");
+                match apply_observation(&mut runtime, runner_id, observation) {
+                    Some(previous) => {
+                        let current = publish_runtime(&app, &state, runner_id, runtime, &previous);
+                        if let Some(error) = current.last_error.as_deref() {
+                            if previous.error.as_deref() != Some(error) {
+                                warn!("Runner {runner_id}: {error}");
+                            }
+                        }
                     }
-                };
-                match stop_runner_control(&app_handle, &state, &selected) {
-                    Ok(_) => info!("Runner {selected} stopped from tray"),
-                    Err(err) => error!("Runner stop from tray failed: {err}"),
-                };
-            });
-        }
-        "quit" => {
-            app.exit(0);
+                    None => drop(runtime),
+                }
+                if last_service.get(runner_id) != Some(&service) {
+                    last_service.insert(runner_id.to_string(), service.clone());
+                    let _ = app.emit(
+                        "service_status",
+                        ServiceStatusPayload {
+                            runner_id: runner_id.to_string(),
+                            status: service,
+                        },
+                    );
+                }
+            }
+            last_service.retain(|id, _| config.runners.iter().any(|runner| &runner.runner_id == id));
+            crash_loops.retain(|id| config.runners.iter().any(|runner| runner.runner_id == id));
+            let summary = dashboard_summary(&state);
+            if summary.tooltip() != last_tooltip {
+                last_tooltip = summary.tooltip();
+                update_tray_tooltip(&app, &summary);
+            }
+            if summary.health() != last_health {
+                last_health = summary.health();
+                update_tray_icon(&app, last_health);
+            }
+            let menu = tray_menu_key(&state);
+            if menu != last_menu {
+                update_tray_menu(&app, &menu);
+                last_menu = menu;
+            }
         }
-        _ => {}
-    }
+    });
 }
 
-fn setup_tray(app: &AppHandle) -> Result<(), Error> {
+fn auto_restart_delay(attempts: u32) -> Duration {
+    let secs = AUTO_RESTART_BASE_DELAY_SECS.saturating_mul(1 << attempts.min(16));
+    Duration::from_secs(secs.min(AUTO_RESTART_MAX_DELAY_SECS))
+}
+
+// `stop_runner` takes the child out of the map before signalling it, so only exits
+// nobody asked for are recorded in `runner_exits`; user stops never trigger a restart.
+// Returns whether the runner was relaunched.
+async fn run_watchdog(app: &AppHandle, state: &AppState, runner: &RunnerProfile) -> bool {
+    let runner_id = runner.runner_id.as_str();
+    let managed_by_service = runner.service.installed
+        || runner.service.provider == crate::config::ServiceProvider::External;
+    if !runner.auto_restart || runner.ephemeral || managed_by_service {
+        return false;
+    }
+    let exit = state
+        .runner_exits
+        .lock()
+        .expect("runner exits mutex poisoned")
+        .remove(runner_id);
+    let now = std::time::Instant::now();
+    let due = {
+        let mut backoffs = state
+            .restart_backoff
+            .lock()
+            .expect("restart backoff mutex poisoned");
+        if let Some(exit) = exit {
+            let backoff = backoffs.entry(runner_id.to_string()).or_default();
+            if backoff.attempts >= AUTO_RESTART_MAX_ATTEMPTS {
+                backoffs.remove(runner_id);
+                let message = format!(
+                    "runner exited ({exit}) after {AUTO_RESTART_MAX_ATTEMPTS} automatic restarts; giving up"
+                );
+                error!("Runner {runner_id}: {message}");
+                drop(backoffs);
+                update_runtime(app, state, runner_id, RunnerStatus::Offline, None, Some(message));
+                return false;
+            }
+            let delay = auto_restart_delay(backoff.attempts);
+            warn!(
+                "Runner {runner_id} exited unexpectedly ({exit}); restarting in {}s",
+                delay.as_secs()
+            );
+            backoff.next_attempt = Some(now + delay);
+        }
+        match backoffs.get_mut(runner_id) {
+            Some(backoff) if backoff.next_attempt.is_some_and(|at| at <= now) => {
+                backoff.attempts += 1;
+                backoff.next_attempt = None;
+                backoff.last_restart = Some(now);
+                Some(backoff.attempts)
+            }
+            _ => None,
+        }
+    };
+    let Some(attempt) = due else {
+        return false;
+    };
+    if let Err(err) = ensure_runner_capacity(state, runner_id, &BTreeSet::new()) {
+        // Not the runner's fault: retry later without spending an attempt.
+        warn!("Auto-restart of runner {runner_id} skipped: {err}");
+        if let Some(backoff) = state
+            .restart_backoff
+            .lock()
+            .expect("restart backoff mutex poisoned")
+            .get_mut(runner_id)
+        {
+            backoff.attempts = attempt - 1;
+            backoff.next_attempt = Some(now + auto_restart_delay(backoff.attempts));
+        }
+        update_runtime(
+            app,
+            state,
+            runner_id,
+            RunnerStatus::Offline,
+            None,
+            Some(format!("auto-restart skipped: {err}")),
+        );
+        return false;
+    }
+    match launch_runner(state, runner).await {
+        Ok(pid) => {
+            info!("Runner {runner_id} auto-restarted with pid {pid} (attempt {attempt})");
+            update_runtime(app, state, runner_id, RunnerStatus::Idle, Some(pid), None);
+            true
+        }
+        Err(err) => {
+            error!("Auto-restart of runner {runner_id} failed: {err}");
+            if let Some(backoff) = state
+                .restart_backoff
+                .lock()
+                .expect("restart backoff mutex poisoned")
+                .get_mut(runner_id)
+            {
+                backoff.next_attempt = Some(now + auto_restart_delay(attempt));
+            }
+            update_runtime(
+                app,
+                state,
+                runner_id,
+                RunnerStatus::Offline,
+                None,
+                Some(format!("auto-restart failed: {err}")),
+            );
+            false
+        }
+    }
+}
+
+fn clear_restart_backoff_if_stable(state: &AppState, runner_id: &str) {
+    let mut backoffs = state
+        .restart_backoff
+        .lock()
+        .expect("restart backoff mutex poisoned");
+    let stable = backoffs.get(runner_id).is_some_and(|backoff| {
+        backoff.next_attempt.is_none()
+            && backoff
+                .last_restart
+                .is_some_and(|at| at.elapsed() >= Duration::from_secs(AUTO_RESTART_STABLE_SECS))
+    });
+    if stable {
+        backoffs.remove(runner_id);
+    }
+}
+
+/// Adds jobs finished since the last pass to the runner's lifetime count.
+fn record_completed_jobs(state: &AppState, runner: &RunnerProfile) {
+    let runner_id = runner.runner_id.as_str();
+    let cursor = state
+        .runtime
+        .lock()
+        .expect("runtime mutex poisoned")
+        .get(runner_id)
+        .and_then(|runtime| runtime.job_log_cursor.clone());
+    let (completed, next) = match runner_mgmt::count_completed_jobs(
+        &runner_mgmt::runner_log_dir(runner),
+        cursor.as_ref(),
+    ) {
+        Ok(counted) => counted,
+        Err(err) => {
+            warn!("job count failed for {runner_id}: {err}");
+            return;
+        }
+    };
+    if completed == 0 && next == cursor {
+        return;
+    }
+    let mut runtime_map = state.runtime.lock().expect("runtime mutex poisoned");
+    let runtime = runtime_map.entry(runner_id.to_string()).or_default();
+    runtime.jobs_completed += completed;
+    runtime.job_log_cursor = next;
+    state.persist_runtime(&runtime_map);
+}
+
+/// Pushes saved settings into the parts of the app that cache them: the shared
+/// HTTP client, log scrubbing and rotation, and the metrics listener.
+fn apply_runtime_settings(
+    app: &AppHandle,
+    state: &AppState,
+    settings: &config::SettingsConfig,
+) -> AppResult<()> {
+    http::configure(http::NetworkSettings::from_settings(settings));
+    logging::configure_scrub_patterns(&settings.scrub_patterns);
+    logging::configure_log_rotation(logging::LogRotation::from_settings(settings));
+    apply_metrics_settings(app, state).map_err(|err| {
+        AppError::new("config", format!("metrics endpoint failed to start: {err}"))
+    })
+}
+
+/// Starts, stops or rebinds the metrics listener to match the current settings.
+fn apply_metrics_settings(app: &AppHandle, state: &AppState) -> Result<(), Error> {
+    let settings = state.config.get().settings;
+    let mut server = state.metrics_server.lock().expect("metrics server mutex poisoned");
+    let wanted = settings.metrics_enabled.then_some(settings.metrics_port);
+    if server.as_ref().map(|server| server.port) == wanted {
+        return Ok(());
+    }
+    if let Some(current) = server.take() {
+        current.stop();
+    }
+    if let Some(port) = wanted {
+        *server = Some(metrics::start(app.clone(), port)?);
+    }
+    Ok(())
+}
+
+fn clear_restart_backoff(state: &AppState, runner_id: &str) {
+    state
+        .restart_backoff
+        .lock()
+        .expect("restart backoff mutex poisoned")
+        .remove(runner_id);
+}
+
+fn compute_runner_status(
+    state: &AppState,
+    runner: &RunnerProfile,
+) -> (RunnerStatus, Option<u32>) {
+    let (status, pid, _) = compute_runner_activity(state, runner);
+    (status, pid)
+}
+
+fn compute_runner_activity(
+    state: &AppState,
+    runner: &RunnerProfile,
+) -> (RunnerStatus, Option<u32>, Option<CurrentJob>) {
+    let runner_id = runner.runner_id.as_str();
+    let (running, pid) = check_runner_process(state, runner_id);
+    let service_running = match cached_service_status(state, runner, false) {
+        Ok(status) => status.running,
+        Err(err) => {
+            warn!("service status check failed for {runner_id}: {err}");
+            false
+        }
+    };
+    let running = running || service_running;
+    let (status, job) = if running {
+        match runner_mgmt::classify_runner_activity(&runner_mgmt::runner_log_dir(runner)) {
+            Ok(activity) => activity,
+            Err(err) => {
+                warn!("runner status classification failed for {runner_id}: {err}");
+                (RunnerStatus::Idle, None)
+            }
+        }
+    } else {
+        (RunnerStatus::Offline, None)
+    };
+    (status, pid, job)
+}
+
+fn cached_service_status(
+    state: &AppState,
+    profile: &RunnerProfile,
+    force: bool,
+) -> Result<ServiceStatus, Error> {
+    let runner_id = profile.runner_id.as_str();
+    if !force {
+        let cache = state
+            .service_status_cache
+            .lock()
+            .expect("service status cache poisoned");
+        if let Some((checked_at, status)) = cache.get(runner_id) {
+            if checked_at.elapsed() < SERVICE_STATUS_TTL {
+                return Ok(status.clone());
+            }
+        }
+    }
+    let status = service_mgmt::status(profile)?;
+    state
+        .service_status_cache
+        .lock()
+        .expect("service status cache poisoned")
+        .insert(runner_id.to_string(), (std::time::Instant::now(), status.clone()));
+    Ok(status)
+}
+
+fn invalidate_service_status(state: &AppState, runner_id: &str) {
+    state
+        .service_status_cache
+        .lock()
+        .expect("service status cache poisoned")
+        .remove(runner_id);
+}
+
+fn service_status_or_fallback(
+    state: &AppState,
+    profile: &RunnerProfile,
+    force: bool,
+) -> ServiceStatus {
+    match cached_service_status(state, profile, force) {
+        Ok(status) => status,
+        Err(err) => {
+            warn!(
+                "service status failed for {}: {}",
+                profile.runner_id, err
+            );
+            ServiceStatus {
+                installed: profile.service.installed,
+                running: false,
+                enabled: profile.service.run_on_boot,
+                linger: None,
+                launchd_kind: None,
+                restarts: None,
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TrayAction<'a> {
+    Open,
+    Start(&'a str),
+    Stop(&'a str),
+    Quit,
+}
+
+/// Per-runner items carry the runner id after the action, e.g. `start:<runner_id>`.
+fn parse_tray_menu_id(menu_id: &str) -> Option<TrayAction<'_>> {
+    match menu_id {
+        "open" => Some(TrayAction::Open),
+        "quit" => Some(TrayAction::Quit),
+        _ => {
+            let (action, runner_id) = menu_id.split_once(':')?;
+            match action {
+                "start" => Some(TrayAction::Start(runner_id)),
+                "stop" => Some(TrayAction::Stop(runner_id)),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn handle_tray_menu(app: &AppHandle, menu_id: &str) {
+    match parse_tray_menu_id(menu_id) {
+        Some(TrayAction::Open) => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        Some(TrayAction::Start(runner_id)) => {
+            let app_handle = app.clone();
+            let runner_id = runner_id.to_string();
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle.state::<AppState>();
+                match start_runner_control(&app_handle, &state, &runner_id).await {
+                    Ok(_) => info!("Runner {runner_id} started from tray"),
+                    Err(err) => error!("Runner start from tray failed: {err}"),
+                };
+            });
+        }
+        Some(TrayAction::Stop(runner_id)) => {
+            let app_handle = app.clone();
+            let runner_id = runner_id.to_string();
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle.state::<AppState>();
+                match stop_runner_control(&app_handle, &state, &runner_id).await {
+                    Ok(_) => info!("Runner {runner_id} stopped from tray"),
+                    Err(err) => error!("Runner stop from tray failed: {err}"),
+                };
+            });
+        }
+        Some(TrayAction::Quit) => request_quit(app),
+        None => {}
+    }
+}
+
+fn status_label(status: RunnerStatus) -> &'static str {
+    match status {
+        RunnerStatus::Offline => "offline",
+        RunnerStatus::Idle => "idle",
+        RunnerStatus::Running => "running",
+    }
+}
+
+/// Runner names and statuses the tray menu was built from; the menu is rebuilt
+/// when this changes.
+fn tray_menu_key(state: &AppState) -> Vec<(String, String, RunnerStatus)> {
+    let config = state.config.get();
+    let runtime = state.runtime.lock().expect("runtime mutex poisoned");
+    config
+        .runners
+        .iter()
+        .map(|runner| {
+            let status = runtime
+                .get(&runner.runner_id)
+                .map(|runtime| runtime.status)
+                .unwrap_or(RunnerStatus::Offline);
+            (runner.runner_id.clone(), runner.display_name.clone(), status)
+        })
+        .collect()
+}
+
+fn build_tray_menu(
+    app: &AppHandle,
+    runners: &[(String, String, RunnerStatus)],
+) -> Result<Menu<tauri::Wry>, Error> {
+    let menu_error = |err: tauri::Error| Error::Service(err.to_string());
     let open_item = MenuItem::with_id(app, "open", "Open RunnerBuddy", true, None::<&str>)
-        .map_err(|err| Error::Service(err.to_string()))?;
-    let start_item = MenuItem::with_id(app, "start", "Start runner", true, None::<&str>)
-        .map_err(|err| Error::Service(err.to_string()))?;
-    let stop_item = MenuItem::with_id(app, "stop", "Stop runner", true, None::<&str>)
-        .map_err(|err| Error::Service(err.to_string()))?;
+        .map_err(menu_error)?;
     let quit_item = MenuItem::with_id(app, "quit", "Quit RunnerBuddy", true, None::<&str>)
-        .map_err(|err| Error::Service(err.to_string()))?;
+        .map_err(menu_error)?;
 
-    let menu = MenuBuilder::new(app)
-        .item(&open_item)
-        .item(&start_item)
-        .item(&stop_item)
+    let mut builder = MenuBuilder::new(app).item(&open_item);
+    if !runners.is_empty() {
+        builder = builder.separator();
+    }
+    for (runner_id, name, status) in runners {
+        let offline = *status == RunnerStatus::Offline;
+        let submenu = SubmenuBuilder::new(app, format!("{name} — {}", status_label(*status)))
+            .text(format!("start:{runner_id}"), "Start")
+            .text(format!("stop:{runner_id}"), "Stop")
+            .build()
+            .map_err(menu_error)?;
+        for (action, enabled) in [("start", offline), ("stop", !offline)] {
+            if let Some(item) = submenu
+                .get(format!("{action}:{runner_id}").as_str())
+                .and_then(|item| item.as_menuitem().cloned())
+            {
+                item.set_enabled(enabled).map_err(menu_error)?;
+            }
+        }
+        builder = builder.item(&submenu);
+    }
+    builder
         .separator()
         .item(&quit_item)
         .build()
-        .map_err(|err| Error::Service(err.to_string()))?;
+        .map_err(menu_error)
+}
+
+fn update_tray_menu(app: &AppHandle, runners: &[(String, String, RunnerStatus)]) {
+    let Some(tray) = app.tray_by_id("runnerbuddy") else {
+        return;
+    };
+    let result = build_tray_menu(app, runners)
+        .and_then(|menu| tray.set_menu(Some(menu)).map_err(|err| Error::Service(err.to_string())));
+    if let Err(err) = result {
+        warn!("failed to rebuild tray menu: {err}");
+    }
+}
+
+fn setup_tray(app: &AppHandle) -> Result<(), Error> {
+    let state = app.state::<AppState>();
+    let menu = build_tray_menu(app, &tray_menu_key(&state))?;
 
     let icon = app
         .default_window_icon()
@@ -394,11 +1432,288 @@ async fn settings_get(state: State<'_, AppState>) -> AppResult<SettingsSnapshot>
     })
 }
 
+/// Writes the whole config (runner profiles, settings, alias names) as JSON.
+/// Tokens and keys live in the credential store and are never exported.
+#[tauri::command]
+async fn config_export(state: State<'_, AppState>, path: String) -> AppResult<()> {
+    let path = util::expand_path(&path);
+    let data = serde_json::to_string_pretty(&state.config.get())
+        .map_err(Error::from)
+        .map_err(AppError::from)?;
+    std::fs::write(&path, data)
+        .map_err(Error::from)
+        .map_err(AppError::from)?;
+    info!("Config exported to {:?}", path);
+    Ok(())
+}
+
+#[tauri::command]
+async fn config_import(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    merge: bool,
+) -> AppResult<ConfigImportResult> {
+    let path = util::expand_path(&path);
+    let data = std::fs::read_to_string(&path)
+        .map_err(Error::from)
+        .map_err(AppError::from)?;
+    let imported = config::parse_config_export(&data).map_err(AppError::from)?;
+    let mut imported_runners = 0;
+    let updated = state
+        .config
+        .update(|config| imported_runners = config.import(imported, merge))
+        .map_err(AppError::from)?;
+    apply_runtime_settings(&app, &state, &updated.settings)?;
+
+    let mut missing_aliases = Vec::new();
+    let mut discovered = Vec::new();
+    let referenced: BTreeSet<String> =
+        updated.runners.iter().map(|runner| runner.pat_alias.clone()).collect();
+    for alias in referenced {
+        if updated.pat_aliases.contains(&alias) {
+            continue;
+        }
+        if credential_stored(&updated, &alias)? {
+            discovered.push(alias);
+        } else {
+            missing_aliases.push(alias);
+        }
+    }
+    if !discovered.is_empty() {
+        state
+            .config
+            .update(|config| config.pat_aliases.extend(discovered))
+            .map_err(AppError::from)?;
+    }
+    info!(
+        "Config imported from {:?} (merge={merge}): {imported_runners} runner(s), {} alias(es) need credentials",
+        path,
+        missing_aliases.len()
+    );
+    Ok(ConfigImportResult {
+        imported_runners,
+        missing_aliases,
+    })
+}
+
+#[derive(serde::Serialize)]
+struct InventoryRow {
+    runner_id: String,
+    display_name: String,
+    runner_name: String,
+    scope: Option<String>,
+    labels: Vec<String>,
+    install_path: String,
+    provider: crate::config::ServiceProvider,
+    runner_version: Option<String>,
+    version_pinned: bool,
+    status: RunnerStatus,
+    last_seen_at: Option<String>,
+    last_heartbeat: Option<u64>,
+    jobs_completed: u64,
+}
+
+const INVENTORY_CSV_HEADER: &[&str] = &[
+    "runner_id",
+    "display_name",
+    "runner_name",
+    "scope",
+    "labels",
+    "install_path",
+    "provider",
+    "runner_version",
+    "version_pinned",
+    "status",
+    "last_seen_at",
+    "last_heartbeat",
+    "jobs_completed",
+];
+
+fn inventory_rows(
+    config: &config::Config,
+    runtime: &HashMap<String, RuntimeState>,
+) -> Vec<InventoryRow> {
+    config
+        .runners
+        .iter()
+        .map(|runner| {
+            let runtime = runtime.get(&runner.runner_id).cloned().unwrap_or_default();
+            InventoryRow {
+                runner_id: runner.runner_id.clone(),
+                display_name: runner.display_name.clone(),
+                runner_name: runner.runner_name.clone(),
+                scope: runner
+                    .scope
+                    .as_ref()
+                    .map(|scope| scope.url(&config.settings.github_base_url)),
+                labels: runner.labels.clone(),
+                install_path: runner.install.install_path.clone(),
+                provider: runner.service.provider.clone(),
+                runner_version: runner.runner_version.clone(),
+                version_pinned: runner.version_pinned,
+                status: runtime.status,
+                last_seen_at: runner.last_seen_at.clone(),
+                last_heartbeat: runtime.last_heartbeat,
+                jobs_completed: runtime.jobs_completed,
+            }
+        })
+        .collect()
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn serde_label<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn inventory_csv(rows: &[InventoryRow]) -> String {
+    let mut out = INVENTORY_CSV_HEADER.join(",");
+    out.push_str("\r\n");
+    for row in rows {
+        let fields = [
+            row.runner_id.clone(),
+            row.display_name.clone(),
+            row.runner_name.clone(),
+            row.scope.clone().unwrap_or_default(),
+            row.labels.join(","),
+            row.install_path.clone(),
+            serde_label(&row.provider),
+            row.runner_version.clone().unwrap_or_default(),
+            row.version_pinned.to_string(),
+            serde_label(&row.status),
+            row.last_seen_at.clone().unwrap_or_default(),
+            row.last_heartbeat.map(|ts| ts.to_string()).unwrap_or_default(),
+            row.jobs_completed.to_string(),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+#[tauri::command]
+async fn export_inventory(
+    state: State<'_, AppState>,
+    format: String,
+    path: String,
+) -> AppResult<usize> {
+    let path = util::expand_path(&path);
+    let config = state.config.get();
+    let runtime = state.runtime.lock().expect("runtime mutex poisoned").clone();
+    let rows = inventory_rows(&config, &runtime);
+    let data = match format.as_str() {
+        "csv" => inventory_csv(&rows),
+        "json" => serde_json::to_string_pretty(&rows)
+            .map_err(Error::from)
+            .map_err(AppError::from)?,
+        other => {
+            return Err(AppError::new(
+                "config",
+                format!("unsupported inventory format: {other}"),
+            ))
+        }
+    };
+    std::fs::write(&path, data)
+        .map_err(Error::from)
+        .map_err(AppError::from)?;
+    info!("Inventory of {} runner(s) exported to {:?}", rows.len(), path);
+    Ok(rows.len())
+}
+
 #[tauri::command]
 async fn settings_update(
+    app: AppHandle,
     state: State<'_, AppState>,
     patch: SettingsPatch,
 ) -> AppResult<SettingsSnapshot> {
+    let current = state.config.get().settings;
+    let proxy_url = match patch.proxy_url.as_deref() {
+        Some(value) => Some(http::normalize_proxy_url(value).map_err(AppError::from)?),
+        None => None,
+    };
+    for value in [
+        patch.connect_timeout_secs,
+        patch.request_timeout_secs,
+        patch.download_timeout_secs,
+        patch.status_poll_interval_secs,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        if value == 0 {
+            return Err(AppError::new("config", "timeouts and intervals must be at least 1 second"));
+        }
+    }
+    let ca_bundle_path = match patch.ca_bundle_path.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(path) => {
+            http::load_ca_bundle(path).map_err(AppError::from)?;
+            Some(Some(path.to_string()))
+        }
+        None => None,
+    };
+    let runner_download_base = match patch.runner_download_base.as_deref().map(str::trim) {
+        Some("") => Some(None),
+        Some(base) if !base.starts_with("http://") && !base.starts_with("https://") => {
+            return Err(AppError::new(
+                "config",
+                "runner download mirror must be an http(s) URL",
+            ));
+        }
+        Some(base) => Some(Some(base.trim_end_matches('/').to_string())),
+        None => None,
+    };
+    if patch.metrics_port == Some(0) {
+        return Err(AppError::new("config", "metrics port must be between 1 and 65535"));
+    }
+    if matches!(patch.scan_depth, Some(depth) if !(1..=discovery::MAX_SCAN_DEPTH).contains(&depth)) {
+        return Err(AppError::new(
+            "config",
+            format!("scan depth must be between 1 and {}", discovery::MAX_SCAN_DEPTH),
+        ));
+    }
+    let scan_paths = patch.scan_paths.map(|paths| {
+        paths
+            .iter()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty())
+            .collect::<Vec<_>>()
+    });
+    let runner_download_version = patch.runner_download_version.as_deref().map(|value| {
+        let version = value.trim().trim_start_matches('v');
+        (!version.is_empty()).then(|| version.to_string())
+    });
+    let github_base_url = match patch.github_base_url.as_deref() {
+        Some(value) => Some(config::normalize_github_base_url(value).map_err(AppError::from)?),
+        None => None,
+    };
+    let api_base_url = match (patch.api_base_url.as_deref(), github_base_url.as_deref()) {
+        (Some(value), _) if !value.trim().is_empty() => {
+            Some(value.trim().trim_end_matches('/').to_string())
+        }
+        (_, Some(base)) => Some(config::derive_api_base_url(base)),
+        _ => None,
+    };
+    if let Some(api_base) = api_base_url.as_deref() {
+        if api_base != current.api_base_url {
+            github_api::check_api_base(api_base)
+                .await
+                .map_err(AppError::from)?;
+            info!("GitHub API base validated: {api_base}");
+        }
+    }
     let updated = state
         .config
         .update(|config| {
@@ -416,13 +1731,105 @@ async fn settings_update(
             if let Some(value) = patch.adoption_default {
                 config.settings.adoption_default = value;
             }
+            if let Some(value) = github_base_url {
+                config.settings.github_base_url = value;
+            }
+            if let Some(value) = api_base_url {
+                config.settings.api_base_url = value;
+            }
+            if let Some(value) = proxy_url {
+                config.settings.proxy_url = value;
+            }
+            if let Some(value) = ca_bundle_path {
+                config.settings.ca_bundle_path = value;
+            }
+            if let Some(value) = patch.danger_accept_invalid_certs {
+                config.settings.danger_accept_invalid_certs = value;
+            }
+            if let Some(value) = patch.connect_timeout_secs {
+                config.settings.connect_timeout_secs = value;
+            }
+            if let Some(value) = patch.request_timeout_secs {
+                config.settings.request_timeout_secs = value;
+            }
+            if let Some(value) = patch.download_timeout_secs {
+                config.settings.download_timeout_secs = value;
+            }
+            if let Some(value) = patch.runner_stop_grace_secs {
+                config.settings.runner_stop_grace_secs = value;
+            }
+            if let Some(value) = patch.runner_log_max_bytes {
+                config.settings.runner_log_max_bytes = value;
+            }
+            if let Some(value) = patch.status_poll_interval_secs {
+                config.settings.status_poll_interval_secs = value;
+            }
+            if let Some(value) = patch.scrub_patterns {
+                config.settings.scrub_patterns = value;
+            }
+            if let Some(value) = patch.require_checksum {
+                config.settings.require_checksum = value;
+            }
+            if let Some(value) = runner_download_base {
+                config.settings.runner_download_base = value;
+            }
+            if let Some(value) = runner_download_version {
+                config.settings.runner_download_version = value;
+            }
+            if let Some(value) = scan_paths {
+                config.settings.scan_paths = value;
+            }
+            if let Some(value) = patch.scan_depth {
+                config.settings.scan_depth = value;
+            }
+            if let Some(value) = patch.downloads_keep {
+                config.settings.downloads_keep = value;
+            }
+            if let Some(value) = patch.max_concurrent_runners {
+                config.settings.max_concurrent_runners = value;
+            }
+            if let Some(value) = patch.notify_job_started {
+                config.settings.notify_job_started = value;
+            }
+            if let Some(value) = patch.notify_job_finished {
+                config.settings.notify_job_finished = value;
+            }
+            if let Some(value) = patch.notify_runner_errors {
+                config.settings.notify_runner_errors = value;
+            }
+            if let Some(value) = patch.metrics_enabled {
+                config.settings.metrics_enabled = value;
+            }
+            if let Some(value) = patch.metrics_port {
+                config.settings.metrics_port = value;
+            }
+            if let Some(value) = patch.log_format {
+                config.settings.log_format = value;
+            }
+            if let Some(value) = patch.app_log_max_bytes {
+                config.settings.app_log_max_bytes = value;
+            }
+            if let Some(value) = patch.app_log_keep_files {
+                config.settings.app_log_keep_files = value;
+            }
+            if let Some(value) = patch.app_log_keep_days {
+                config.settings.app_log_keep_days = value;
+            }
         })
         .map_err(AppError::from)?;
+    apply_runtime_settings(&app, &state, &updated.settings)?;
     info!(
-        "Settings updated: auto_updates_enabled={}, auto_check_on_launch={}, adoption_default={:?}",
+        "Settings updated: auto_updates_enabled={}, auto_check_on_launch={}, adoption_default={:?}, github_base_url={}, proxy={}",
         updated.settings.auto_updates_enabled,
         updated.settings.auto_check_updates_on_launch,
-        updated.settings.adoption_default
+        updated.settings.adoption_default,
+        updated.settings.github_base_url,
+        updated
+            .settings
+            .proxy_url
+            .as_deref()
+            .map(http::redact_url)
+            .unwrap_or_else(|| "none".to_string())
     );
     Ok(SettingsSnapshot {
         onboarding: updated.onboarding,
@@ -533,6 +1940,14 @@ async fn runners_create_profile(
             migration_status: crate::config::MigrationStatus::None,
         },
         runner_version: None,
+        runner_group: None,
+        ephemeral: false,
+        ephemeral_restart: false,
+        jit: false,
+        auto_restart: false,
+        start_on_app_launch: false,
+        version_pinned: false,
+        env: Default::default(),
         pat_alias: input
             .pat_alias
             .unwrap_or_else(|| config.pat_default_alias.clone()),
@@ -576,6 +1991,39 @@ struct RunnerProfilePatch {
     work_dir: Option<String>,
     scope: Option<RunnerScope>,
     pat_alias: Option<String>,
+    ephemeral_restart: Option<bool>,
+    auto_restart: Option<bool>,
+    start_on_app_launch: Option<bool>,
+    version_pinned: Option<bool>,
+    restart_policy: Option<crate::config::RestartPolicy>,
+    restart_sec: Option<u64>,
+    /// `0` clears the limit and falls back to the systemd default.
+    start_limit_interval_secs: Option<u64>,
+    /// `0` clears the limit and falls back to the systemd default.
+    start_limit_burst: Option<u32>,
+    /// Empty string removes the limit.
+    memory_max: Option<String>,
+    /// Empty string removes the limit.
+    cpu_quota: Option<String>,
+    keep_alive: Option<crate::config::KeepAlive>,
+    /// `0` clears the interval and falls back to the launchd default.
+    throttle_interval_secs: Option<u64>,
+    /// `0` clears it; launchd runs the job at normal priority.
+    nice: Option<i32>,
+    process_type: Option<crate::config::ProcessType>,
+}
+
+/// Trims a unit directive value; empty clears it. Whitespace is rejected so a
+/// value can never spill into another line of the unit file.
+fn unit_directive_value(name: &str, value: &str) -> AppResult<Option<String>> {
+    let value = value.trim();
+    if value.chars().any(|ch| ch.is_whitespace() || ch.is_control()) {
+        return Err(AppError::new(
+            "config",
+            format!("invalid {name} value: {value:?}"),
+        ));
+    }
+    Ok((!value.is_empty()).then(|| value.to_string()))
 }
 
 #[tauri::command]
@@ -584,7 +2032,33 @@ async fn runners_update_profile(
     runner_id: String,
     patch: RunnerProfilePatch,
 ) -> AppResult<RunnerProfile> {
-    state
+    let unit_changed = patch.restart_policy.is_some()
+        || patch.restart_sec.is_some()
+        || patch.start_limit_interval_secs.is_some()
+        || patch.start_limit_burst.is_some()
+        || patch.memory_max.is_some()
+        || patch.cpu_quota.is_some()
+        || patch.keep_alive.is_some()
+        || patch.throttle_interval_secs.is_some()
+        || patch.nice.is_some()
+        || patch.process_type.is_some();
+    if let Some(nice) = patch.nice.filter(|nice| !(-20..=19).contains(nice)) {
+        return Err(AppError::new(
+            "config",
+            format!("invalid Nice value {nice}: expected -20 to 19"),
+        ));
+    }
+    let memory_max = patch
+        .memory_max
+        .as_deref()
+        .map(|value| unit_directive_value("MemoryMax", value))
+        .transpose()?;
+    let cpu_quota = patch
+        .cpu_quota
+        .as_deref()
+        .map(|value| unit_directive_value("CPUQuota", value))
+        .transpose()?;
+    let updated = state
         .config
         .update_runner(&runner_id, |runner| {
             if let Some(display_name) = patch.display_name {
@@ -605,10 +2079,121 @@ async fn runners_update_profile(
             if let Some(pat_alias) = patch.pat_alias {
                 runner.pat_alias = pat_alias;
             }
+            if let Some(ephemeral_restart) = patch.ephemeral_restart {
+                runner.ephemeral_restart = ephemeral_restart;
+            }
+            if let Some(auto_restart) = patch.auto_restart {
+                runner.auto_restart = auto_restart;
+            }
+            if let Some(start_on_app_launch) = patch.start_on_app_launch {
+                runner.start_on_app_launch = start_on_app_launch;
+            }
+            if let Some(version_pinned) = patch.version_pinned {
+                runner.version_pinned = version_pinned;
+            }
+            if let Some(restart_policy) = patch.restart_policy {
+                runner.service.restart_policy = restart_policy;
+            }
+            if let Some(restart_sec) = patch.restart_sec {
+                runner.service.restart_sec = restart_sec;
+            }
+            if let Some(interval) = patch.start_limit_interval_secs {
+                runner.service.start_limit_interval_secs = (interval > 0).then_some(interval);
+            }
+            if let Some(burst) = patch.start_limit_burst {
+                runner.service.start_limit_burst = (burst > 0).then_some(burst);
+            }
+            if let Some(memory_max) = memory_max {
+                runner.service.memory_max = memory_max;
+            }
+            if let Some(cpu_quota) = cpu_quota {
+                runner.service.cpu_quota = cpu_quota;
+            }
+            if let Some(keep_alive) = patch.keep_alive {
+                runner.service.keep_alive = keep_alive;
+            }
+            if let Some(throttle) = patch.throttle_interval_secs {
+                runner.service.throttle_interval_secs = (throttle > 0).then_some(throttle);
+            }
+            if let Some(nice) = patch.nice {
+                runner.service.nice = (nice != 0).then_some(nice);
+            }
+            if let Some(process_type) = patch.process_type {
+                runner.service.process_type = process_type;
+            }
         })
+        .map_err(AppError::from)?;
+    if unit_changed {
+        refresh_installed_service(&state, &updated)?;
+    }
+    Ok(updated)
+}
+
+fn refresh_installed_service(state: &AppState, profile: &RunnerProfile) -> AppResult<()> {
+    if profile.service.installed
+        && profile.service.provider == crate::config::ServiceProvider::Runnerbuddy
+    {
+        service_mgmt::refresh_unit(profile).map_err(AppError::from)?;
+        invalidate_service_status(state, &profile.runner_id);
+    }
+    Ok(())
+}
+
+/// Replaces the runner's extra environment. It is applied to the runner process
+/// and the service definition; the runner's own `.env` still wins for jobs.
+#[tauri::command]
+async fn runners_set_env(
+    state: State<'_, AppState>,
+    runner_id: String,
+    env: BTreeMap<String, String>,
+) -> AppResult<RunnerProfile> {
+    let mut cleaned = BTreeMap::new();
+    for (key, value) in env {
+        let key = key.trim().to_string();
+        runner_mgmt::validate_env_var(&key, &value).map_err(AppError::from)?;
+        cleaned.insert(key, value);
+    }
+    let updated = state
+        .config
+        .update_runner(&runner_id, |runner| runner.env = cleaned)
+        .map_err(AppError::from)?;
+    refresh_installed_service(&state, &updated)?;
+    Ok(updated)
+}
+
+/// The runner's own `.env`, which feeds job steps rather than the listener process.
+#[tauri::command]
+async fn runners_get_env_file(
+    state: State<'_, AppState>,
+    runner_id: String,
+) -> AppResult<Vec<runner_mgmt::EnvEntry>> {
+    let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+    runner_mgmt::read_env_file(&util::expand_path(&profile.install.install_path))
         .map_err(AppError::from)
 }
 
+#[tauri::command]
+async fn runners_set_env_file(
+    state: State<'_, AppState>,
+    runner_id: String,
+    entries: Vec<runner_mgmt::EnvEntry>,
+    merge: Option<bool>,
+) -> AppResult<Vec<runner_mgmt::EnvEntry>> {
+    let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+    let install_path = util::expand_path(&profile.install.install_path);
+    let entries: Vec<_> = entries
+        .into_iter()
+        .map(|entry| runner_mgmt::EnvEntry {
+            key: entry.key.trim().to_string(),
+            value: entry.value,
+        })
+        .collect();
+    runner_mgmt::write_env_file(&install_path, &entries, merge.unwrap_or(true))
+        .map_err(AppError::from)?;
+    info!("Updated .env for runner {runner_id}");
+    runner_mgmt::read_env_file(&install_path).map_err(AppError::from)
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum RunnerDeleteMode {
@@ -627,13 +2212,17 @@ async fn runners_delete_profile(
     info!("Runner delete requested for {runner_id} ({mode:?})");
     let runner_id_log = runner_id.clone();
     let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
-    let _ = runner_mgmt::stop_runner(&runner_id, &state.runner_children);
+    let _ = runner_mgmt::stop_runner(&runner_id, &state.runner_children, stop_grace(&state)).await;
     if profile.service.provider == crate::config::ServiceProvider::Runnerbuddy {
         let _ = service_mgmt::stop(&profile);
         let _ = service_mgmt::uninstall(&profile);
     }
+    invalidate_service_status(&state, &runner_id);
     if matches!(mode, RunnerDeleteMode::UnregisterAndDelete) {
-        unregister_runner(&profile).await.map_err(AppError::from)?;
+        let config = state.config.get();
+        unregister_runner(&profile, &config)
+            .await
+            .map_err(AppError::from)?;
     }
     if matches!(mode, RunnerDeleteMode::LocalDelete | RunnerDeleteMode::UnregisterAndDelete) {
         fn remove_dir(path: &PathBuf, label: &str) {
@@ -667,6 +2256,12 @@ async fn runners_delete_profile(
 
     let mut runtime = state.runtime.lock().expect("runtime mutex poisoned");
     runtime.remove(&runner_id);
+    state
+        .notify_limiter
+        .lock()
+        .expect("notify limiter poisoned")
+        .forget(&runner_id);
+    state.persist_runtime(&runtime);
     let _ = app.emit(
         "runner_status",
         RunnerStatusPayload {
@@ -674,6 +2269,7 @@ async fn runners_delete_profile(
             status: RunnerStatus::Offline,
             pid: None,
             last_heartbeat: Some(now_ts()),
+            current_job: None,
         },
     );
 
@@ -695,7 +2291,8 @@ async fn runners_select(
     Ok(())
 }
 
-fn gh_token_from_cli() -> AppResult<String> {
+/// Places the GitHub CLI is usually installed; GUI launches often lack the shell's PATH.
+fn gh_cli_candidates() -> Vec<String> {
     let mut candidates = Vec::from(["gh"]);
     if cfg!(target_os = "macos") {
         candidates.extend(["/opt/homebrew/bin/gh", "/usr/local/bin/gh", "/usr/bin/gh"]);
@@ -711,14 +2308,18 @@ fn gh_token_from_cli() -> AppResult<String> {
         .map(|home| vec![format!("{home}/.local/bin/gh")])
         .unwrap_or_default();
 
+    candidates
+        .into_iter()
+        .map(str::to_string)
+        .chain(home_candidates)
+        .collect()
+}
+
+fn gh_token_from_cli() -> AppResult<secrets::Secret> {
     let mut output = None;
     let mut tried = Vec::new();
 
-    for candidate in candidates
-        .into_iter()
-        .map(str::to_string)
-        .chain(home_candidates.into_iter())
-    {
+    for candidate in gh_cli_candidates() {
         if tried.contains(&candidate) {
             continue;
         }
@@ -783,7 +2384,8 @@ fn gh_token_from_cli() -> AppResult<String> {
         ));
     }
 
-    let token = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stdout = zeroize::Zeroizing::new(output.stdout);
+    let token = secrets::Secret::new(String::from_utf8_lossy(&stdout).trim().to_string());
     if token.is_empty() {
         return Err(AppError::new(
             "cli",
@@ -794,37 +2396,251 @@ fn gh_token_from_cli() -> AppResult<String> {
     Ok(token)
 }
 
+fn set_auth_method(
+    state: &AppState,
+    alias: &str,
+    method: Option<config::AuthMethod>,
+) -> AppResult<()> {
+    state
+        .config
+        .update(|config| match method {
+            Some(method) => {
+                config.auth_methods.insert(alias.to_string(), method);
+            }
+            None => {
+                config.auth_methods.remove(alias);
+            }
+        })
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+fn index_alias(state: &AppState, alias: &str, stored: bool) -> AppResult<()> {
+    state
+        .config
+        .update(|config| {
+            if stored {
+                config.pat_aliases.insert(alias.to_string());
+            } else {
+                config.pat_aliases.remove(alias);
+            }
+        })
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn auth_save_pat(
+    state: State<'_, AppState>,
+    alias: String,
+    pat: secrets::Secret,
+) -> AppResult<()> {
+    secrets::save_pat(&alias, &pat).map_err(AppError::from)?;
+    set_auth_method(&state, &alias, None)?;
+    index_alias(&state, &alias, true)?;
+    info!("PAT stored in credential store for alias {alias}");
+    Ok(())
+}
+
+#[tauri::command]
+async fn auth_import_gh_token(state: State<'_, AppState>, alias: String) -> AppResult<()> {
+    let token = gh_token_from_cli()?;
+    secrets::save_pat(&alias, &token).map_err(AppError::from)?;
+    set_auth_method(&state, &alias, None)?;
+    index_alias(&state, &alias, true)?;
+    info!("GitHub CLI token imported into credential store for alias {alias}");
+    Ok(())
+}
+
 #[tauri::command]
-async fn auth_save_pat(alias: String, pat: String) -> AppResult<()> {
-    secrets::save_pat(&alias, &pat).map_err(AppError::from)?;
-    info!("PAT stored in credential store for alias {alias}");
+async fn auth_save_app(
+    state: State<'_, AppState>,
+    alias: String,
+    app_id: u64,
+    installation_id: u64,
+    private_key: secrets::Secret,
+) -> AppResult<()> {
+    let api_base = state.config.get().settings.api_base_url;
+    github_api::get_installation_token(&api_base, app_id, installation_id, &private_key)
+        .await
+        .map_err(AppError::from)?;
+    secrets::save_app_key(&alias, &private_key).map_err(AppError::from)?;
+    set_auth_method(
+        &state,
+        &alias,
+        Some(config::AuthMethod::App {
+            app_id,
+            installation_id,
+        }),
+    )?;
+    index_alias(&state, &alias, true)?;
+    info!("GitHub App {app_id} (installation {installation_id}) stored for alias {alias}");
     Ok(())
 }
 
 #[tauri::command]
-async fn auth_import_gh_token(alias: String) -> AppResult<()> {
-    let token = gh_token_from_cli()?;
-    secrets::save_pat(&alias, &token).map_err(AppError::from)?;
-    info!("GitHub CLI token imported into credential store for alias {alias}");
+async fn auth_clear_app(state: State<'_, AppState>, alias: String) -> AppResult<()> {
+    secrets::clear_app_key(&alias).map_err(AppError::from)?;
+    set_auth_method(&state, &alias, None)?;
+    let has_pat = secrets::load_pat(&alias).map_err(AppError::from)?.is_some();
+    index_alias(&state, &alias, has_pat)?;
+    info!("GitHub App credentials cleared for alias {alias}");
     Ok(())
 }
 
 #[tauri::command]
-async fn auth_clear_pat(alias: String) -> AppResult<()> {
+async fn auth_clear_pat(state: State<'_, AppState>, alias: String) -> AppResult<()> {
     secrets::clear_pat(&alias).map_err(AppError::from)?;
+    if matches!(state.config.get().auth_method(&alias), config::AuthMethod::Pat) {
+        index_alias(&state, &alias, false)?;
+    }
     info!("PAT cleared from credential store for alias {alias}");
     Ok(())
 }
 
 #[tauri::command]
-async fn auth_check_pat(alias: String) -> AppResult<bool> {
-    let pat = secrets::load_pat(&alias).map_err(AppError::from)?;
-    if let Some(pat) = pat {
-        github_api::validate_pat(&pat).await.map_err(AppError::from)?;
-        Ok(true)
+async fn auth_secret_backend() -> AppResult<secrets::SecretBackendInfo> {
+    secrets::backend_info().map_err(AppError::from)
+}
+
+fn credential_stored(config: &Config, alias: &str) -> AppResult<bool> {
+    let secret = match config.auth_method(alias) {
+        config::AuthMethod::Pat => secrets::load_pat(alias),
+        config::AuthMethod::App { .. } => secrets::load_app_key(alias),
+    }
+    .map_err(AppError::from)?;
+    Ok(secret.is_some())
+}
+
+/// Lists every alias the app knows about. Aliases saved before the index existed
+/// are probed in the credential store once and added to it.
+#[tauri::command]
+async fn auth_list_aliases(state: State<'_, AppState>) -> AppResult<Vec<AliasInfo>> {
+    let config = state.config.get();
+    let mut discovered = Vec::new();
+    for alias in config.known_aliases() {
+        if config.pat_aliases.contains(&alias) {
+            continue;
+        }
+        if credential_stored(&config, &alias)? {
+            discovered.push(alias);
+        }
+    }
+    let config = if discovered.is_empty() {
+        config
     } else {
-        Ok(false)
+        state
+            .config
+            .update(|config| config.pat_aliases.extend(discovered))
+            .map_err(AppError::from)?
+    };
+    Ok(config
+        .known_aliases()
+        .into_iter()
+        .map(|alias| AliasInfo {
+            auth_method: config.auth_method(&alias),
+            stored: config.pat_aliases.contains(&alias),
+            is_default: config.pat_default_alias == alias,
+            runner_ids: config
+                .runners
+                .iter()
+                .filter(|runner| runner.pat_alias == alias)
+                .map(|runner| runner.runner_id.clone())
+                .collect(),
+            alias,
+        })
+        .collect())
+}
+
+/// Moves the stored credential to a new alias and repoints the default alias and
+/// every runner that used the old one.
+#[tauri::command]
+async fn auth_rename_alias(
+    state: State<'_, AppState>,
+    old_alias: String,
+    new_alias: String,
+) -> AppResult<()> {
+    let new_alias = new_alias.trim().to_string();
+    if new_alias.is_empty() {
+        return Err(AppError::new("config", "alias is required"));
+    }
+    if new_alias == old_alias {
+        return Ok(());
+    }
+    let config = state.config.get();
+    if !config.known_aliases().contains(&old_alias) {
+        return Err(AppError::new("config", format!("alias not found: {old_alias}")));
+    }
+    if config.known_aliases().contains(&new_alias) {
+        return Err(AppError::new("config", format!("alias already exists: {new_alias}")));
+    }
+    let moved = secrets::move_alias(&old_alias, &new_alias).map_err(AppError::from)?;
+    if !moved.any() {
+        return Err(AppError::new(
+            "config",
+            format!("no credential is stored under alias {old_alias}"),
+        ));
+    }
+    let saved = state.config.update(|config| {
+        config.rename_alias(&old_alias, &new_alias);
+        config.pat_aliases.insert(new_alias.clone());
+    });
+    if let Err(err) = saved {
+        // Keep the secrets where the unchanged config still points.
+        if let Err(undo) = secrets::move_alias(&new_alias, &old_alias) {
+            warn!("failed to move credentials back to alias {old_alias}: {undo}");
+        }
+        return Err(AppError::from(err));
+    }
+    info!("Renamed credential alias {old_alias} to {new_alias}");
+    Ok(())
+}
+
+#[tauri::command]
+async fn auth_check_pat(
+    state: State<'_, AppState>,
+    alias: String,
+    scope: Option<config::RunnerScope>,
+) -> AppResult<PatCheckResult> {
+    let config = state.config.get();
+    if let config::AuthMethod::App { .. } = config.auth_method(&alias) {
+        // Installation tokens carry permissions rather than OAuth scopes; minting one is the check.
+        let token = github_api::resolve_token(&config, &alias)
+            .await
+            .map_err(AppError::from)?;
+        return Ok(PatCheckResult {
+            ok: token.is_some(),
+            granted: Vec::new(),
+            missing: Vec::new(),
+        });
+    }
+    let Some(pat) = secrets::load_pat(&alias).map_err(AppError::from)? else {
+        return Ok(PatCheckResult {
+            ok: false,
+            granted: Vec::new(),
+            missing: Vec::new(),
+        });
+    };
+    let api_base = config.settings.api_base_url;
+    let granted = github_api::validate_pat(&api_base, &pat)
+        .await
+        .map_err(AppError::from)?;
+    // Fine-grained tokens do not advertise scopes, so only classic PATs can be checked.
+    let missing = match (&granted, scope.as_ref()) {
+        (Some(granted), Some(scope)) => github_api::missing_scopes(scope, granted),
+        _ => Vec::new(),
+    };
+    if !missing.is_empty() {
+        warn!(
+            "PAT for alias {alias} is missing scopes: {}",
+            missing.join(", ")
+        );
     }
+    Ok(PatCheckResult {
+        ok: missing.is_empty(),
+        granted: granted.unwrap_or_default(),
+        missing,
+    })
 }
 
 #[tauri::command]
@@ -838,39 +2654,208 @@ async fn auth_set_default_alias(state: State<'_, AppState>, alias: String) -> Ap
     Ok(())
 }
 
-fn require_pat(alias: &str) -> AppResult<String> {
-    let pat = secrets::load_pat(alias).map_err(AppError::from)?;
-    pat.ok_or_else(|| AppError::new("secrets", "PAT not found in keychain"))
+/// App installation tokens have no user, so `/user/*` listings don't work for them.
+fn is_app_alias(config: &config::Config, alias: &str) -> bool {
+    matches!(config.auth_method(alias), config::AuthMethod::App { .. })
 }
 
-#[tauri::command]
-async fn github_get_registration_token(scope: config::RunnerScope, alias: String) -> AppResult<github_api::RegistrationToken> {
-    info!("GitHub registration token requested for {} via alias {}", scope.url(), alias);
-    let pat = require_pat(&alias)?;
-    let token = github_api::get_registration_token(&scope, &pat)
+async fn require_token(state: &AppState, alias: &str) -> AppResult<secrets::Secret> {
+    let config = state.config.get();
+    let token = github_api::resolve_token(&config, alias)
         .await
         .map_err(AppError::from)?;
-    Ok(token)
+    token.ok_or_else(|| AppError::new("secrets", "credentials not found in keychain"))
+}
+
+#[tauri::command]
+async fn github_get_registration_token(
+    state: State<'_, AppState>,
+    scope: config::RunnerScope,
+    alias: String,
+) -> AppResult<github_api::RegistrationTokenStatus> {
+    let settings = state.config.get().settings;
+    info!(
+        "GitHub registration token requested for {} via alias {}",
+        scope.url(&settings.github_base_url),
+        alias
+    );
+    let pat = require_token(&state, &alias).await?;
+    let token = github_api::cached_registration_token(
+        &state.registration_tokens,
+        &settings.api_base_url,
+        &scope,
+        &pat,
+    )
+    .await
+    .map_err(AppError::from)?;
+    Ok(token.into())
 }
 
 #[tauri::command]
-async fn github_list_repos(alias: String) -> AppResult<Vec<github_api::RepoInfo>> {
+async fn github_list_repos(
+    state: State<'_, AppState>,
+    alias: String,
+    admin_only: Option<bool>,
+    refresh: Option<bool>,
+) -> AppResult<github_api::Listing<github_api::RepoInfo>> {
     info!("GitHub repo list requested via alias {}", alias);
-    let pat = require_pat(&alias)?;
-    let repos = github_api::list_repos(&pat).await.map_err(AppError::from)?;
-    info!("GitHub repo list returned {} repos for alias {}", repos.len(), alias);
+    let pat = require_token(&state, &alias).await?;
+    let config = state.config.get();
+    let api_base = &config.settings.api_base_url;
+    if refresh.unwrap_or(false) {
+        github_api::clear_listing_cache(&state.github_listings);
+    }
+    let repos = if is_app_alias(&config, &alias) {
+        github_api::list_installation_repos(api_base, &pat).await
+    } else {
+        github_api::list_repos(
+            api_base,
+            &pat,
+            admin_only.unwrap_or(false),
+            &state.github_listings,
+        )
+        .await
+    }
+    .map_err(AppError::from)?;
+    info!(
+        "GitHub repo list returned {} repos for alias {}",
+        repos.items.len(),
+        alias
+    );
+    Ok(repos)
+}
+
+#[tauri::command]
+async fn github_search_repos(
+    state: State<'_, AppState>,
+    alias: String,
+    query: String,
+    admin_only: Option<bool>,
+) -> AppResult<github_api::Listing<github_api::RepoInfo>> {
+    info!("GitHub repo search requested via alias {}", alias);
+    let pat = require_token(&state, &alias).await?;
+    let config = state.config.get();
+    let api_base = &config.settings.api_base_url;
+    let repos = if is_app_alias(&config, &alias) {
+        github_api::search_installation_repos(api_base, &pat, &query).await
+    } else {
+        github_api::search_repos(
+            api_base,
+            &pat,
+            &query,
+            admin_only.unwrap_or(false),
+            &state.github_listings,
+        )
+        .await
+    }
+    .map_err(AppError::from)?;
+    info!(
+        "GitHub repo search returned {} repos for alias {}",
+        repos.items.len(),
+        alias
+    );
     Ok(repos)
 }
 
 #[tauri::command]
-async fn github_list_orgs(alias: String) -> AppResult<Vec<github_api::OrgInfo>> {
+async fn github_list_orgs(
+    state: State<'_, AppState>,
+    alias: String,
+    refresh: Option<bool>,
+) -> AppResult<github_api::Listing<github_api::OrgInfo>> {
     info!("GitHub org list requested via alias {}", alias);
-    let pat = require_pat(&alias)?;
-    let orgs = github_api::list_orgs(&pat).await.map_err(AppError::from)?;
-    info!("GitHub org list returned {} orgs for alias {}", orgs.len(), alias);
+    let pat = require_token(&state, &alias).await?;
+    let config = state.config.get();
+    let api_base = &config.settings.api_base_url;
+    if refresh.unwrap_or(false) {
+        github_api::clear_listing_cache(&state.github_listings);
+    }
+    let orgs = if is_app_alias(&config, &alias) {
+        github_api::list_installation_orgs(api_base, &pat).await
+    } else {
+        github_api::list_orgs(api_base, &pat, &state.github_listings).await
+    }
+    .map_err(AppError::from)?;
+    info!("GitHub org list returned {} orgs for alias {}", orgs.items.len(), alias);
     Ok(orgs)
 }
 
+#[tauri::command]
+async fn github_list_runners(
+    state: State<'_, AppState>,
+    scope: config::RunnerScope,
+    alias: String,
+) -> AppResult<github_api::Listing<github_api::RegisteredRunner>> {
+    let settings = state.config.get().settings;
+    info!(
+        "GitHub runner list requested for {} via alias {}",
+        scope.url(&settings.github_base_url),
+        alias
+    );
+    let pat = require_token(&state, &alias).await?;
+    let runners = github_api::list_registered_runners(&settings.api_base_url, &scope, &pat)
+        .await
+        .map_err(AppError::from)?;
+    info!("GitHub runner list returned {} runners", runners.items.len());
+    Ok(runners)
+}
+
+#[tauri::command]
+async fn github_list_runner_groups(
+    state: State<'_, AppState>,
+    scope: config::RunnerScope,
+    alias: String,
+) -> AppResult<github_api::Listing<github_api::RunnerGroup>> {
+    let pat = require_token(&state, &alias).await?;
+    let api_base = state.config.get().settings.api_base_url;
+    let groups = github_api::list_runner_groups(&api_base, &scope, &pat)
+        .await
+        .map_err(AppError::from)?;
+    info!("GitHub runner group list returned {} groups", groups.items.len());
+    Ok(groups)
+}
+
+#[derive(serde::Serialize)]
+struct NetworkProxyStatus {
+    proxy: http::ProxyInfo,
+    api_base_url: String,
+    reachable: bool,
+    error: Option<String>,
+}
+
+#[tauri::command]
+async fn network_proxy_status(state: State<'_, AppState>) -> AppResult<NetworkProxyStatus> {
+    let api_base_url = state.config.get().settings.api_base_url;
+    let proxy = http::proxy_info();
+    let result = github_api::check_api_base(&api_base_url).await;
+    if let Err(err) = &result {
+        warn!("GitHub API connectivity check failed ({:?} proxy): {err}", proxy.source);
+    }
+    Ok(NetworkProxyStatus {
+        proxy,
+        api_base_url,
+        reachable: result.is_ok(),
+        error: result.err().map(|err| err.to_string()),
+    })
+}
+
+#[tauri::command]
+async fn github_rate_limit(
+    state: State<'_, AppState>,
+    alias: String,
+) -> AppResult<github_api::RateLimit> {
+    let pat = require_token(&state, &alias).await?;
+    let api_base = state.config.get().settings.api_base_url;
+    let rate_limit = github_api::get_rate_limit(&api_base, &pat)
+        .await
+        .map_err(AppError::from)?;
+    info!(
+        "GitHub rate limit for alias {}: {}/{} remaining",
+        alias, rate_limit.remaining, rate_limit.limit
+    );
+    Ok(rate_limit)
+}
+
 #[tauri::command]
 async fn runner_repair_scope(
     state: State<'_, AppState>,
@@ -887,43 +2872,453 @@ async fn runner_download(
     state: State<'_, AppState>,
     runner_id: String,
     version: Option<String>,
+    expected_sha256: Option<String>,
 ) -> AppResult<RunnerProfile> {
     info!("Runner download requested for {runner_id}");
-    runner_mgmt::download_runner(&app, &state.config, &runner_id, version)
+    let cancel = register_download(&state, &runner_id);
+    let result = runner_mgmt::download_runner(
+        &app,
+        &state.config,
+        &runner_id,
+        version,
+        expected_sha256,
+        Some(&cancel),
+    )
+    .await
+    .map_err(AppError::from);
+    finish_download(&state, &runner_id, &cancel);
+    result
+}
+
+fn register_download(state: &AppState, runner_id: &str) -> Arc<AtomicBool> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    state
+        .download_cancels
+        .lock()
+        .expect("download cancel mutex poisoned")
+        .insert(runner_id.to_string(), cancel.clone());
+    cancel
+}
+
+fn finish_download(state: &AppState, runner_id: &str, cancel: &Arc<AtomicBool>) {
+    let mut cancels = state
+        .download_cancels
+        .lock()
+        .expect("download cancel mutex poisoned");
+    // A newer download for the same runner may have replaced this entry.
+    if cancels
+        .get(runner_id)
+        .is_some_and(|current| Arc::ptr_eq(current, cancel))
+    {
+        cancels.remove(runner_id);
+    }
+}
+
+#[tauri::command]
+async fn runner_download_cancel(state: State<'_, AppState>, runner_id: String) -> AppResult<bool> {
+    let cancels = state
+        .download_cancels
+        .lock()
+        .expect("download cancel mutex poisoned");
+    let Some(cancel) = cancels.get(&runner_id) else {
+        return Ok(false);
+    };
+    info!("Runner download cancel requested for {runner_id}");
+    cancel.store(true, Ordering::SeqCst);
+    Ok(true)
+}
+
+#[tauri::command]
+async fn runner_install_dependencies(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    runner_id: String,
+) -> AppResult<()> {
+    #[cfg(target_os = "linux")]
+    {
+        info!("Runner dependency install requested for {runner_id}");
+        runner_mgmt::install_dependencies(&app, &state.config, &runner_id)
+            .await
+            .map_err(AppError::from)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (app, state, runner_id);
+        Err(AppError::new(
+            "unsupported",
+            "installdependencies.sh is only needed on Linux",
+        ))
+    }
+}
+
+#[tauri::command]
+async fn runner_check_update(
+    state: State<'_, AppState>,
+    runner_id: String,
+) -> AppResult<runner_mgmt::RunnerUpdateInfo> {
+    let info = runner_mgmt::check_runner_update(&state.config, &runner_id)
+        .await
+        .map_err(AppError::from)?;
+    info!(
+        "Runner {runner_id} update check: current={:?}, latest={}, available={}, pinned={}",
+        info.current, info.latest, info.update_available, info.pinned
+    );
+    *state
+        .latest_runner_version
+        .lock()
+        .expect("latest version mutex poisoned") = Some(info.latest.clone());
+    Ok(info)
+}
+
+#[tauri::command]
+async fn downloads_usage() -> AppResult<runner_mgmt::DownloadsUsage> {
+    let dir = runner_mgmt::downloads_dir().map_err(AppError::from)?;
+    runner_mgmt::downloads_usage(&dir).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn prune_downloads(keep: usize) -> AppResult<runner_mgmt::PruneResult> {
+    let dir = runner_mgmt::downloads_dir().map_err(AppError::from)?;
+    runner_mgmt::prune_downloads(&dir, keep).map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn list_runner_versions(limit: Option<u32>) -> AppResult<Vec<runner_mgmt::RunnerRelease>> {
+    runner_mgmt::list_runner_versions(limit.unwrap_or(30))
         .await
         .map_err(AppError::from)
 }
 
+/// Runner ids whose RunnerBuddy-spawned process is still alive. Service-managed
+/// runners are not included since they outlive the app.
+fn active_child_runners(state: &AppState) -> Vec<String> {
+    let mut children = state
+        .runner_children
+        .lock()
+        .expect("runner child mutex poisoned");
+    let mut runner_ids: Vec<String> = children
+        .iter_mut()
+        .filter_map(|(runner_id, child)| {
+            matches!(child.try_wait(), Ok(None)).then(|| runner_id.clone())
+        })
+        .collect();
+    runner_ids.sort();
+    runner_ids
+}
+
+/// Exits right away when nothing would be interrupted; otherwise asks the window
+/// to confirm, which then calls `app_quit`.
+fn request_quit(app: &AppHandle) {
+    let runner_ids = active_child_runners(&app.state::<AppState>());
+    if runner_ids.is_empty() {
+        app.exit(0);
+        return;
+    }
+    info!("Quit requested with {} active runner(s); asking for confirmation", runner_ids.len());
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let _ = app.emit("quit_requested", QuitRequestedPayload { runner_ids });
+}
+
+/// Gracefully stops runner child processes, then exits. `force` skips the stop
+/// for scripted shutdown.
+#[tauri::command]
+async fn app_quit(app: AppHandle, force: bool) -> AppResult<()> {
+    if !force {
+        let state = app.state::<AppState>();
+        let runner_ids = active_child_runners(&state);
+        futures_util::future::join_all(runner_ids.iter().map(|runner_id| {
+            let (app, state) = (&app, &state);
+            async move {
+                if let Err(err) = stop_runner_control(app, state, runner_id).await {
+                    warn!("Failed to stop runner {runner_id} before quitting: {err}");
+                }
+            }
+        }))
+        .await;
+    }
+    info!("RunnerBuddy quitting (force={force})");
+    app.exit(0);
+    Ok(())
+}
+
+#[tauri::command]
+async fn app_dashboard(state: State<'_, AppState>) -> AppResult<DashboardSummary> {
+    Ok(dashboard_summary(&state))
+}
+
+/// Whether `runner_update` has to stop the runner first. A pinned runner is refused
+/// before anything is stopped, so a job in progress is never interrupted.
+fn update_needs_stop(profile: &RunnerProfile, status: RunnerStatus) -> Result<bool, Error> {
+    runner_mgmt::ensure_not_pinned(profile)?;
+    Ok(status != RunnerStatus::Offline)
+}
+
+#[tauri::command]
+async fn runner_update(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    runner_id: String,
+) -> AppResult<RunnerProfile> {
+    info!("Runner update requested for {runner_id}");
+    let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+    let (status, _) = compute_runner_status(&state, &profile);
+    let was_running = update_needs_stop(&profile, status).map_err(AppError::from)?;
+    if was_running {
+        stop_runner_control(&app, &state, &runner_id).await?;
+    }
+    let cancel = register_download(&state, &runner_id);
+    let updated = runner_mgmt::update_runner(&app, &state.config, &runner_id, Some(&cancel))
+        .await
+        .map_err(AppError::from);
+    finish_download(&state, &runner_id, &cancel);
+    if was_running {
+        // Bring the runner back either way, but never let a restart error hide
+        // why the update itself failed.
+        let restarted = start_runner_control(&app, &state, &runner_id).await;
+        match (&updated, restarted) {
+            (Ok(_), Err(err)) => return Err(err),
+            (Err(update_err), Err(err)) => {
+                warn!("Runner {runner_id} update failed ({update_err}); restarting it also failed: {err}");
+            }
+            (_, Ok(_)) => {}
+        }
+    }
+    let updated = updated?;
+    info!(
+        "Runner {runner_id} updated to {}",
+        updated.runner_version.as_deref().unwrap_or("unknown")
+    );
+    Ok(updated)
+}
+
 #[tauri::command]
 async fn runner_configure(
+    app: AppHandle,
     state: State<'_, AppState>,
     runner_id: String,
-    scope: config::RunnerScope,
-    name: String,
-    labels: Vec<String>,
-    work_dir: String,
+    options: runner_mgmt::ConfigureOptions,
 ) -> AppResult<RunnerProfile> {
-    runner_mgmt::configure_runner(&state.config, &runner_id, scope, name, labels, work_dir)
+    runner_mgmt::configure_runner(
+        &app,
+        &state.config,
+        &state.registration_tokens,
+        &runner_id,
+        options,
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn runner_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    runner_id: String,
+) -> AppResult<RuntimeState> {
+    start_runner_control(&app, &state, &runner_id).await
+}
+
+#[tauri::command]
+async fn runner_stop(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    runner_id: String,
+) -> AppResult<RuntimeState> {
+    stop_runner_control(&app, &state, &runner_id).await
+}
+
+/// Pre-flight for onboarding: required tools, secret storage, data dir access and
+/// GitHub reachability.
+#[tauri::command]
+async fn diagnose_environment(
+    state: State<'_, AppState>,
+) -> AppResult<Vec<diagnostics::EnvironmentCheck>> {
+    use diagnostics::EnvironmentCheck;
+
+    let mut checks = Vec::new();
+    checks.push(match diagnostics::find_executable(&gh_cli_candidates()) {
+        Some(path) => EnvironmentCheck::new("gh", true, path.to_string_lossy()),
+        None => EnvironmentCheck::new(
+            "gh",
+            false,
+            "GitHub CLI not found (optional unless you sign in with `gh`)",
+        ),
+    });
+    let data_dir = config::data_dir().map_err(AppError::from)?;
+    checks.extend(
+        tauri::async_runtime::spawn_blocking(move || {
+            diagnostics::local_environment_checks(&data_dir)
+        })
         .await
-        .map_err(AppError::from)
+        .map_err(|err| AppError::new("diagnostics", format!("environment checks failed: {err}")))?,
+    );
+
+    checks.push(match secrets::backend_info() {
+        Ok(backend) => match backend.backend {
+            secrets::SecretBackend::Keyring => {
+                EnvironmentCheck::new("keyring", true, "OS credential store available")
+            }
+            secrets::SecretBackend::File => EnvironmentCheck::new(
+                "keyring",
+                false,
+                format!(
+                    "OS credential store unavailable; using encrypted file {}",
+                    backend.location.unwrap_or_default()
+                ),
+            ),
+        },
+        Err(err) => EnvironmentCheck::new("keyring", false, err.to_string()),
+    });
+
+    let api_base = state.config.get().settings.api_base_url;
+    match github_api::check_api_base(&api_base).await {
+        Ok(skew) => {
+            checks.push(EnvironmentCheck::new("github", true, api_base));
+            checks.push(match skew {
+                Some(skew) => match github_api::clock_skew_problem(skew) {
+                    Some(problem) => EnvironmentCheck::new("clock", false, problem),
+                    None => EnvironmentCheck::new("clock", true, format!("within {skew}s of GitHub")),
+                },
+                None => EnvironmentCheck::new("clock", false, "GitHub response had no Date header"),
+            });
+        }
+        Err(err) => checks.push(EnvironmentCheck::new("github", false, err.to_string())),
+    }
+    Ok(checks)
+}
+
+/// Unauthenticated reachability of the API base and the runner download host.
+#[tauri::command]
+async fn test_github_connectivity(
+    state: State<'_, AppState>,
+) -> AppResult<Vec<github_api::ConnectivityProbe>> {
+    let settings = state.config.get().settings;
+    let api_url = format!("{}/zen", settings.api_base_url.trim_end_matches('/'));
+    let download_url = runner_mgmt::download_host_url(&settings);
+    let (api, download) = futures_util::future::join(
+        github_api::probe_url("api", &api_url),
+        github_api::probe_url("download", &download_url),
+    )
+    .await;
+    Ok(vec![api, download])
+}
+
+/// Starts every configured runner that is offline; failures (including hitting
+/// `max_concurrent_runners`) are reported per runner and don't stop the rest.
+#[tauri::command]
+async fn runners_start_all(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<BTreeMap<String, BulkOutcome>> {
+    let mut outcomes = BTreeMap::new();
+    let mut started = BTreeSet::new();
+    for runner in state.config.get().runners.iter() {
+        let runner_id = runner.runner_id.clone();
+        let (status, _) = compute_runner_status(&state, runner);
+        let outcome = if status != RunnerStatus::Offline {
+            BulkOutcome::skipped()
+        } else {
+            let result = match ensure_runner_capacity(&state, &runner_id, &started) {
+                Ok(()) => start_runner_control(&app, &state, &runner_id).await,
+                Err(err) => Err(AppError::from(err)),
+            };
+            if result.is_ok() {
+                started.insert(runner_id.clone());
+            }
+            BulkOutcome::from_result(result)
+        };
+        if let Some(error) = outcome.error.as_deref() {
+            warn!("Bulk start failed for {runner_id}: {error}");
+        }
+        outcomes.insert(runner_id, outcome);
+    }
+    info!("Bulk start finished for {} runner(s)", outcomes.len());
+    Ok(outcomes)
 }
 
 #[tauri::command]
-async fn runner_start(
+async fn runners_stop_all(
     app: AppHandle,
     state: State<'_, AppState>,
-    runner_id: String,
-) -> AppResult<RuntimeState> {
-    start_runner_control(&app, &state, &runner_id)
+) -> AppResult<BTreeMap<String, BulkOutcome>> {
+    let mut outcomes = BTreeMap::new();
+    for runner in state.config.get().runners.iter() {
+        let runner_id = runner.runner_id.clone();
+        let (status, _) = compute_runner_status(&state, runner);
+        let outcome = if status == RunnerStatus::Offline {
+            BulkOutcome::skipped()
+        } else {
+            BulkOutcome::from_result(stop_runner_control(&app, &state, &runner_id).await)
+        };
+        if let Some(error) = outcome.error.as_deref() {
+            warn!("Bulk stop failed for {runner_id}: {error}");
+        }
+        outcomes.insert(runner_id, outcome);
+    }
+    info!("Bulk stop finished for {} runner(s)", outcomes.len());
+    Ok(outcomes)
 }
 
 #[tauri::command]
-async fn runner_stop(
+async fn runner_drain(
     app: AppHandle,
     state: State<'_, AppState>,
     runner_id: String,
+    timeout_secs: Option<u64>,
 ) -> AppResult<RuntimeState> {
-    stop_runner_control(&app, &state, &runner_id)
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DRAIN_TIMEOUT_SECS));
+    let emit_phase = |phase: &str, elapsed: Duration| {
+        let _ = app.emit(
+            "runner_drain",
+            DrainPayload {
+                runner_id: runner_id.clone(),
+                phase: phase.to_string(),
+                elapsed_secs: elapsed.as_secs(),
+            },
+        );
+    };
+    let start = std::time::Instant::now();
+    loop {
+        let profile =
+            config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+        let (status, pid) = compute_runner_status(&state, &profile);
+        match status {
+            RunnerStatus::Offline => {
+                emit_phase("stopped", start.elapsed());
+                return Ok(update_runtime(&app, &state, &runner_id, status, pid, None));
+            }
+            RunnerStatus::Idle => break,
+            RunnerStatus::Running => {
+                update_runtime(&app, &state, &runner_id, status, pid, None);
+                if start.elapsed() >= timeout {
+                    emit_phase("timeout", start.elapsed());
+                    return Err(AppError::new(
+                        "runner",
+                        format!(
+                            "runner {runner_id} is still running a job after {}s; it was not stopped",
+                            timeout.as_secs()
+                        ),
+                    ));
+                }
+                emit_phase("waiting", start.elapsed());
+                tokio::time::sleep(Duration::from_secs(DRAIN_POLL_SECS)).await;
+            }
+        }
+    }
+    emit_phase("stopping", start.elapsed());
+    let runtime = stop_runner_control(&app, &state, &runner_id).await?;
+    emit_phase("stopped", start.elapsed());
+    Ok(runtime)
+}
+
+#[tauri::command]
+async fn status_monitor_set_paused(state: State<'_, AppState>, paused: bool) -> AppResult<()> {
+    state.status_monitor_paused.store(paused, Ordering::Relaxed);
+    info!("Background status monitor {}", if paused { "paused" } else { "resumed" });
+    Ok(())
 }
 
 #[tauri::command]
@@ -960,10 +3355,45 @@ async fn runner_status_all(
 async fn service_install(
     state: State<'_, AppState>,
     runner_id: String,
+    system_scope: Option<bool>,
+    system_user: Option<String>,
 ) -> AppResult<()> {
-    let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+    let mut profile =
+        config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
     ensure_no_external_conflict(&profile)?;
+    if system_scope == Some(true) && !cfg!(any(target_os = "linux", target_os = "macos")) {
+        return Err(AppError::new(
+            "service",
+            "system-wide services are only supported on Linux and macOS",
+        ));
+    }
+    let system_scope = system_scope.unwrap_or(profile.service.system_scope);
+    let system_user = system_user
+        .map(|user| user.trim().to_string())
+        .filter(|user| !user.is_empty())
+        .or_else(|| profile.service.system_user.clone());
+    if system_scope != profile.service.system_scope || system_user != profile.service.system_user {
+        // Remove the unit from its old location before installing it in the new scope.
+        if profile.service.installed
+            && profile.service.provider == crate::config::ServiceProvider::Runnerbuddy
+        {
+            let _ = service_mgmt::uninstall(&profile);
+        }
+        profile = state
+            .config
+            .update_runner(&runner_id, |runner| {
+                runner.service.system_scope = system_scope;
+                runner.service.system_user = system_user;
+            })
+            .map_err(AppError::from)?;
+    }
+    if profile.ephemeral {
+        warn!(
+            "Runner {runner_id} is ephemeral; the service will restart run.sh after each job but cannot re-register it"
+        );
+    }
     service_mgmt::install(&profile).map_err(AppError::from)?;
+    invalidate_service_status(&state, &runner_id);
     info!("Service installed for runner {runner_id}");
     state
         .config
@@ -990,6 +3420,7 @@ async fn service_enable_on_boot(
         service_mgmt::install(&profile).map_err(AppError::from)?;
     }
     service_mgmt::enable_on_boot(&profile, enabled).map_err(AppError::from)?;
+    invalidate_service_status(&state, &runner_id);
     info!("Run on boot set to {enabled} for {runner_id}");
     state
         .config
@@ -1008,19 +3439,22 @@ async fn service_enable_on_boot(
 async fn service_status(
     state: State<'_, AppState>,
     runner_id: String,
+    force: Option<bool>,
 ) -> AppResult<service_mgmt::ServiceStatus> {
     let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
-    service_mgmt::status(&profile).map_err(AppError::from)
+    cached_service_status(&state, &profile, force.unwrap_or(false)).map_err(AppError::from)
 }
 
 #[tauri::command]
 async fn service_status_all(
     state: State<'_, AppState>,
+    force: Option<bool>,
 ) -> AppResult<HashMap<String, service_mgmt::ServiceStatus>> {
     let config = state.config.get();
+    let force = force.unwrap_or(false);
     let mut results = HashMap::new();
     for runner in config.runners.iter() {
-        let status = service_status_or_fallback(runner);
+        let status = service_status_or_fallback(&state, runner, force);
         results.insert(runner.runner_id.clone(), status);
     }
     Ok(results)
@@ -1041,17 +3475,295 @@ async fn logs_tail(
     runner_id: String,
     source: String,
     limit: Option<usize>,
-) -> AppResult<Vec<logs::LogLine>> {
+    from_offset: Option<u64>,
+    before_offset: Option<u64>,
+) -> AppResult<logs::LogChunk> {
     let config = state.config.get();
-    let path = logs::resolve_source_path(&config, &runner_id, &state.log_paths.app_log, &source);
     let limit = limit.unwrap_or(200);
-    logs::tail(&path, limit).map_err(AppError::from)
+    if source == logs::JOURNAL_SOURCE {
+        return logs::read_journal(&config, &runner_id, from_offset, before_offset, limit)
+            .map_err(AppError::from);
+    }
+    let path = logs::resolve_source_path(&config, &runner_id, &state.log_paths.app_log, &source);
+    match from_offset {
+        Some(offset) => logs::read_after(&path, offset, limit),
+        None => logs::read_before(&path, before_offset, limit),
+    }
+    .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn logs_merged(
+    state: State<'_, AppState>,
+    runner_id: String,
+    limit: Option<usize>,
+) -> AppResult<Vec<logs::MergedLogLine>> {
+    let config = state.config.get();
+    logs::read_merged(&config, &runner_id, limit.unwrap_or(500).clamp(1, 5000))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+async fn logs_clear(
+    runner_id: String,
+    include_rotated: Option<bool>,
+) -> AppResult<logs::LogCleanup> {
+    let cleanup = logs::clear_runner_logs(&runner_id, include_rotated.unwrap_or(false))
+        .map_err(AppError::from)?;
+    info!(
+        "Cleared {} log file(s) for runner {runner_id}, freed {} bytes",
+        cleanup.files.len(),
+        cleanup.freed_bytes
+    );
+    Ok(cleanup)
+}
+
+#[tauri::command]
+async fn logs_prune(
+    state: State<'_, AppState>,
+    runner_id: String,
+    keep_days: u64,
+) -> AppResult<logs::LogCleanup> {
+    let runner = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+    let live = compute_runner_status(&state, &runner).0 != RunnerStatus::Offline;
+    let cleanup = logs::prune_diag_logs(&runner, keep_days, live).map_err(AppError::from)?;
+    info!(
+        "Pruned {} diag log(s) older than {keep_days} days for runner {runner_id}, freed {} bytes",
+        cleanup.files.len(),
+        cleanup.freed_bytes
+    );
+    Ok(cleanup)
+}
+
+#[tauri::command]
+async fn get_log_level(state: State<'_, AppState>) -> AppResult<String> {
+    logging::current_log_filter(&state.log_filter).map_err(AppError::from)
+}
+
+/// Swaps the app log filter for this session only; relaunching restores the
+/// `RUST_LOG`/`info` default.
+#[tauri::command]
+async fn set_log_level(state: State<'_, AppState>, level: String) -> AppResult<String> {
+    let applied = logging::set_log_filter(&state.log_filter, &level).map_err(AppError::from)?;
+    info!("App log level set to {applied}");
+    Ok(applied)
+}
+
+#[tauri::command]
+async fn logs_search(
+    state: State<'_, AppState>,
+    runner_id: String,
+    query: String,
+    is_regex: Option<bool>,
+    max_results: Option<usize>,
+) -> AppResult<Vec<logs::LogMatch>> {
+    let config = state.config.get();
+    logs::search(
+        &config,
+        &runner_id,
+        &state.log_paths.app_log,
+        &query,
+        is_regex.unwrap_or(false),
+        max_results.unwrap_or(500).clamp(1, 5000),
+    )
+    .map_err(AppError::from)
+}
+
+fn runner_location(profile: &RunnerProfile, which: &str) -> AppResult<PathBuf> {
+    let install_path = util::expand_path(&profile.install.install_path);
+    match which {
+        "install" => Ok(install_path),
+        // config.sh resolves a relative `--work` against the install directory.
+        "work" => Ok(install_path.join(util::expand_path(&profile.work_dir))),
+        "logs" => config::runner_logs_dir(&profile.runner_id).map_err(AppError::from),
+        other => Err(AppError::new(
+            "runner",
+            format!("unknown runner location: {other}"),
+        )),
+    }
+}
+
+/// Opens the runner's install, work or RunnerBuddy log directory in the file manager.
+#[tauri::command]
+async fn open_runner_location(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    runner_id: String,
+    which: String,
+) -> AppResult<()> {
+    let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+    let path = runner_location(&profile, &which)?;
+    if !path.is_dir() {
+        return Err(AppError::new(
+            "runner",
+            format!("{which} directory does not exist yet: {}", path.display()),
+        ));
+    }
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|err| AppError::new("runner", format!("failed to open {}: {err}", path.display())))
+}
+
+/// Bundles logs and a redacted config into a zip for attaching to bug reports.
+/// Without a runner id, logs for every runner are included.
+#[tauri::command]
+async fn diagnostics_export(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    runner_id: Option<String>,
+    destination: String,
+) -> AppResult<diagnostics::DiagnosticsExport> {
+    let destination = destination.trim();
+    if destination.is_empty() {
+        return Err(AppError::new("config", "destination path is required"));
+    }
+    let config = state.config.get();
+    let app_version = app.package_info().version.to_string();
+    let export = diagnostics::export_bundle(
+        &config,
+        runner_id.as_deref(),
+        &state.log_paths.app_log,
+        &app_version,
+        &util::expand_path(destination),
+    )
+    .map_err(AppError::from)?;
+    info!("Exported diagnostics bundle to {}", export.path);
+    Ok(export)
+}
+
+fn follow_id(runner_id: &str, source: &str) -> String {
+    format!("{runner_id}:{source}")
+}
+
+/// Streams lines appended to a log source as `log_line` events until stopped.
+/// Starting a follow for the same runner and source replaces the previous one.
+#[tauri::command]
+async fn logs_follow_start(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    runner_id: String,
+    source: String,
+) -> AppResult<String> {
+    let id = follow_id(&runner_id, &source);
+    let config = state.config.get();
+    let app_handle = app.clone();
+    let task_id = id.clone();
+    let task = if source == logs::JOURNAL_SOURCE {
+        let mut follower =
+            logs::JournalFollower::new(&config, &runner_id).map_err(AppError::from)?;
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(LOG_FOLLOW_POLL).await;
+                match follower.poll() {
+                    Ok(lines) => emit_log_lines(&app_handle, &task_id, &runner_id, &source, lines),
+                    Err(err) => warn!("log follow {task_id} read failed: {err}"),
+                }
+            }
+        })
+    } else {
+        follow_log_file(app_handle, &state, &config, task_id, runner_id, source)
+    };
+    if let Some(previous) = state
+        .log_followers
+        .lock()
+        .expect("log followers mutex poisoned")
+        .insert(id.clone(), task)
+    {
+        previous.abort();
+    }
+    Ok(id)
+}
+
+fn emit_log_lines(
+    app: &AppHandle,
+    follow_id: &str,
+    runner_id: &str,
+    source: &str,
+    lines: Vec<String>,
+) {
+    for line in lines {
+        let _ = app.emit(
+            "log_line",
+            LogLinePayload {
+                follow_id: follow_id.to_string(),
+                runner_id: runner_id.to_string(),
+                source: source.to_string(),
+                line: logs::LogLine::parse(line),
+            },
+        );
+    }
+}
+
+fn follow_log_file(
+    app_handle: AppHandle,
+    state: &AppState,
+    config: &Config,
+    task_id: String,
+    runner_id: String,
+    source: String,
+) -> tauri::async_runtime::JoinHandle<()> {
+    let path = logs::resolve_source_path(config, &runner_id, &state.log_paths.app_log, &source);
+    let mut follower = logs::LogFollower::new(path);
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(LOG_FOLLOW_POLL).await;
+            let state = app_handle.state::<AppState>();
+            // The latest diag file changes as the runner rolls its _diag logs.
+            let path = logs::resolve_source_path(
+                &state.config.get(),
+                &runner_id,
+                &state.log_paths.app_log,
+                &source,
+            );
+            if path != follower.path() {
+                follower = logs::LogFollower::new(path);
+            }
+            match follower.poll() {
+                Ok(lines) => emit_log_lines(&app_handle, &task_id, &runner_id, &source, lines),
+                Err(err) => warn!("log follow {task_id} read failed: {err}"),
+            }
+        }
+    })
+}
+
+/// Stops one follow, or every active follow when `follow_id` is omitted.
+#[tauri::command]
+async fn logs_follow_stop(state: State<'_, AppState>, follow_id: Option<String>) -> AppResult<()> {
+    let mut followers = state
+        .log_followers
+        .lock()
+        .expect("log followers mutex poisoned");
+    match follow_id {
+        Some(id) => {
+            if let Some(task) = followers.remove(&id) {
+                task.abort();
+            }
+        }
+        None => {
+            for (_, task) in followers.drain() {
+                task.abort();
+            }
+        }
+    }
+    Ok(())
 }
 
 #[tauri::command]
-async fn discover_scan(state: State<'_, AppState>) -> AppResult<Vec<discovery::DiscoveryCandidate>> {
+async fn discover_scan(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    include_sizes: Option<bool>,
+) -> AppResult<Vec<discovery::DiscoveryCandidate>> {
     let config = state.config.get();
-    let candidates = discovery::scan(&config).map_err(AppError::from)?;
+    let include_sizes = include_sizes.unwrap_or(false);
+    let candidates = tauri::async_runtime::spawn_blocking(move || {
+        discovery::scan(&config, include_sizes, |scanned, total| {
+            let _ = app.emit("discovery_progress", DiscoveryProgressPayload { scanned, total });
+        })
+    })
+    .await
+    .map_err(|err| AppError::new("discover", format!("discovery scan task failed: {err}")))?
+    .map_err(AppError::from)?;
     let mut cache = state.discovery_cache.lock().expect("discovery mutex poisoned");
     cache.clear();
     for candidate in candidates.iter() {
@@ -1104,6 +3816,24 @@ async fn discover_migrate_service(
     Ok(())
 }
 
+#[tauri::command]
+async fn discover_restore_external_service(
+    state: State<'_, AppState>,
+    runner_id: String,
+) -> AppResult<()> {
+    let mut profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+    let _ = runner_mgmt::stop_runner(&runner_id, &state.runner_children, stop_grace(&state)).await;
+    discovery::restore_external_service(&mut profile).map_err(AppError::from)?;
+    state
+        .config
+        .update_runner(&runner_id, |runner| {
+            runner.service = profile.service.clone();
+        })
+        .map_err(AppError::from)?;
+    invalidate_service_status(&state, &runner_id);
+    Ok(())
+}
+
 #[tauri::command]
 async fn discover_remove_external_artifacts(
     state: State<'_, AppState>,
@@ -1120,6 +3850,18 @@ async fn discover_remove_external_artifacts(
     Ok(())
 }
 
+#[tauri::command]
+async fn discover_orphaned_services() -> AppResult<Vec<discovery::OrphanedService>> {
+    tauri::async_runtime::spawn_blocking(discovery::scan_orphaned_services)
+        .await
+        .map_err(|err| AppError::new("discover", format!("orphaned service scan failed: {err}")))
+}
+
+#[tauri::command]
+async fn discover_remove_orphaned_service(service_id: String) -> AppResult<()> {
+    discovery::remove_orphaned_service(&service_id).map_err(AppError::from)
+}
+
 #[tauri::command]
 async fn discover_verify_runner(
     state: State<'_, AppState>,
@@ -1128,6 +3870,17 @@ async fn discover_verify_runner(
     verify_runner_install(&state, &runner_id).await
 }
 
+/// Fast static check of the install files; `discover_verify_runner` boots the runner instead.
+#[tauri::command]
+async fn verify_install_integrity(
+    state: State<'_, AppState>,
+    runner_id: String,
+) -> AppResult<runner_mgmt::IntegrityReport> {
+    let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
+    let install_path = util::expand_path(&profile.install.install_path);
+    Ok(runner_mgmt::verify_install_integrity(&install_path))
+}
+
 #[tauri::command]
 async fn discover_delete_original_install(
     state: State<'_, AppState>,
@@ -1141,6 +3894,7 @@ async fn discover_move_install(
     state: State<'_, AppState>,
     runner_id: String,
     destination: Option<String>,
+    verify_hashes: Option<bool>,
 ) -> AppResult<RunnerProfile> {
     let profile = config::find_runner(&state.config.get(), &runner_id).map_err(AppError::from)?;
     if profile.service.provider == crate::config::ServiceProvider::External {
@@ -1152,11 +3906,18 @@ async fn discover_move_install(
             ));
         }
     }
-    let _ = runner_mgmt::stop_runner(&runner_id, &state.runner_children);
+    let _ = runner_mgmt::stop_runner(&runner_id, &state.runner_children, stop_grace(&state)).await;
     if profile.service.provider == crate::config::ServiceProvider::Runnerbuddy {
         let _ = service_mgmt::stop(&profile);
     }
-    discovery::move_install(&state.config, &runner_id, destination).map_err(AppError::from)
+    invalidate_service_status(&state, &runner_id);
+    discovery::move_install(
+        &state.config,
+        &runner_id,
+        destination,
+        verify_hashes.unwrap_or(true),
+    )
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -1187,7 +3948,7 @@ async fn discover_rollback_move(
     );
 
     let managed_path = util::expand_path(&profile.install.install_path);
-    let _ = runner_mgmt::stop_runner(&runner_id, &state.runner_children);
+    let _ = runner_mgmt::stop_runner(&runner_id, &state.runner_children, stop_grace(&state)).await;
     if profile.service.provider == crate::config::ServiceProvider::Runnerbuddy
         && profile.service.installed
     {
@@ -1210,6 +3971,7 @@ async fn discover_rollback_move(
     {
         service_mgmt::install(&updated_profile).map_err(AppError::from)?;
     }
+    invalidate_service_status(&state, &runner_id);
 
     if managed_path == original_path {
         warn!("Rollback skipped deleting managed copy because paths match: {:?}", managed_path);
@@ -1246,7 +4008,8 @@ async fn verify_runner_install(
     let (child_running, _) = check_runner_process(state, runner_id);
     let service_status = service_mgmt::status(&profile).map_err(AppError::from)?;
     if child_running {
-        let _ = runner_mgmt::stop_runner(runner_id, &state.runner_children);
+        let _ = runner_mgmt::stop_runner(runner_id, &state.runner_children, stop_grace(state))
+            .await;
     }
     if service_status.running {
         let _ = service_mgmt::stop(&profile);
@@ -1260,7 +4023,8 @@ async fn verify_runner_install(
     if started_via_service {
         service_mgmt::start(&profile).map_err(AppError::from)?;
     } else {
-        runner_mgmt::start_runner(&state.config, runner_id, &state.runner_children)
+        launch_runner(state, &profile)
+            .await
             .map_err(AppError::from)?;
     }
     let timeout = Duration::from_secs(60);
@@ -1284,8 +4048,10 @@ async fn verify_runner_install(
         let _ = service_mgmt::stop(&profile);
     }
     if !child_running && !started_via_service {
-        let _ = runner_mgmt::stop_runner(runner_id, &state.runner_children);
+        let _ = runner_mgmt::stop_runner(runner_id, &state.runner_children, stop_grace(state))
+            .await;
     }
+    invalidate_service_status(state, runner_id);
 
     let status = if ok {
         crate::config::MigrationStatus::Verified
@@ -1386,8 +4152,13 @@ fn check_runner_process(state: &AppState, runner_id: &str) -> (bool, Option<u32>
         .expect("runner child mutex poisoned");
     if let Some(child) = guard.get_mut(runner_id) {
         match child.try_wait() {
-            Ok(Some(_)) => {
+            Ok(Some(exit)) => {
                 guard.remove(runner_id);
+                state
+                    .runner_exits
+                    .lock()
+                    .expect("runner exits mutex poisoned")
+                    .insert(runner_id.to_string(), exit);
             }
             Ok(None) => {
                 pid = Some(child.id());
@@ -1401,14 +4172,15 @@ fn check_runner_process(state: &AppState, runner_id: &str) -> (bool, Option<u32>
     (running, pid)
 }
 
-async fn unregister_runner(profile: &RunnerProfile) -> Result<(), Error> {
+async fn unregister_runner(profile: &RunnerProfile, config: &Config) -> Result<(), Error> {
     let scope = match profile.scope.clone() {
         Some(scope) => scope,
         None => return Ok(()),
     };
-    let pat = secrets::load_pat(&profile.pat_alias)?
-        .ok_or_else(|| Error::Runner("PAT not found for unregister".into()))?;
-    let token = github_api::get_remove_token(&scope, &pat).await?;
+    let api_base = &config.settings.api_base_url;
+    let pat = github_api::resolve_token(config, &profile.pat_alias)
+        .await?
+        .ok_or_else(|| Error::Runner("credentials not found for unregister".into()))?;
     let install_path = util::expand_path(&profile.install.install_path);
     let config_script = if cfg!(target_os = "windows") {
         install_path.join("config.cmd")
@@ -1416,17 +4188,35 @@ async fn unregister_runner(profile: &RunnerProfile) -> Result<(), Error> {
         install_path.join("config.sh")
     };
     if config_script.exists() {
-        let status = std::process::Command::new(config_script)
-            .current_dir(&install_path)
-            .arg("remove")
-            .arg("--token")
-            .arg(&token.token)
-            .status();
-        if let Ok(status) = status {
-            if !status.success() {
-                warn!("runner remove failed");
-            }
+        let token = github_api::get_remove_token(api_base, &scope, &pat).await?;
+        let status =
+            runner_mgmt::config_script_command(&config_script, &install_path, &token.token)
+                .envs(&profile.env)
+                .arg("remove")
+                .status();
+        match status {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(_) => warn!("runner remove failed; falling back to API deletion"),
+            Err(err) => warn!("runner remove failed ({err}); falling back to API deletion"),
+        }
+    }
+    let runners = github_api::list_registered_runners(api_base, &scope, &pat).await?;
+    match runners
+        .items
+        .iter()
+        .find(|runner| runner.name == profile.runner_name)
+    {
+        Some(runner) => {
+            github_api::delete_runner(api_base, &scope, runner.id, &pat).await?;
+            info!(
+                "Deleted runner {} (id {}) via GitHub API",
+                runner.name, runner.id
+            );
         }
+        None => warn!(
+            "runner {} not registered with GitHub; nothing to unregister",
+            profile.runner_name
+        ),
     }
     Ok(())
 }
@@ -1435,10 +4225,16 @@ async fn unregister_runner(profile: &RunnerProfile) -> Result<(), Error> {
 pub fn run() {
     let log_setup = logging::init_logging().expect("failed to init logging");
     let config_store = config::ConfigStore::load().expect("failed to load config");
+    http::configure(http::NetworkSettings::from_settings(&config_store.get().settings));
+    logging::configure_scrub_patterns(&config_store.get().settings.scrub_patterns);
+    logging::configure_log_rotation(logging::LogRotation::from_settings(
+        &config_store.get().settings,
+    ));
     let app_state = AppState::new(config_store, log_setup);
     info!("RunnerBuddy starting");
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_process::init())
         .manage(app_state)
         .setup(|app| {
@@ -1447,6 +4243,10 @@ pub fn run() {
             if let Err(err) = setup_tray(app.handle()) {
                 error!("tray setup failed: {err}");
             }
+            spawn_status_monitor(app.handle().clone());
+            if let Err(err) = apply_metrics_settings(app.handle(), &app.state::<AppState>()) {
+                error!("metrics endpoint failed to start: {err}");
+            }
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -1454,37 +4254,82 @@ pub fn run() {
             runners_default_profile,
             settings_get,
             settings_update,
+            config_export,
+            config_import,
             onboarding_complete,
             onboarding_reset,
             runners_create_profile,
             runners_update_profile,
+            runners_set_env,
+            runners_get_env_file,
+            runners_set_env_file,
             runners_delete_profile,
             runners_select,
             auth_save_pat,
             auth_import_gh_token,
+            auth_save_app,
+            auth_clear_app,
             auth_clear_pat,
+            auth_list_aliases,
+            auth_secret_backend,
+            auth_rename_alias,
             auth_check_pat,
             auth_set_default_alias,
             github_get_registration_token,
             github_list_repos,
+            github_search_repos,
             github_list_orgs,
+            github_list_runners,
+            github_list_runner_groups,
+            github_rate_limit,
+            network_proxy_status,
             runner_repair_scope,
             runner_download,
+            runner_download_cancel,
+            runner_install_dependencies,
+            runner_check_update,
+            runner_update,
             runner_configure,
             runner_start,
             runner_stop,
+            runner_drain,
+            status_monitor_set_paused,
             runner_status,
             runner_status_all,
+            app_dashboard,
+            app_quit,
+            open_runner_location,
+            verify_install_integrity,
+            list_runner_versions,
+            downloads_usage,
+            prune_downloads,
+            runners_start_all,
+            runners_stop_all,
+            export_inventory,
+            diagnose_environment,
+            test_github_connectivity,
             service_install,
             service_enable_on_boot,
             service_status,
             service_status_all,
             logs_list_sources,
             logs_tail,
+            logs_merged,
+            logs_clear,
+            logs_prune,
+            logs_search,
+            get_log_level,
+            set_log_level,
+            diagnostics_export,
+            logs_follow_start,
+            logs_follow_stop,
             discover_scan,
             discover_import,
             discover_migrate_service,
             discover_remove_external_artifacts,
+            discover_restore_external_service,
+            discover_orphaned_services,
+            discover_remove_orphaned_service,
             discover_verify_runner,
             discover_delete_original_install,
             discover_move_install,
@@ -1498,8 +4343,9 @@ pub fn run() {
 mod tests {
     use super::*;
     use crate::config::{
-        default_install_path, now_iso8601, new_runner_id, InstallConfig, InstallMode,
-        MigrationStatus, RunnerServiceConfig, RunnerScope, ServiceProvider,
+        default_install_path, default_restart_sec, now_iso8601, new_runner_id, InstallConfig,
+        InstallMode, KeepAlive, MigrationStatus, ProcessType, RestartPolicy, RunnerServiceConfig,
+        RunnerScope, ServiceProvider,
     };
     use crate::{runner_mgmt, secrets};
     use std::collections::HashMap;
@@ -1508,6 +4354,78 @@ mod tests {
     use tempfile::tempdir;
     use tauri::test::mock_app;
 
+    #[test]
+    fn monitor_observation_keeps_the_finished_job_for_notifications() {
+        let mut runtime = HashMap::new();
+        let job = CurrentJob {
+            name: "build".to_string(),
+            run_id: None,
+            url: None,
+        };
+        let observe = |status, job: Option<CurrentJob>| Observation {
+            status,
+            pid: Some(42),
+            job,
+            crash_loop: None,
+        };
+
+        let started = apply_observation(
+            &mut runtime,
+            "abc",
+            observe(RunnerStatus::Running, Some(job.clone())),
+        )
+        .expect("job started");
+        assert_eq!(started.status, RunnerStatus::Offline);
+        let notes = notifications::transition_notifications("ci-1", &started, &runtime["abc"]);
+        assert!(notes.is_empty());
+
+        let finished = apply_observation(&mut runtime, "abc", observe(RunnerStatus::Idle, None))
+            .expect("job finished");
+        assert_eq!(finished.job.as_ref(), Some(&job));
+        assert_eq!(runtime["abc"].current_job, None);
+        let notes = notifications::transition_notifications("ci-1", &finished, &runtime["abc"]);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].kind, notifications::NotificationKind::JobFinished);
+        assert_eq!(notes[0].body, "ci-1: build");
+
+        assert!(apply_observation(&mut runtime, "abc", observe(RunnerStatus::Idle, None)).is_none());
+    }
+
+    #[test]
+    fn monitor_reports_a_crash_loop_once_and_clears_it_when_it_ends() {
+        let mut runtime = HashMap::new();
+        let mut crash_loops = service_mgmt::CrashLoopDetector::default();
+        let start = std::time::Instant::now();
+        let mut poll = |runtime: &mut HashMap<String, RuntimeState>, minute: u64, count: u32| {
+            let restarts = service_mgmt::ServiceRestarts {
+                count,
+                last_exit_code: Some(1),
+            };
+            let now = start + Duration::from_secs(minute * 60);
+            let observation = Observation {
+                status: RunnerStatus::Offline,
+                pid: None,
+                job: None,
+                crash_loop: crash_loops.observe("abc", restarts, now),
+            };
+            apply_observation(runtime, "abc", observation)
+        };
+
+        assert!(poll(&mut runtime, 0, 0).is_none());
+        assert!(poll(&mut runtime, 1, 2).is_none());
+        let looping = poll(&mut runtime, 2, 4).expect("crash loop reported");
+        assert_eq!(looping.error, None);
+        let message = runtime["abc"].last_error.clone().expect("crash loop error");
+        assert!(service_mgmt::is_crash_loop_error(&message));
+
+        assert!(poll(&mut runtime, 3, 6).is_none(), "a continuing loop is not a new change");
+        assert_eq!(runtime["abc"].last_error.as_ref(), Some(&message));
+
+        assert!(poll(&mut runtime, 20, 6).is_some(), "the end of the loop is published");
+        assert_eq!(runtime["abc"].last_error, None);
+        assert!(poll(&mut runtime, 21, 6).is_none());
+    }
+
     fn sample_profile() -> RunnerProfile {
         RunnerProfile {
             runner_id: "abc".to_string(),
@@ -1523,6 +4441,14 @@ mod tests {
                 migration_status: MigrationStatus::Verified,
             },
             runner_version: None,
+            runner_group: None,
+            ephemeral: false,
+            ephemeral_restart: false,
+            jit: false,
+            auto_restart: false,
+            start_on_app_launch: false,
+            version_pinned: false,
+            env: Default::default(),
             pat_alias: "default".to_string(),
             service: RunnerServiceConfig {
                 installed: true,
@@ -1531,12 +4457,169 @@ mod tests {
                 external_id: Some("svc.id".to_string()),
                 external_path: Some("/tmp/service.plist".to_string()),
                 external_restore: None,
+                system_scope: false,
+                system_user: None,
+                restart_policy: RestartPolicy::Always,
+                restart_sec: default_restart_sec(),
+                start_limit_interval_secs: None,
+                start_limit_burst: None,
+                memory_max: None,
+                cpu_quota: None,
+                keep_alive: KeepAlive::Always,
+                throttle_interval_secs: None,
+                nice: None,
+                process_type: ProcessType::Default,
             },
             created_at: "2024-01-01T00:00:00Z".to_string(),
             last_seen_at: None,
         }
     }
 
+    #[test]
+    fn clearing_external_service_resets_provider_and_fields() {
+        let mut profile = sample_profile();
+        discovery::clear_external_service(&mut profile);
+        assert_eq!(profile.service.provider, ServiceProvider::Unknown);
+        assert!(!profile.service.installed);
+        assert!(!profile.service.run_on_boot);
+        assert!(profile.service.external_id.is_none());
+        assert!(profile.service.external_path.is_none());
+        assert_eq!(profile.install.install_path, "/tmp/runner");
+    }
+
+    #[test]
+    fn metrics_render_gauges_per_runner_with_escaped_labels() {
+        let mut config = crate::config::Config::default();
+        let mut profile = sample_profile();
+        profile.display_name = "Build \"fast\"".to_string();
+        profile.runner_version = Some("2.320.0".to_string());
+        config.runners.push(profile);
+        let mut runtime = HashMap::new();
+        runtime.insert(
+            "abc".to_string(),
+            RuntimeState {
+                status: RunnerStatus::Running,
+                jobs_completed: 12,
+                ..RuntimeState::default()
+            },
+        );
+
+        let text = metrics::render(&config, &runtime);
+        let labels = "runner_id=\"abc\",name=\"Build \\\"fast\\\"\"";
+        assert!(text.contains("# TYPE runnerbuddy_jobs_completed_total counter\n"));
+        assert!(text.contains(&format!("runnerbuddy_runner_up{{{labels}}} 1\n")));
+        assert!(text.contains(&format!("runnerbuddy_runner_running{{{labels}}} 1\n")));
+        assert!(text.contains(&format!("runnerbuddy_jobs_completed_total{{{labels}}} 12\n")));
+        assert!(text.contains(&format!(
+            "runnerbuddy_runner_version_info{{{labels},version=\"2.320.0\"}} 1\n"
+        )));
+
+        let idle = metrics::render(&config, &HashMap::new());
+        assert!(idle.contains(&format!("runnerbuddy_runner_up{{{labels}}} 0\n")));
+    }
+
+    #[test]
+    fn pinned_running_runner_is_refused_before_it_is_stopped() {
+        let mut profile = sample_profile();
+        profile.runner_version = Some("2.320.0".to_string());
+        assert!(update_needs_stop(&profile, RunnerStatus::Running).expect("unpinned"));
+        assert!(!update_needs_stop(&profile, RunnerStatus::Offline).expect("unpinned"));
+
+        profile.version_pinned = true;
+        let err = update_needs_stop(&profile, RunnerStatus::Running).expect_err("pinned");
+        assert!(err.to_string().contains("pinned to version 2.320.0"));
+    }
+
+    #[test]
+    fn concurrency_limit_applies_only_when_full() {
+        assert!(concurrency_limit_error(10, 0).is_none());
+        assert!(concurrency_limit_error(1, 2).is_none());
+        let err = concurrency_limit_error(2, 2).expect("limit reached");
+        assert!(err.to_string().contains("2 of 2 running"));
+    }
+
+    #[test]
+    fn inventory_csv_quotes_fields_and_flattens_scope() {
+        let mut profile = sample_profile();
+        profile.display_name = "Build \"fast\", x64".to_string();
+        profile.labels = vec!["self-hosted".to_string(), "linux".to_string()];
+        profile.scope = Some(RunnerScope::Org {
+            org: "acme".to_string(),
+        });
+        let mut config = crate::config::Config::default();
+        config.runners.push(profile);
+        let mut runtime = HashMap::new();
+        runtime.insert(
+            "abc".to_string(),
+            RuntimeState {
+                status: RunnerStatus::Idle,
+                pid: None,
+                last_heartbeat: Some(42),
+                last_error: None,
+                current_job: None,
+                jobs_completed: 3,
+                job_log_cursor: None,
+            },
+        );
+
+        let rows = inventory_rows(&config, &runtime);
+        assert_eq!(rows[0].scope.as_deref(), Some("https://github.com/acme"));
+        let csv = inventory_csv(&rows);
+        let mut lines = csv.split("\r\n");
+        assert!(lines.next().unwrap().starts_with("runner_id,display_name,"));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("abc,\"Build \"\"fast\"\", x64\",runner,"));
+        assert!(row.contains(",\"self-hosted,linux\","));
+        assert!(row.ends_with(",false,idle,,42,3"));
+    }
+
+    #[test]
+    fn status_badge_paints_corner_or_greys_out() {
+        let (width, height) = (16u32, 16u32);
+        let base: Vec<u8> = [200u8, 40, 40, 255].repeat((width * height) as usize);
+
+        let mut badged = base.clone();
+        paint_status_badge(&mut badged, width, height, TrayHealth::Running.badge_color());
+        let corner = (((height - 3) * width + (width - 3)) * 4) as usize;
+        assert_eq!(&badged[corner..corner + 3], &[59, 130, 246]);
+        assert_eq!(&badged[..4], &base[..4]);
+
+        let mut grey = base.clone();
+        paint_status_badge(&mut grey, width, height, TrayHealth::Offline.badge_color());
+        assert_eq!(grey[0], grey[1]);
+        assert_eq!(grey[1], grey[2]);
+        assert_eq!(grey[3], 255);
+    }
+
+    #[test]
+    fn runner_locations_resolve_relative_work_dir() {
+        let mut profile = sample_profile();
+        profile.work_dir = "_work".to_string();
+        assert_eq!(
+            runner_location(&profile, "install").unwrap(),
+            PathBuf::from("/tmp/runner")
+        );
+        assert_eq!(
+            runner_location(&profile, "work").unwrap(),
+            PathBuf::from("/tmp/runner/_work")
+        );
+        profile.work_dir = "/srv/work".to_string();
+        assert_eq!(runner_location(&profile, "work").unwrap(), PathBuf::from("/srv/work"));
+        assert!(runner_location(&profile, "bin").is_err());
+    }
+
+    #[test]
+    fn tray_menu_ids_carry_runner_ids() {
+        assert_eq!(parse_tray_menu_id("open"), Some(TrayAction::Open));
+        assert_eq!(
+            parse_tray_menu_id("start:6f1c-runner"),
+            Some(TrayAction::Start("6f1c-runner"))
+        );
+        assert_eq!(parse_tray_menu_id("stop:abc"), Some(TrayAction::Stop("abc")));
+        assert_eq!(parse_tray_menu_id("start"), None);
+        assert_eq!(parse_tray_menu_id("remove:abc"), None);
+    }
+
     #[test]
     fn external_conflict_message_includes_details() {
         let profile = sample_profile();
@@ -1544,6 +4627,9 @@ mod tests {
             installed: true,
             running: false,
             enabled: true,
+            linger: None,
+            launchd_kind: None,
+            restarts: None,
         };
         let message = external_conflict_message(&profile, &status).expect("message");
         assert!(message.contains("id: svc.id"));
@@ -1558,6 +4644,9 @@ mod tests {
             installed: true,
             running: true,
             enabled: true,
+            linger: None,
+            launchd_kind: None,
+            restarts: None,
         };
         assert!(external_conflict_message(&profile, &status).is_none());
     }
@@ -1569,11 +4658,22 @@ mod tests {
             installed: true,
             running: false,
             enabled: false,
+            linger: None,
+            launchd_kind: None,
+            restarts: None,
         };
         let err = ensure_no_external_conflict_with_status(&profile, status).expect_err("conflict");
         assert_eq!(err.code, "service");
     }
 
+    #[test]
+    fn auto_restart_delay_doubles_up_to_cap() {
+        assert_eq!(auto_restart_delay(0), Duration::from_secs(5));
+        assert_eq!(auto_restart_delay(2), Duration::from_secs(20));
+        assert_eq!(auto_restart_delay(10), Duration::from_secs(AUTO_RESTART_MAX_DELAY_SECS));
+        assert_eq!(auto_restart_delay(u32::MAX), Duration::from_secs(AUTO_RESTART_MAX_DELAY_SECS));
+    }
+
     #[test]
     fn validate_delete_original_requires_verified() {
         let mut profile = sample_profile();
@@ -1674,6 +4774,14 @@ mod tests {
                         migration_status: MigrationStatus::None,
                     },
                     runner_version: None,
+                    runner_group: None,
+                    ephemeral: false,
+                    ephemeral_restart: false,
+                    jit: false,
+                    auto_restart: false,
+                    start_on_app_launch: false,
+                    version_pinned: false,
+                    env: Default::default(),
                     pat_alias: "integration".to_string(),
                     service: RunnerServiceConfig::default(),
                     created_at: now_iso8601(),
@@ -1686,17 +4794,25 @@ mod tests {
         let app = mock_app();
         let app_handle = app.handle();
 
-        runner_mgmt::download_runner(&app_handle, &config_store, &runner_id, None)
+        runner_mgmt::download_runner(&app_handle, &config_store, &runner_id, None, None, None)
             .await
             .expect("download runner");
 
+        let token_cache = Mutex::new(HashMap::new());
         let profile = runner_mgmt::configure_runner(
+            &app_handle,
             &config_store,
+            &token_cache,
             &runner_id,
-            scope,
-            runner_name,
-            vec!["self-hosted".to_string(), "runnerbuddy".to_string()],
-            work_dir.clone(),
+            runner_mgmt::ConfigureOptions {
+                scope,
+                name: runner_name,
+                labels: vec!["self-hosted".to_string(), "runnerbuddy".to_string()],
+                work_dir: work_dir.clone(),
+                runner_group: None,
+                ephemeral: false,
+                jit: false,
+            },
         )
         .await
         .expect("configure runner");
@@ -1718,8 +4834,10 @@ mod tests {
         }
         assert!(ready, "runner did not report ready state");
 
-        let _ = runner_mgmt::stop_runner(&runner_id, &child_map);
-        unregister_runner(&profile).await.expect("unregister runner");
+        let _ = runner_mgmt::stop_runner(&runner_id, &child_map, Duration::from_secs(30)).await;
+        unregister_runner(&profile, &config_store.get())
+            .await
+            .expect("unregister runner");
         secrets::clear_pat("integration").expect("clear pat");
         let _ = std::fs::remove_dir_all(&install_path);
         let _ = std::fs::remove_dir_all(&work_dir);
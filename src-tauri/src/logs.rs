@@ -1,10 +1,20 @@
-use crate::config::Config;
+use crate::config::{Config, RunnerProfile};
 use crate::errors::Error;
 use crate::logging::scrub_sensitive;
 use crate::runner_mgmt::{latest_log_file, runner_log_dir};
-use crate::util::{read_file_tail, LOG_TAIL_BYTES};
+use crate::util::LOG_TAIL_BYTES;
+use regex::{Regex, RegexBuilder};
 use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+/// Output of a RunnerBuddy-managed systemd unit, read through `journalctl`.
+pub const JOURNAL_SOURCE: &str = "service-journal";
 
 #[derive(Debug, Serialize)]
 pub struct LogSource {
@@ -13,9 +23,80 @@ pub struct LogSource {
     pub path: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Verbose,
+    Info,
+    Warn,
+    Error,
+}
+
+/// A scrubbed log line, split into timestamp, level and message when it follows
+/// the runner diag format (`[2024-01-15 10:23:45Z INFO Runner] message`) or the
+/// console format of `run.sh` (`2024-01-15 10:23:45Z: message`, no level).
+#[derive(Debug, Serialize, Clone)]
 pub struct LogLine {
-    pub line: String,
+    pub raw: String,
+    pub ts: Option<String>,
+    pub level: Option<LogLevel>,
+    pub message: String,
+}
+
+impl LogLine {
+    /// `raw` must already be scrubbed; the message is cut from it, not re-read.
+    pub fn parse(raw: String) -> Self {
+        static DIAG: OnceLock<Regex> = OnceLock::new();
+        static CONSOLE: OnceLock<Regex> = OnceLock::new();
+        let pattern = DIAG.get_or_init(|| {
+            Regex::new(
+                r"^\[(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}Z) (VERB|INFO|WARN|ERR)\s*[^\]]*\] ?(.*)$",
+            )
+            .expect("diag log pattern")
+        });
+        let console = CONSOLE.get_or_init(|| {
+            Regex::new(r"^(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}Z): (.*)$")
+                .expect("console log pattern")
+        });
+        if let Some(captures) = console.captures(&raw) {
+            return Self {
+                ts: Some(captures[1].to_string()),
+                level: None,
+                message: captures[2].to_string(),
+                raw: raw.clone(),
+            };
+        }
+        let Some(captures) = pattern.captures(&raw) else {
+            return Self {
+                message: raw.clone(),
+                raw,
+                ts: None,
+                level: None,
+            };
+        };
+        let level = match &captures[2] {
+            "VERB" => LogLevel::Verbose,
+            "INFO" => LogLevel::Info,
+            "WARN" => LogLevel::Warn,
+            _ => LogLevel::Error,
+        };
+        Self {
+            ts: Some(captures[1].to_string()),
+            level: Some(level),
+            message: captures[3].to_string(),
+            raw: raw.clone(),
+        }
+    }
+}
+
+/// Lines read from a byte range of a log file. Offsets always fall on line
+/// boundaries, so `end_offset` can be passed back as `from_offset` to fetch only
+/// newer lines, and `start_offset` as `before_offset` to scroll back.
+#[derive(Debug, Serialize)]
+pub struct LogChunk {
+    pub lines: Vec<LogLine>,
+    pub start_offset: u64,
+    pub end_offset: u64,
 }
 
 fn log_dir_for_runner(runner_id: &str) -> PathBuf {
@@ -65,24 +146,554 @@ pub fn list_sources(
             path: latest.to_string_lossy().to_string(),
         });
     }
+    if let Some(unit) = journal_unit(&runner) {
+        sources.push(LogSource {
+            id: JOURNAL_SOURCE.to_string(),
+            label: "Service Journal".to_string(),
+            path: format!("journalctl {} -u {}", unit.scope_flag(), unit.name),
+        });
+    }
     Ok(sources)
 }
 
-pub fn tail(path: &Path, limit: usize) -> Result<Vec<LogLine>, Error> {
-    let contents = match read_file_tail(path, LOG_TAIL_BYTES) {
-        Ok(Some(contents)) => contents,
-        Ok(None) => return Ok(Vec::new()),
+#[derive(Debug, Serialize)]
+pub struct LogMatch {
+    pub source: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// Searches every log source of a runner, stopping after `max_results` matches.
+pub fn search(
+    config: &Config,
+    runner_id: &str,
+    app_log: &Path,
+    query: &str,
+    is_regex: bool,
+    max_results: usize,
+) -> Result<Vec<LogMatch>, Error> {
+    let matcher = build_matcher(query, is_regex)?;
+    let mut matches = Vec::new();
+    for source in list_sources(config, runner_id, app_log)? {
+        if source.id == JOURNAL_SOURCE {
+            continue;
+        }
+        search_file(
+            Path::new(&source.path),
+            &source.id,
+            &matcher,
+            max_results,
+            &mut matches,
+        )?;
+        if matches.len() >= max_results {
+            break;
+        }
+    }
+    Ok(matches)
+}
+
+fn build_matcher(query: &str, is_regex: bool) -> Result<Regex, Error> {
+    if query.is_empty() {
+        return Err(Error::Runner("search query is empty".into()));
+    }
+    let pattern = if is_regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!is_regex)
+        .build()
+        .map_err(|err| Error::Runner(format!("invalid search pattern: {err}")))
+}
+
+// Matches are re-checked after scrubbing so a query cannot fish for redacted secrets.
+fn search_file(
+    path: &Path,
+    source: &str,
+    matcher: &Regex,
+    max_results: usize,
+    matches: &mut Vec<LogMatch>,
+) -> Result<(), Error> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut reader = BufReader::new(file);
+    let mut raw = Vec::new();
+    let mut line_number = 0;
+    while matches.len() < max_results && reader.read_until(b'\n', &mut raw)? > 0 {
+        line_number += 1;
+        let text = String::from_utf8_lossy(&raw);
+        let text = text.trim_end_matches(['\n', '\r']);
+        if matcher.is_match(text) {
+            let line = scrub_sensitive(text);
+            if matcher.is_match(&line) {
+                matches.push(LogMatch {
+                    source: source.to_string(),
+                    line_number,
+                    line,
+                });
+            }
+        }
+        raw.clear();
+    }
+    Ok(())
+}
+
+fn read_range(path: &Path, start: u64, end: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut buf = Vec::new();
+    file.take(end.saturating_sub(start)).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+// Complete (newline-terminated) lines in `buf` with their absolute start/end offsets.
+fn complete_lines(buf: &[u8], base: u64) -> Vec<(u64, u64, String)> {
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    for (index, byte) in buf.iter().enumerate() {
+        if *byte == b'\n' {
+            let len = index + 1 - line_start;
+            lines.push(line_piece(&buf[line_start..index], base + line_start as u64, len));
+            line_start = index + 1;
+        }
+    }
+    lines
+}
+
+// `bytes` starting at offset `start`, spanning `len` bytes of the file.
+fn line_piece(bytes: &[u8], start: u64, len: usize) -> (u64, u64, String) {
+    let text = String::from_utf8_lossy(bytes);
+    (start, start + len as u64, scrub_sensitive(text.trim_end_matches('\r')))
+}
+
+fn file_len(path: &Path) -> Result<Option<u64>, Error> {
+    match fs::metadata(path) {
+        Ok(metadata) => Ok(Some(metadata.len())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn into_chunk(lines: Vec<(u64, u64, String)>, fallback: u64) -> LogChunk {
+    let start_offset = lines.first().map(|line| line.0).unwrap_or(fallback);
+    let end_offset = lines.last().map(|line| line.1).unwrap_or(fallback);
+    LogChunk {
+        lines: lines
+            .into_iter()
+            .map(|(_, _, line)| LogLine::parse(line))
+            .collect(),
+        start_offset,
+        end_offset,
+    }
+}
+
+/// Up to `limit` lines ending at `before_offset` (or the end of the file).
+pub fn read_before(path: &Path, before_offset: Option<u64>, limit: usize) -> Result<LogChunk, Error> {
+    let Some(len) = file_len(path)? else {
+        return Ok(into_chunk(Vec::new(), 0));
+    };
+    let end = before_offset.unwrap_or(len).min(len);
+    let start = end.saturating_sub(LOG_TAIL_BYTES as u64);
+    let at_line_start = start == 0 || read_range(path, start - 1, start)? == b"\n";
+    let buf = read_range(path, start, end)?;
+    let mut lines = complete_lines(&buf, start);
+    // Paging back through a line longer than the window: `end` is inside it, so
+    // the bytes after the last newline are the next piece of that line.
+    let tail = lines.last().map_or(start, |line| line.1);
+    if tail < end && (end < len || lines.is_empty()) {
+        let from = (tail - start) as usize;
+        lines.push(line_piece(&buf[from..], tail, buf.len() - from));
+    }
+    // The window may begin mid-line; that fragment belongs to an older page
+    // unless it is all the window holds.
+    if !at_line_start && lines.len() > 1 {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(limit);
+    Ok(into_chunk(lines.split_off(skip), end))
+}
+
+/// Up to `limit` complete lines appended after `from_offset`. A file that shrank
+/// below the offset was truncated or rotated, so reading restarts at its beginning.
+pub fn read_after(path: &Path, from_offset: u64, limit: usize) -> Result<LogChunk, Error> {
+    let Some(len) = file_len(path)? else {
+        return Ok(into_chunk(Vec::new(), 0));
+    };
+    let start = if from_offset > len { 0 } else { from_offset };
+    let end = len.min(start + LOG_TAIL_BYTES as u64);
+    let buf = read_range(path, start, end)?;
+    let mut lines = complete_lines(&buf, start);
+    // A line longer than the window is handed out in pieces so followers move on.
+    if lines.is_empty() && buf.len() == LOG_TAIL_BYTES {
+        lines.push(line_piece(&buf, start, buf.len()));
+    }
+    lines.truncate(limit);
+    Ok(into_chunk(lines, start))
+}
+
+/// A line of the merged runner timeline, tagged with the source it came from.
+#[derive(Debug, Serialize)]
+pub struct MergedLogLine {
+    pub source: String,
+    #[serde(flatten)]
+    pub line: LogLine,
+}
+
+/// The latest `limit` lines of stdout, stderr and the newest diag log as one
+/// time-ordered list. Lines without a timestamp keep their place after the
+/// preceding line of the same file (or before the first timestamped one).
+pub fn read_merged(config: &Config, runner_id: &str, limit: usize) -> Result<Vec<MergedLogLine>, Error> {
+    let runner = crate::config::find_runner(config, runner_id)?;
+    let log_dir = log_dir_for_runner(runner_id);
+    let mut sources = vec![
+        ("runner-stdout", log_dir.join("runner-stdout.log")),
+        ("runner-stderr", log_dir.join("runner-stderr.log")),
+    ];
+    if let Some(latest) = diag_log_latest(&runner) {
+        sources.push(("runner-diag", latest));
+    }
+    merge_sources(&sources, limit)
+}
+
+fn merge_sources(sources: &[(&str, PathBuf)], limit: usize) -> Result<Vec<MergedLogLine>, Error> {
+    let mut keyed = Vec::new();
+    for (source, path) in sources {
+        let lines = read_before(path, None, limit)?.lines;
+        let mut sort_key = lines.iter().find_map(|line| line.ts.clone());
+        for line in lines {
+            if line.ts.is_some() {
+                sort_key = line.ts.clone();
+            }
+            keyed.push((
+                sort_key.clone(),
+                MergedLogLine {
+                    source: source.to_string(),
+                    line,
+                },
+            ));
+        }
+    }
+    // Runner timestamps are fixed-width UTC, so they order correctly as strings.
+    // Sources without any timestamp go after the last timestamped line, so a
+    // `limit` keeps them, and the stable sort keeps file order among equal keys.
+    keyed.sort_by(|a, b| a.0.is_none().cmp(&b.0.is_none()).then_with(|| a.0.cmp(&b.0)));
+    let skip = keyed.len().saturating_sub(limit);
+    Ok(keyed.into_iter().skip(skip).map(|(_, line)| line).collect())
+}
+
+/// Files cleared or removed by `clear_runner_logs`/`prune_diag_logs`.
+#[derive(Debug, Serialize, Default)]
+pub struct LogCleanup {
+    pub files: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+/// Truncates the RunnerBuddy-managed stdout/stderr logs and, with
+/// `include_rotated`, removes their `.1` rotations. Truncating is safe while a
+/// runner is writing: the files are opened in append mode, so writes continue
+/// at the new end.
+pub fn clear_runner_logs(runner_id: &str, include_rotated: bool) -> Result<LogCleanup, Error> {
+    clear_log_dir(&log_dir_for_runner(runner_id), include_rotated)
+}
+
+fn clear_log_dir(log_dir: &Path, include_rotated: bool) -> Result<LogCleanup, Error> {
+    let mut cleanup = LogCleanup::default();
+    for name in ["runner-stdout.log", "runner-stderr.log"] {
+        let path = log_dir.join(name);
+        if let Some(len) = file_len(&path)?.filter(|len| *len > 0) {
+            OpenOptions::new().write(true).open(&path)?.set_len(0)?;
+            cleanup.freed_bytes += len;
+            cleanup.files.push(name.to_string());
+        }
+        if include_rotated {
+            let rotated = log_dir.join(format!("{name}.1"));
+            if let Some(len) = file_len(&rotated)? {
+                fs::remove_file(&rotated)?;
+                cleanup.freed_bytes += len;
+                cleanup.files.push(format!("{name}.1"));
+            }
+        }
+    }
+    Ok(cleanup)
+}
+
+/// Deletes files in the runner's `_diag` directory last modified more than
+/// `keep_days` ago. While the runner is `live`, the newest file of each log
+/// family (`Runner_`, `Worker_`, ...) is kept since it may still be open.
+pub fn prune_diag_logs(
+    runner: &RunnerProfile,
+    keep_days: u64,
+    live: bool,
+) -> Result<LogCleanup, Error> {
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(keep_days.saturating_mul(86_400)))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    prune_diag_dir(&runner_log_dir(runner), cutoff, live)
+}
+
+fn prune_diag_dir(dir: &Path, cutoff: SystemTime, live: bool) -> Result<LogCleanup, Error> {
+    let mut cleanup = LogCleanup::default();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(cleanup),
         Err(err) => return Err(err.into()),
     };
-    let lines: Vec<LogLine> = contents
+    let mut files = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        let name = entry.file_name().to_string_lossy().to_string();
+        files.push((name, modified, metadata.len()));
+    }
+    let mut newest: HashMap<&str, (SystemTime, &str)> = HashMap::new();
+    if live {
+        for (name, modified, _) in &files {
+            let family = name.split('_').next().unwrap_or(name);
+            let current = newest.entry(family).or_insert((*modified, name));
+            if *modified > current.0 {
+                *current = (*modified, name);
+            }
+        }
+    }
+    let protected: Vec<&str> = newest.values().map(|(_, name)| *name).collect();
+    let mut removable: Vec<_> = files
+        .iter()
+        .filter(|(name, modified, _)| *modified < cutoff && !protected.contains(&name.as_str()))
+        .collect();
+    removable.sort();
+    for (name, _, size) in removable {
+        match fs::remove_file(dir.join(name)) {
+            Ok(()) => {
+                cleanup.freed_bytes += size;
+                cleanup.files.push(name.clone());
+            }
+            Err(err) => warn!("failed to remove diag log {name}: {err}"),
+        }
+    }
+    Ok(cleanup)
+}
+
+struct JournalUnit {
+    name: String,
+    system: bool,
+}
+
+impl JournalUnit {
+    fn scope_flag(&self) -> &'static str {
+        if self.system {
+            "--system"
+        } else {
+            "--user"
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn journal_unit(runner: &RunnerProfile) -> Option<JournalUnit> {
+    let managed = runner.service.provider == crate::config::ServiceProvider::Runnerbuddy;
+    (managed && runner.service.installed).then(|| JournalUnit {
+        name: crate::service_mgmt::unit_name(&runner.runner_id),
+        system: runner.service.system_scope,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn journal_unit(_runner: &RunnerProfile) -> Option<JournalUnit> {
+    None
+}
+
+fn find_journal_unit(config: &Config, runner_id: &str) -> Result<JournalUnit, Error> {
+    let runner = crate::config::find_runner(config, runner_id)?;
+    journal_unit(&runner).ok_or_else(|| {
+        Error::Runner(format!("runner {runner_id} has no RunnerBuddy-managed systemd unit"))
+    })
+}
+
+// Journal entries as (realtime microseconds, scrubbed message). Those timestamps
+// serve as the chunk offsets, so paging works the same as for files.
+fn journal_entries(unit: &JournalUnit, extra: &[String]) -> Result<Vec<(u64, String)>, Error> {
+    let output = std::process::Command::new("journalctl")
+        .arg(unit.scope_flag())
+        .args(["-u", &unit.name, "-o", "json", "--no-pager"])
+        .args(extra)
+        .output()
+        .map_err(|err| Error::Runner(format!("failed to run journalctl: {err}")))?;
+    if !output.status.success() {
+        return Err(Error::Runner(format!(
+            "journalctl failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
         .lines()
-        .rev()
-        .take(limit)
-        .map(|line| LogLine {
-            line: scrub_sensitive(line),
+        .filter_map(parse_journal_entry)
+        .collect())
+}
+
+fn parse_journal_entry(raw: &str) -> Option<(u64, String)> {
+    let entry: serde_json::Value = serde_json::from_str(raw).ok()?;
+    let timestamp = entry.get("__REALTIME_TIMESTAMP")?.as_str()?.parse().ok()?;
+    // Messages with invalid UTF-8 are exported as byte arrays.
+    let message = match entry.get("MESSAGE")? {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(bytes) => {
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .filter_map(|byte| byte.as_u64().map(|byte| byte as u8))
+                .collect();
+            String::from_utf8_lossy(&bytes).to_string()
+        }
+        _ => return None,
+    };
+    Some((timestamp, scrub_sensitive(message.trim_end())))
+}
+
+fn journal_chunk(entries: Vec<(u64, String)>, fallback: u64) -> LogChunk {
+    into_chunk(
+        entries
+            .into_iter()
+            .map(|(timestamp, line)| (timestamp, timestamp + 1, line))
+            .collect(),
+        fallback,
+    )
+}
+
+/// Journal counterpart of `read_before`/`read_after`; offsets are journal timestamps.
+pub fn read_journal(
+    config: &Config,
+    runner_id: &str,
+    from_offset: Option<u64>,
+    before_offset: Option<u64>,
+    limit: usize,
+) -> Result<LogChunk, Error> {
+    let unit = find_journal_unit(config, runner_id)?;
+    read_journal_unit(&unit, from_offset, before_offset, limit)
+}
+
+fn read_journal_unit(
+    unit: &JournalUnit,
+    from_offset: Option<u64>,
+    before_offset: Option<u64>,
+    limit: usize,
+) -> Result<LogChunk, Error> {
+    if let Some(from) = from_offset {
+        let mut entries = journal_entries(unit, &[format!("--since=@{}", from / 1_000_000)])?;
+        entries.retain(|(timestamp, _)| *timestamp >= from);
+        entries.truncate(limit);
+        return Ok(journal_chunk(entries, from));
+    }
+    let mut extra = vec![format!("--lines={limit}")];
+    if let Some(before) = before_offset {
+        extra.push(format!("--until=@{}", before.div_ceil(1_000_000)));
+    }
+    let mut entries = journal_entries(unit, &extra)?;
+    if let Some(before) = before_offset {
+        entries.retain(|(timestamp, _)| *timestamp < before);
+    }
+    let skip = entries.len().saturating_sub(limit);
+    Ok(journal_chunk(entries.split_off(skip), before_offset.unwrap_or(0)))
+}
+
+/// `LogFollower` for the service journal: yields entries newer than the last poll.
+pub struct JournalFollower {
+    unit: JournalUnit,
+    cursor: u64,
+}
+
+impl JournalFollower {
+    pub fn new(config: &Config, runner_id: &str) -> Result<Self, Error> {
+        let cursor = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_micros() as u64)
+            .unwrap_or(0);
+        Ok(Self {
+            unit: find_journal_unit(config, runner_id)?,
+            cursor,
         })
-        .collect();
-    Ok(lines.into_iter().rev().collect())
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<String>, Error> {
+        let chunk = read_journal_unit(&self.unit, Some(self.cursor), None, 1000)?;
+        self.cursor = chunk.end_offset;
+        Ok(chunk.lines.into_iter().map(|line| line.raw).collect())
+    }
+}
+
+/// Incremental reader for `logs_follow_start`: returns lines appended since the last poll.
+pub struct LogFollower {
+    path: PathBuf,
+    offset: u64,
+    identity: Option<u64>,
+    pending: Vec<u8>,
+}
+
+impl LogFollower {
+    /// Starts at the current end of `path`; earlier lines come from `tail`.
+    pub fn new(path: PathBuf) -> Self {
+        let metadata = fs::metadata(&path).ok();
+        Self {
+            offset: metadata.as_ref().map(|meta| meta.len()).unwrap_or(0),
+            identity: metadata.as_ref().and_then(file_identity),
+            path,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<String>, Error> {
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let identity = file_identity(&metadata);
+        // Rotated (new inode) or truncated: read the replacement file from the start.
+        if identity != self.identity || metadata.len() < self.offset {
+            self.offset = 0;
+            self.identity = identity;
+            self.pending.clear();
+        }
+        if metadata.len() == self.offset {
+            return Ok(Vec::new());
+        }
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        self.offset += buf.len() as u64;
+        self.pending.extend_from_slice(&buf);
+        let Some(last_newline) = self.pending.iter().rposition(|byte| *byte == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let complete: Vec<u8> = self.pending.drain(..=last_newline).collect();
+        Ok(String::from_utf8_lossy(&complete)
+            .lines()
+            .map(scrub_sensitive)
+            .collect())
+    }
+}
+
+#[cfg(unix)]
+fn file_identity(metadata: &fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_identity(_metadata: &fs::Metadata) -> Option<u64> {
+    None
 }
 
 pub fn resolve_source_path(
@@ -112,6 +723,43 @@ mod tests {
     use super::*;
     use std::time::{SystemTime, UNIX_EPOCH};
 
+    #[test]
+    fn journal_entries_parse_text_and_byte_messages() {
+        let text = r#"{"__REALTIME_TIMESTAMP":"1700000000123456","MESSAGE":"Listening for Jobs"}"#;
+        assert_eq!(
+            parse_journal_entry(text),
+            Some((1_700_000_000_123_456, "Listening for Jobs".to_string()))
+        );
+        let bytes = r#"{"__REALTIME_TIMESTAMP":"5","MESSAGE":[116,111,107,101,110,32,103,104,112,95,49,50,51,52,53,54,55,56,57,48,97,98]}"#;
+        let (_, line) = parse_journal_entry(bytes).expect("entry");
+        assert!(line.contains("[REDACTED]"));
+        assert!(parse_journal_entry(r#"{"MESSAGE":"no timestamp"}"#).is_none());
+    }
+
+    #[test]
+    fn log_lines_split_diag_format_and_fall_back_to_raw() {
+        let line = LogLine::parse(
+            "[2024-01-15 10:23:45Z ERR  JobDispatcher] Job failed: exit 1".to_string(),
+        );
+        assert_eq!(line.ts.as_deref(), Some("2024-01-15 10:23:45Z"));
+        assert_eq!(line.level, Some(LogLevel::Error));
+        assert_eq!(line.message, "Job failed: exit 1");
+
+        let line = LogLine::parse("[2024-01-15 10:23:45Z INFO Runner] Listening".to_string());
+        assert_eq!(line.level, Some(LogLevel::Info));
+        assert_eq!(line.message, "Listening");
+
+        let line = LogLine::parse("2024-01-15 10:23:46Z: Running job: build".to_string());
+        assert_eq!(line.ts.as_deref(), Some("2024-01-15 10:23:46Z"));
+        assert_eq!(line.level, None);
+        assert_eq!(line.message, "Running job: build");
+
+        let line = LogLine::parse("√ Connected to GitHub".to_string());
+        assert_eq!(line.ts, None);
+        assert_eq!(line.level, None);
+        assert_eq!(line.message, line.raw);
+    }
+
     #[test]
     fn tail_missing_file_returns_empty() {
         let ts = SystemTime::now()
@@ -120,7 +768,196 @@ mod tests {
             .as_nanos();
         let path = std::env::temp_dir().join(format!("runnerbuddy-missing-log-{ts}.log"));
         assert!(!path.exists());
-        let lines = tail(&path, 50).expect("tail should succeed for missing file");
-        assert!(lines.is_empty());
+        let chunk = read_before(&path, None, 50).expect("tail should succeed for missing file");
+        assert!(chunk.lines.is_empty());
+    }
+
+    #[test]
+    fn search_matches_scrubbed_lines_and_caps_results() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("runner-stderr.log");
+        fs::write(
+            &path,
+            "ok\nERROR: disk full\nauth ghp_abcdefghijklmnop\nerror: retry\n",
+        )
+        .expect("write log");
+
+        let mut matches = Vec::new();
+        let matcher = build_matcher("error", false).expect("matcher");
+        search_file(&path, "runner-stderr", &matcher, 10, &mut matches).expect("search");
+        let lines: Vec<_> = matches.iter().map(|m| m.line_number).collect();
+        assert_eq!(lines, [2, 4]);
+
+        let mut matches = Vec::new();
+        let matcher = build_matcher("ghp_[a-z]+", true).expect("matcher");
+        search_file(&path, "runner-stderr", &matcher, 10, &mut matches).expect("search");
+        assert!(matches.is_empty());
+
+        let mut matches = Vec::new();
+        let matcher = build_matcher("r", false).expect("matcher");
+        search_file(&path, "runner-stderr", &matcher, 1, &mut matches).expect("search");
+        assert_eq!(matches.len(), 1);
+
+        assert!(build_matcher("(unclosed", true).is_err());
+        assert!(build_matcher("", false).is_err());
+    }
+
+    #[test]
+    fn merged_sources_interleave_by_timestamp() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let stdout = dir.path().join("runner-stdout.log");
+        let stderr = dir.path().join("runner-stderr.log");
+        let diag = dir.path().join("Runner_1.log");
+        fs::write(
+            &stdout,
+            "2024-01-15 10:00:01Z: Listening for Jobs\n2024-01-15 10:00:05Z: Running job: build\n",
+        )
+        .expect("write stdout");
+        fs::write(&stderr, "Unhandled exception\n   at Worker.Run()\n").expect("write stderr");
+        fs::write(
+            &diag,
+            "[2024-01-15 10:00:03Z INFO JobDispatcher] Job request\ncontinued detail\n[2024-01-15 10:00:07Z ERR  Worker] Job failed\n",
+        )
+        .expect("write diag");
+        let sources = [
+            ("runner-stdout", stdout),
+            ("runner-stderr", stderr),
+            ("runner-diag", diag),
+        ];
+
+        let merged = merge_sources(&sources, 100).expect("merge");
+        let order: Vec<_> = merged
+            .iter()
+            .map(|line| (line.source.as_str(), line.line.message.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("runner-stdout", "Listening for Jobs"),
+                ("runner-diag", "Job request"),
+                ("runner-diag", "continued detail"),
+                ("runner-stdout", "Running job: build"),
+                ("runner-diag", "Job failed"),
+                ("runner-stderr", "Unhandled exception"),
+                ("runner-stderr", "   at Worker.Run()"),
+            ]
+        );
+
+        let latest = merge_sources(&sources, 3).expect("merge");
+        let latest: Vec<_> = latest.iter().map(|line| line.line.message.as_str()).collect();
+        assert_eq!(latest, ["Job failed", "Unhandled exception", "   at Worker.Run()"]);
+    }
+
+    #[test]
+    fn clearing_truncates_logs_and_optionally_drops_rotations() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(dir.path().join("runner-stdout.log"), "12345").expect("write stdout");
+        fs::write(dir.path().join("runner-stdout.log.1"), "123").expect("write rotated");
+        fs::write(dir.path().join("runner-stderr.log"), "").expect("write stderr");
+
+        let kept = clear_log_dir(dir.path(), false).expect("clear");
+        assert_eq!(kept.files, ["runner-stdout.log"]);
+        assert_eq!(kept.freed_bytes, 5);
+        assert!(dir.path().join("runner-stdout.log.1").exists());
+
+        let all = clear_log_dir(dir.path(), true).expect("clear");
+        assert_eq!(all.files, ["runner-stdout.log.1"]);
+        assert_eq!(all.freed_bytes, 3);
+        assert_eq!(fs::metadata(dir.path().join("runner-stdout.log")).expect("stdout").len(), 0);
+    }
+
+    #[test]
+    fn diag_prune_spares_the_newest_file_per_family_while_live() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        for name in ["Runner_1.log", "Runner_2.log", "Worker_1.log"] {
+            fs::write(dir.path().join(name), "log").expect("write diag");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        let cutoff = SystemTime::now() + Duration::from_secs(60);
+
+        let live = prune_diag_dir(dir.path(), cutoff, true).expect("prune");
+        assert_eq!(live.files, ["Runner_1.log"]);
+        assert_eq!(live.freed_bytes, 3);
+
+        let stopped = prune_diag_dir(dir.path(), cutoff, false).expect("prune");
+        assert_eq!(stopped.files, ["Runner_2.log", "Worker_1.log"]);
+
+        let untouched = prune_diag_dir(dir.path(), SystemTime::UNIX_EPOCH, false).expect("prune");
+        assert!(untouched.files.is_empty());
+    }
+
+    #[test]
+    fn chunks_page_forward_and_backward_by_offset() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("runner-stdout.log");
+        fs::write(&path, "one\ntwo\nthree\nfour\npartial").expect("write log");
+
+        let latest = read_before(&path, None, 2).expect("read latest");
+        let text: Vec<_> = latest.lines.iter().map(|line| line.raw.as_str()).collect();
+        assert_eq!(text, ["three", "four"]);
+        assert_eq!(latest.end_offset, 19);
+
+        let older = read_before(&path, Some(latest.start_offset), 10).expect("read older");
+        let text: Vec<_> = older.lines.iter().map(|line| line.raw.as_str()).collect();
+        assert_eq!(text, ["one", "two"]);
+        assert_eq!(older.start_offset, 0);
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).expect("open log");
+        std::io::Write::write_all(&mut file, b" done\nfive\n").expect("append");
+        let newer = read_after(&path, latest.end_offset, 1).expect("read newer");
+        let text: Vec<_> = newer.lines.iter().map(|line| line.raw.as_str()).collect();
+        assert_eq!(text, ["partial done"]);
+        let rest = read_after(&path, newer.end_offset, 10).expect("read rest");
+        assert_eq!(rest.lines.len(), 1);
+        assert_eq!(rest.lines[0].raw, "five");
+    }
+
+    #[test]
+    fn lines_longer_than_the_window_are_paged_in_pieces() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("runner-stdout.log");
+        let long = "x".repeat(LOG_TAIL_BYTES + 10);
+        fs::write(&path, format!("first\n{long}\nlast\n")).expect("write log");
+        let long_start = "first\n".len() as u64;
+
+        let latest = read_before(&path, None, 10).expect("read latest");
+        let text: Vec<_> = latest.lines.iter().map(|line| line.raw.as_str()).collect();
+        assert_eq!(text, ["last"]);
+        let middle = read_before(&path, Some(latest.start_offset), 10).expect("read middle");
+        assert_eq!(middle.lines.len(), 1);
+        assert_eq!(middle.lines[0].raw.len(), LOG_TAIL_BYTES - 1);
+        let oldest = read_before(&path, Some(middle.start_offset), 10).expect("read oldest");
+        let text: Vec<_> = oldest.lines.iter().map(|line| line.raw.as_str()).collect();
+        assert_eq!(text, ["first", "x".repeat(11).as_str()]);
+        assert_eq!(oldest.start_offset, 0);
+
+        let head = read_after(&path, long_start, 10).expect("read long head");
+        assert_eq!(head.lines.len(), 1);
+        assert_eq!(head.lines[0].raw.len(), LOG_TAIL_BYTES);
+        assert_eq!(head.end_offset, long_start + LOG_TAIL_BYTES as u64);
+        let rest = read_after(&path, head.end_offset, 10).expect("read rest");
+        let text: Vec<_> = rest.lines.iter().map(|line| line.raw.as_str()).collect();
+        assert_eq!(text, ["x".repeat(10).as_str(), "last"]);
+    }
+
+    #[test]
+    fn follower_reads_appended_lines_and_survives_rotation() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("runner-stdout.log");
+        fs::write(&path, "before follow\n").expect("write log");
+        let mut follower = LogFollower::new(path.clone());
+        assert!(follower.poll().expect("poll").is_empty());
+
+        let mut file = fs::OpenOptions::new().append(true).open(&path).expect("open log");
+        write!(file, "first\nsecond par").expect("append");
+        assert_eq!(follower.poll().expect("poll"), vec!["first".to_string()]);
+        writeln!(file, "tial").expect("append");
+        assert_eq!(follower.poll().expect("poll"), vec!["second partial".to_string()]);
+
+        fs::rename(&path, dir.path().join("runner-stdout.log.1")).expect("rotate");
+        fs::write(&path, "fresh\n").expect("write new log");
+        assert_eq!(follower.poll().expect("poll"), vec!["fresh".to_string()]);
     }
 }
@@ -0,0 +1,178 @@
+use crate::config::{Config, RunnerProfile};
+use crate::errors::Error;
+use crate::state::{AppState, RunnerStatus, RuntimeState};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tracing::{info, warn};
+
+const ACCEPT_POLL: Duration = Duration::from_millis(250);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A running `/metrics` listener; dropping it does not stop the thread, `stop` does.
+pub struct MetricsServer {
+    pub port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+impl MetricsServer {
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Binds `127.0.0.1:<port>` and serves Prometheus text exposition on `/metrics`.
+pub fn start<R: Runtime>(app: AppHandle<R>, port: u16) -> Result<MetricsServer, Error> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    listener.set_nonblocking(true)?;
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_flag = stop.clone();
+    std::thread::spawn(move || {
+        while !stop_flag.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(err) = handle_request(&app, stream) {
+                        warn!("metrics request failed: {err}");
+                    }
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(ACCEPT_POLL);
+                }
+                Err(err) => {
+                    warn!("metrics listener error: {err}");
+                    std::thread::sleep(ACCEPT_POLL);
+                }
+            }
+        }
+        info!("Metrics endpoint on port {port} stopped");
+    });
+    info!("Metrics endpoint listening on http://127.0.0.1:{port}/metrics");
+    Ok(MetricsServer { port, stop })
+}
+
+fn handle_request<R: Runtime>(app: &AppHandle<R>, mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+    // Only the request line matters; headers and bodies are ignored.
+    let mut buf = [0u8; 1024];
+    let read = stream.read(&mut buf)?;
+    let request = String::from_utf8_lossy(&buf[..read]);
+    let mut parts = request.lines().next().unwrap_or_default().split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/metrics")) => {
+            let state = app.state::<AppState>();
+            let config = state.config.get();
+            let runtime = state.runtime.lock().expect("runtime mutex poisoned").clone();
+            (
+                "200 OK",
+                "text/plain; version=0.0.4; charset=utf-8",
+                render(&config, &runtime),
+            )
+        }
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "method not allowed\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// One runner's value in a metric family.
+struct Sample {
+    /// Labels beyond `runner_id` and `name`.
+    labels: Vec<(&'static str, String)>,
+    value: u64,
+}
+
+impl Sample {
+    fn plain(value: u64) -> Option<Self> {
+        Some(Self {
+            labels: Vec::new(),
+            value,
+        })
+    }
+}
+
+/// A metric family with the function that reads each runner's value, so a
+/// family's name and its value are defined in one place.
+struct Family {
+    name: &'static str,
+    kind: &'static str,
+    help: &'static str,
+    sample: fn(&RunnerProfile, &RuntimeState) -> Option<Sample>,
+}
+
+const FAMILIES: [Family; 4] = [
+    Family {
+        name: "runnerbuddy_runner_up",
+        kind: "gauge",
+        help: "Whether the runner process or service is up.",
+        sample: |_, current| Sample::plain(u64::from(current.status != RunnerStatus::Offline)),
+    },
+    Family {
+        name: "runnerbuddy_runner_running",
+        kind: "gauge",
+        help: "Whether the runner is executing a job.",
+        sample: |_, current| Sample::plain(u64::from(current.status == RunnerStatus::Running)),
+    },
+    Family {
+        name: "runnerbuddy_jobs_completed_total",
+        kind: "counter",
+        help: "Jobs completed, as counted from the runner diag logs.",
+        sample: |_, current| Sample::plain(current.jobs_completed),
+    },
+    Family {
+        name: "runnerbuddy_runner_version_info",
+        kind: "gauge",
+        help: "Installed runner version.",
+        sample: |runner, _| {
+            runner.runner_version.as_ref().map(|version| Sample {
+                labels: vec![("version", version.clone())],
+                value: 1,
+            })
+        },
+    },
+];
+
+/// Prometheus text exposition of the runtime state the status monitor keeps current.
+pub fn render(config: &Config, runtime: &HashMap<String, RuntimeState>) -> String {
+    let mut out = String::new();
+    for family in FAMILIES.iter() {
+        let name = family.name;
+        let _ = writeln!(out, "# HELP {name} {}", family.help);
+        let _ = writeln!(out, "# TYPE {name} {}", family.kind);
+        for runner in config.runners.iter() {
+            let current = runtime.get(&runner.runner_id).cloned().unwrap_or_default();
+            let Some(sample) = (family.sample)(runner, &current) else {
+                continue;
+            };
+            let mut labels = format!(
+                "runner_id=\"{}\",name=\"{}\"",
+                escape_label(&runner.runner_id),
+                escape_label(&runner.display_name)
+            );
+            for (key, value) in sample.labels {
+                let _ = write!(labels, ",{key}=\"{}\"", escape_label(&value));
+            }
+            let _ = writeln!(out, "{name}{{{labels}}} {}", sample.value);
+        }
+    }
+    out
+}
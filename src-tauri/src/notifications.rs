@@ -0,0 +1,195 @@
+use crate::config::SettingsConfig;
+use crate::state::{CurrentJob, RunnerStatus, RuntimeState};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_notification::NotificationExt;
+use tracing::warn;
+
+/// Minimum gap between two notifications of the same kind for one runner, so a
+/// flapping runner cannot flood the desktop.
+const MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationKind {
+    JobStarted,
+    JobFinished,
+    RunnerError,
+}
+
+impl NotificationKind {
+    pub fn enabled(self, settings: &SettingsConfig) -> bool {
+        match self {
+            Self::JobStarted => settings.notify_job_started,
+            Self::JobFinished => settings.notify_job_finished,
+            Self::RunnerError => settings.notify_runner_errors,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+}
+
+/// What the runtime looked like before `update_runtime` changed it.
+pub struct PreviousRuntime {
+    pub status: RunnerStatus,
+    pub job: Option<CurrentJob>,
+    pub error: Option<String>,
+}
+
+impl PreviousRuntime {
+    pub fn of(runtime: &RuntimeState) -> Self {
+        Self {
+            status: runtime.status,
+            job: runtime.current_job.clone(),
+            error: runtime.last_error.clone(),
+        }
+    }
+}
+
+/// Notifications implied by a runtime change, before settings and rate limits apply.
+pub fn transition_notifications(
+    runner_name: &str,
+    previous: &PreviousRuntime,
+    current: &RuntimeState,
+) -> Vec<Notification> {
+    let mut notifications = Vec::new();
+    let job_label = |job: Option<&CurrentJob>| {
+        job.map(|job| format!("{runner_name}: {}", job.name))
+            .unwrap_or_else(|| runner_name.to_string())
+    };
+    match (previous.status, current.status) {
+        (RunnerStatus::Idle, RunnerStatus::Running) => notifications.push(Notification {
+            kind: NotificationKind::JobStarted,
+            title: "Job started".to_string(),
+            body: job_label(current.current_job.as_ref()),
+        }),
+        (RunnerStatus::Running, RunnerStatus::Idle) => notifications.push(Notification {
+            kind: NotificationKind::JobFinished,
+            title: "Job finished".to_string(),
+            body: job_label(previous.job.as_ref()),
+        }),
+        _ => {}
+    }
+    if let Some(error) = current.last_error.as_deref() {
+        if previous.error.as_deref() != Some(error) {
+            notifications.push(Notification {
+                kind: NotificationKind::RunnerError,
+                title: format!("{runner_name} failed"),
+                body: error.to_string(),
+            });
+        }
+    }
+    notifications
+}
+
+#[derive(Default)]
+pub struct NotifyLimiter {
+    last_sent: HashMap<(String, NotificationKind), Instant>,
+}
+
+impl NotifyLimiter {
+    pub fn allow(&mut self, runner_id: &str, kind: NotificationKind, now: Instant) -> bool {
+        let key = (runner_id.to_string(), kind);
+        if let Some(last) = self.last_sent.get(&key) {
+            if now.duration_since(*last) < MIN_INTERVAL {
+                return false;
+            }
+        }
+        self.last_sent.insert(key, now);
+        true
+    }
+
+    pub fn forget(&mut self, runner_id: &str) {
+        self.last_sent.retain(|(id, _), _| id != runner_id);
+    }
+}
+
+pub fn show<R: Runtime>(app: &AppHandle<R>, notification: &Notification) {
+    if let Err(err) = app
+        .notification()
+        .builder()
+        .title(&notification.title)
+        .body(&notification.body)
+        .show()
+    {
+        warn!("failed to show notification: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime(status: RunnerStatus, job: Option<&str>, error: Option<&str>) -> RuntimeState {
+        RuntimeState {
+            status,
+            current_job: job.map(|name| CurrentJob {
+                name: name.to_string(),
+                run_id: None,
+                url: None,
+            }),
+            last_error: error.map(str::to_string),
+            ..RuntimeState::default()
+        }
+    }
+
+    fn previous(status: RunnerStatus, job: Option<&str>, error: Option<&str>) -> PreviousRuntime {
+        let state = runtime(status, job, error);
+        PreviousRuntime {
+            status: state.status,
+            job: state.current_job,
+            error: state.last_error,
+        }
+    }
+
+    #[test]
+    fn transitions_map_to_notifications() {
+        let started = transition_notifications(
+            "ci-1",
+            &previous(RunnerStatus::Idle, None, None),
+            &runtime(RunnerStatus::Running, Some("build"), None),
+        );
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].kind, NotificationKind::JobStarted);
+        assert_eq!(started[0].body, "ci-1: build");
+
+        let finished = transition_notifications(
+            "ci-1",
+            &previous(RunnerStatus::Running, Some("build"), None),
+            &runtime(RunnerStatus::Idle, None, None),
+        );
+        assert_eq!(finished[0].kind, NotificationKind::JobFinished);
+        assert_eq!(finished[0].body, "ci-1: build");
+
+        let crashed = transition_notifications(
+            "ci-1",
+            &previous(RunnerStatus::Running, Some("build"), None),
+            &runtime(RunnerStatus::Offline, None, Some("exit code 1")),
+        );
+        assert_eq!(crashed.len(), 1);
+        assert_eq!(crashed[0].kind, NotificationKind::RunnerError);
+
+        let same_error = transition_notifications(
+            "ci-1",
+            &previous(RunnerStatus::Offline, None, Some("exit code 1")),
+            &runtime(RunnerStatus::Offline, None, Some("exit code 1")),
+        );
+        assert!(same_error.is_empty());
+    }
+
+    #[test]
+    fn limiter_suppresses_repeats_per_runner_and_kind() {
+        let mut limiter = NotifyLimiter::default();
+        let now = Instant::now();
+        assert!(limiter.allow("a", NotificationKind::RunnerError, now));
+        assert!(!limiter.allow("a", NotificationKind::RunnerError, now + Duration::from_secs(5)));
+        assert!(limiter.allow("a", NotificationKind::JobStarted, now));
+        assert!(limiter.allow("b", NotificationKind::RunnerError, now));
+        assert!(limiter.allow("a", NotificationKind::RunnerError, now + MIN_INTERVAL));
+    }
+}
@@ -1,18 +1,21 @@
 use crate::config::{ConfigStore, InstallMode, RunnerProfile, RunnerScope};
 use crate::errors::Error;
-use crate::github_api;
+use crate::github_api::{self, RegistrationTokenCache};
+use crate::http;
 use crate::logging::scrub_sensitive;
-use crate::secrets;
 use crate::discovery;
 use crate::util::{expand_path, normalize_labels, read_file_tail, LOG_TAIL_BYTES};
 use futures_util::StreamExt;
 use sha2::Digest;
 use serde::Deserialize;
-use std::collections::HashMap;
-use std::fs::{self, File};
-use std::io::{self, Write};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Runtime};
 use tracing::{info, warn};
 
@@ -39,6 +42,94 @@ impl RunnerPlatform {
     fn asset_prefix(&self) -> String {
         format!("actions-runner-{}-{}", self.os, self.arch)
     }
+
+    fn asset_name(&self, version: &str) -> String {
+        format!("{}-{}.{}", self.asset_prefix(), version, self.ext)
+    }
+}
+
+const PRESERVED_ENTRIES: [&str; 8] = [
+    ".runner",
+    ".credentials",
+    ".credentials_rsaparams",
+    ".env",
+    ".path",
+    ".service",
+    "_work",
+    "_diag",
+];
+
+/// The runner reads any `--<arg>` from `ACTIONS_RUNNER_INPUT_<ARG>` and clears it
+/// after parsing, which keeps tokens out of argv where `ps` would show them.
+const TOKEN_ENV: &str = "ACTIONS_RUNNER_INPUT_TOKEN";
+/// `--jitconfig` holds the runner's credentials, so it goes the same way.
+const JIT_CONFIG_ENV: &str = "ACTIONS_RUNNER_INPUT_JITCONFIG";
+/// Download progress events are throttled to a few per second.
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, serde::Serialize)]
+pub struct RunnerUpdateInfo {
+    pub current: Option<String>,
+    pub latest: String,
+    pub update_available: bool,
+    pub pinned: bool,
+}
+
+pub async fn check_runner_update(
+    config_store: &ConfigStore,
+    runner_id: &str,
+) -> Result<RunnerUpdateInfo, Error> {
+    let profile = get_runner_profile(config_store, runner_id)?;
+    let settings = config_store.get().settings;
+    let latest = match (mirror_base(&settings), settings.runner_download_version.as_deref()) {
+        (Some(_), Some(version)) => normalize_version(version),
+        _ => normalize_version(&fetch_release(None).await?.tag_name),
+    };
+    let current = profile.runner_version.as_deref().map(normalize_version);
+    let update_available = !profile.version_pinned
+        && current
+            .as_deref()
+            .map(|current| version_is_newer(&latest, current))
+            .unwrap_or(false);
+    Ok(RunnerUpdateInfo {
+        current,
+        latest,
+        update_available,
+        pinned: profile.version_pinned,
+    })
+}
+
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map(|part| part.trim().parse::<u64>().unwrap_or(0))
+        .collect()
+}
+
+pub(crate) fn version_is_newer(candidate: &str, current: &str) -> bool {
+    version_parts(candidate) > version_parts(current)
+}
+
+/// Pinned runners refuse in-place updates.
+pub fn ensure_not_pinned(profile: &RunnerProfile) -> Result<(), Error> {
+    if profile.version_pinned {
+        return Err(Error::Runner(format!(
+            "runner is pinned to version {}; unpin it before updating",
+            profile.runner_version.as_deref().unwrap_or("unknown")
+        )));
+    }
+    Ok(())
+}
+
+pub async fn update_runner<R: Runtime>(
+    app: &AppHandle<R>,
+    config_store: &ConfigStore,
+    runner_id: &str,
+    cancel: Option<&AtomicBool>,
+) -> Result<RunnerProfile, Error> {
+    let profile = get_runner_profile(config_store, runner_id)?;
+    ensure_not_pinned(&profile)?;
+    download_runner(app, config_store, runner_id, None, None, cancel).await
 }
 
 pub async fn download_runner<R: Runtime>(
@@ -46,60 +137,448 @@ pub async fn download_runner<R: Runtime>(
     config_store: &ConfigStore,
     runner_id: &str,
     version: Option<String>,
+    expected_sha256: Option<String>,
+    cancel: Option<&AtomicBool>,
 ) -> Result<RunnerProfile, Error> {
     let profile = get_runner_profile(config_store, runner_id)?;
+    let expected_sha256 = expected_sha256
+        .map(|hash| normalize_sha256(&hash))
+        .transpose()?;
     if matches!(profile.install.mode, InstallMode::Adopted) {
         return Err(Error::Runner(
             "cannot download runner for adopted install".into(),
         ));
     }
+    let pinned = profile
+        .runner_version
+        .as_deref()
+        .filter(|_| profile.version_pinned);
+    let (version, pin_warning) = pinned_download_version(pinned, version);
+    if let Some(warning) = &pin_warning {
+        warn!("Runner {runner_id}: {warning}");
+    }
     let platform = detect_platform()?;
-    let release = fetch_release(version).await?;
-    let version = normalize_version(&release.tag_name);
-    let asset_name = format!(
-        "{}-{}.{}",
-        platform.asset_prefix(),
-        version,
-        platform.ext
-    );
-    let (asset_url, sha_url) = find_asset_urls(&release.assets, &asset_name)?;
-    let download_dir = crate::config::data_dir()?.join("downloads");
+    let settings = config_store.get().settings;
+    let (version, asset_name, asset_url, sha_url) = match mirror_base(&settings) {
+        Some(base) => {
+            let version = match version.or(settings.runner_download_version.clone()) {
+                Some(version) => normalize_version(&version),
+                None => normalize_version(&fetch_release(None).await?.tag_name),
+            };
+            let asset_name = platform.asset_name(&version);
+            let asset_url = mirror_url(base, &version, &asset_name);
+            let sha_url = format!("{asset_url}.sha256");
+            (version, asset_name, asset_url, Some(sha_url))
+        }
+        None => {
+            let release = fetch_release(version).await?;
+            let version = normalize_version(&release.tag_name);
+            let asset_name = platform.asset_name(&version);
+            let (asset_url, sha_url) = find_asset_urls(&release.assets, &asset_name)?;
+            (version, asset_name, asset_url, sha_url)
+        }
+    };
+    let download_dir = downloads_dir()?;
     fs::create_dir_all(&download_dir)?;
     let archive_path = download_dir.join(&asset_name);
+    let _lease = ArchiveLease::acquire(&archive_path)?;
     info!("Downloading runner {version} for {runner_id}");
-    download_with_progress(app, runner_id, &asset_url, &archive_path).await?;
-    if let Some(sha_url) = sha_url {
-        verify_sha256(&sha_url, &archive_path).await?;
+    download_with_progress(
+        app,
+        runner_id,
+        &asset_url,
+        &archive_path,
+        pin_warning.as_deref(),
+        cancel,
+    )
+    .await?;
+    // An out-of-band hash wins over the published `.sha256`.
+    let published = match (&expected_sha256, sha_url) {
+        (None, Some(sha_url)) => fetch_sha256(&sha_url).await?,
+        _ => None,
+    };
+    let expected = match (expected_sha256, published) {
+        (Some(expected), _) => Some(expected),
+        (None, Some(published)) => Some(published),
+        (None, None) if settings.require_checksum => {
+            let _ = fs::remove_file(&archive_path);
+            return Err(Error::Runner("no checksum available".into()));
+        }
+        (None, None) => {
+            warn!("no checksum published for {asset_name}; skipping verification");
+            None
+        }
+    };
+    if let Some(expected) = expected {
+        verify_sha256(&archive_path, &expected)?;
     }
     let install_path = expand_path(&profile.install.install_path);
-    if install_path.exists() {
-        warn!(
-            "install path {:?} exists; clearing before extraction",
-            install_path
-        );
-        fs::remove_dir_all(&install_path)?;
+    install_archive(&archive_path, &install_path, &platform)?;
+    if settings.downloads_keep > 0 {
+        if let Err(err) = prune_downloads(&download_dir, settings.downloads_keep as usize) {
+            warn!("failed to prune runner downloads: {err}");
+        }
     }
-    fs::create_dir_all(&install_path)?;
-    extract_archive(&archive_path, &install_path)?;
     config_store.update_runner(runner_id, |runner| {
         runner.runner_version = Some(version.to_string());
         runner.install.install_path = install_path.to_string_lossy().to_string();
     })
 }
 
-pub async fn configure_runner(
+pub fn downloads_dir() -> Result<PathBuf, Error> {
+    Ok(crate::config::data_dir()?.join("downloads"))
+}
+
+/// Archives being downloaded, verified or extracted; pruning never touches them.
+fn active_archives() -> &'static Mutex<HashSet<PathBuf>> {
+    static ACTIVE: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+    ACTIVE.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+struct ArchiveLease(PathBuf);
+
+impl ArchiveLease {
+    fn acquire(path: &Path) -> Result<Self, Error> {
+        let mut active = active_archives().lock().expect("active archives poisoned");
+        if !active.insert(path.to_path_buf()) {
+            return Err(Error::Runner(format!(
+                "{} is already being downloaded",
+                path.display()
+            )));
+        }
+        Ok(Self(path.to_path_buf()))
+    }
+}
+
+impl Drop for ArchiveLease {
+    fn drop(&mut self) {
+        active_archives()
+            .lock()
+            .expect("active archives poisoned")
+            .remove(&self.0);
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DownloadedArchive {
+    pub name: String,
+    pub size_bytes: u64,
+    pub modified: Option<u64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct DownloadsUsage {
+    pub path: String,
+    pub total_bytes: u64,
+    /// Newest first.
+    pub archives: Vec<DownloadedArchive>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct PruneResult {
+    pub removed: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+/// Archive files in `dir`, newest first.
+fn downloaded_archives(dir: &Path) -> Result<Vec<(PathBuf, DownloadedArchive)>, Error> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut archives = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs());
+        archives.push((
+            entry.path(),
+            DownloadedArchive {
+                name: entry.file_name().to_string_lossy().to_string(),
+                size_bytes: metadata.len(),
+                modified,
+            },
+        ));
+    }
+    archives.sort_by(|a, b| b.1.modified.cmp(&a.1.modified).then_with(|| a.1.name.cmp(&b.1.name)));
+    Ok(archives)
+}
+
+pub fn downloads_usage(dir: &Path) -> Result<DownloadsUsage, Error> {
+    let archives: Vec<DownloadedArchive> = downloaded_archives(dir)?
+        .into_iter()
+        .map(|(_, archive)| archive)
+        .collect();
+    Ok(DownloadsUsage {
+        path: dir.to_string_lossy().to_string(),
+        total_bytes: archives.iter().map(|archive| archive.size_bytes).sum(),
+        archives,
+    })
+}
+
+/// Deletes all but the `keep` newest archives, skipping any that are in use.
+pub fn prune_downloads(dir: &Path, keep: usize) -> Result<PruneResult, Error> {
+    let mut result = PruneResult {
+        removed: Vec::new(),
+        freed_bytes: 0,
+    };
+    for (path, archive) in downloaded_archives(dir)?.into_iter().skip(keep) {
+        if active_archives()
+            .lock()
+            .expect("active archives poisoned")
+            .contains(&path)
+        {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                result.freed_bytes += archive.size_bytes;
+                result.removed.push(archive.name);
+            }
+            Err(err) => warn!("failed to remove {:?}: {err}", path),
+        }
+    }
+    if !result.removed.is_empty() {
+        info!(
+            "Pruned {} runner download(s), freed {} bytes",
+            result.removed.len(),
+            result.freed_bytes
+        );
+    }
+    Ok(result)
+}
+
+/// A pinned runner reinstalls its pinned version unless another one is asked for
+/// explicitly. That is allowed, but comes back with a warning for the caller.
+fn pinned_download_version(
+    pinned: Option<&str>,
+    requested: Option<String>,
+) -> (Option<String>, Option<String>) {
+    match (pinned, requested) {
+        (Some(pinned), None) => (Some(pinned.to_string()), None),
+        (Some(pinned), Some(requested)) => {
+            let warning = (normalize_version(&requested) != normalize_version(pinned)).then(|| {
+                format!("runner is pinned to {pinned}; installing {requested} as requested")
+            });
+            (Some(requested), warning)
+        }
+        (None, requested) => (requested, None),
+    }
+}
+
+/// Runs the runner's `bin/installdependencies.sh` (as root, through non-interactive
+/// `sudo` when needed) and streams its output as `dependencies` progress events.
+#[cfg(target_os = "linux")]
+pub async fn install_dependencies<R: Runtime>(
+    app: &AppHandle<R>,
     config_store: &ConfigStore,
     runner_id: &str,
-    scope: RunnerScope,
-    name: String,
-    labels: Vec<String>,
-    work_dir: String,
-) -> Result<RunnerProfile, Error> {
+) -> Result<(), Error> {
+    use std::io::BufRead;
+
     let profile = get_runner_profile(config_store, runner_id)?;
-    let pat = secrets::load_pat(&profile.pat_alias)?.ok_or_else(|| {
-        Error::Runner("no PAT found in credential store; save a token first".into())
-    })?;
-    let token = github_api::get_registration_token(&scope, &pat).await?;
+    let script = expand_path(&profile.install.install_path)
+        .join("bin")
+        .join("installdependencies.sh");
+    if !script.exists() {
+        return Err(Error::Runner(format!(
+            "{} not found; download the runner first",
+            script.display()
+        )));
+    }
+    let mut command = if crate::service_mgmt::is_root() {
+        Command::new("sh")
+    } else {
+        let mut command = Command::new("sudo");
+        command.arg("-n").arg("sh");
+        command
+    };
+    // Fold stderr into stdout so the package manager output arrives in order.
+    command
+        .arg("-c")
+        .arg("\"$0\" 2>&1")
+        .arg(&script)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    info!("Installing runner dependencies for {runner_id}");
+    let emit = |percent: u64, message: Option<String>| {
+        let _ = app.emit(
+            "progress",
+            ProgressPayload {
+                runner_id: runner_id.to_string(),
+                phase: "dependencies".to_string(),
+                percent,
+                message,
+                ..Default::default()
+            },
+        );
+    };
+    emit(0, None);
+    let mut child = command.spawn()?;
+    let stdout = child.stdout.take().expect("piped stdout");
+    let app_handle = app.clone();
+    let runner = runner_id.to_string();
+    let status = tauri::async_runtime::spawn_blocking(move || {
+        for line in io::BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = app_handle.emit(
+                "progress",
+                ProgressPayload {
+                    runner_id: runner.clone(),
+                    phase: "dependencies".to_string(),
+                    percent: 0,
+                    message: Some(scrub_sensitive(&line)),
+                    ..Default::default()
+                },
+            );
+        }
+        child.wait()
+    })
+    .await
+    .map_err(|err| Error::Runner(format!("dependency install task failed: {err}")))??;
+    if !status.success() {
+        return Err(Error::Runner(format!(
+            "installdependencies.sh failed ({status}); it needs root or passwordless sudo, or run `sudo {}` yourself",
+            script.display()
+        )));
+    }
+    emit(100, None);
+    Ok(())
+}
+
+/// .NET aborts with an obscure globalization error when libicu is missing, so
+/// catch it before `config.sh` does. Invariant globalization mode skips the check.
+#[cfg(target_os = "linux")]
+fn check_libicu(profile: &RunnerProfile) -> Result<(), Error> {
+    let invariant = profile
+        .env
+        .get("DOTNET_SYSTEM_GLOBALIZATION_INVARIANT")
+        .is_some_and(|value| value == "1" || value.eq_ignore_ascii_case("true"));
+    if invariant || has_libicu() {
+        return Ok(());
+    }
+    Err(Error::Runner(
+        "libicu is not installed, which the runner needs to start. Use \"Install dependencies\" or run `sudo ./bin/installdependencies.sh` in the runner directory, then configure again".into(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn has_libicu() -> bool {
+    if let Ok(output) = Command::new("ldconfig").arg("-p").output() {
+        if output.status.success() {
+            return ldconfig_lists_libicu(&String::from_utf8_lossy(&output.stdout));
+        }
+    }
+    [
+        "/usr/lib",
+        "/usr/lib64",
+        "/lib",
+        "/lib64",
+        "/usr/lib/x86_64-linux-gnu",
+        "/usr/lib/aarch64-linux-gnu",
+        "/usr/lib/arm-linux-gnueabihf",
+    ]
+    .iter()
+    .filter_map(|dir| fs::read_dir(dir).ok())
+    .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+    .any(|entry| entry.file_name().to_string_lossy().starts_with("libicuuc.so"))
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn ldconfig_lists_libicu(output: &str) -> bool {
+    output
+        .lines()
+        .any(|line| line.trim_start().starts_with("libicuuc.so"))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfigureOptions {
+    pub scope: RunnerScope,
+    pub name: String,
+    pub labels: Vec<String>,
+    pub work_dir: String,
+    #[serde(default)]
+    pub runner_group: Option<String>,
+    #[serde(default)]
+    pub ephemeral: bool,
+    /// Skip `config.sh`; each start requests a just-in-time config instead.
+    #[serde(default)]
+    pub jit: bool,
+}
+
+impl ConfigureOptions {
+    /// Options that re-register a runner exactly as its profile describes.
+    pub fn from_profile(profile: &RunnerProfile) -> Option<Self> {
+        Some(Self {
+            scope: profile.scope.clone()?,
+            name: profile.runner_name.clone(),
+            labels: profile.labels.clone(),
+            work_dir: profile.work_dir.clone(),
+            runner_group: profile.runner_group.clone(),
+            ephemeral: profile.ephemeral,
+            jit: profile.jit,
+        })
+    }
+}
+
+/// Fails early when the local clock is far enough off GitHub's that config.sh would
+/// be rejected with an opaque token error; a failed probe is only logged.
+async fn ensure_clock_in_sync(api_base: &str) -> Result<(), Error> {
+    match github_api::check_api_base(api_base).await {
+        Ok(Some(skew)) => match github_api::clock_skew_problem(skew) {
+            Some(problem) => Err(Error::Runner(problem)),
+            None => Ok(()),
+        },
+        Ok(None) => Ok(()),
+        Err(err) => {
+            warn!("Skipping clock skew check: {err}");
+            Ok(())
+        }
+    }
+}
+
+pub async fn configure_runner<R: Runtime>(
+    app: &AppHandle<R>,
+    config_store: &ConfigStore,
+    token_cache: &RegistrationTokenCache,
+    runner_id: &str,
+    options: ConfigureOptions,
+) -> Result<RunnerProfile, Error> {
+    let ConfigureOptions {
+        scope,
+        name,
+        labels,
+        work_dir,
+        runner_group,
+        ephemeral,
+        jit,
+    } = options;
+    // JIT registrations are single-use, so they are always ephemeral.
+    let ephemeral = ephemeral || jit;
+    let runner_group = runner_group
+        .map(|group| group.trim().to_string())
+        .filter(|group| !group.is_empty());
+    if runner_group.is_some() && matches!(scope, RunnerScope::Repo { .. }) {
+        return Err(Error::Runner(
+            "runner groups are only supported for org and enterprise runners".into(),
+        ));
+    }
+    let config = config_store.get();
+    let profile = crate::config::find_runner(&config, runner_id)?;
+    let pat = github_api::resolve_token(&config, &profile.pat_alias)
+        .await?
+        .ok_or_else(|| {
+            Error::Runner("no credentials found in credential store; save a token first".into())
+        })?;
+    github_api::verify_scope_access(&config.settings.api_base_url, &scope, &pat).await?;
     let work_dir = if work_dir.trim().is_empty() {
         profile.work_dir.clone()
     } else {
@@ -109,7 +588,8 @@ pub async fn configure_runner(
         return Err(Error::Runner("work directory is required".into()));
     }
     let install_path = expand_path(&profile.install.install_path);
-    let config_script = runner_script_path(&install_path, RunnerScriptKind::Config)?;
+    #[cfg(target_os = "linux")]
+    check_libicu(&profile)?;
     let work_dir_path = expand_path(&work_dir);
     fs::create_dir_all(&work_dir_path)?;
     let normalized_labels = normalize_labels(labels);
@@ -118,43 +598,159 @@ pub async fn configure_runner(
     } else {
         (Some(normalized_labels.join(",")), normalized_labels)
     };
-    let url = scope.url();
+    if jit {
+        info!("Runner {runner_id} will register just-in-time on each start");
+        return config_store.update_runner(runner_id, |runner| {
+            runner.runner_name = name;
+            runner.labels = stored_labels;
+            runner.work_dir = work_dir;
+            runner.scope = Some(scope.clone());
+            runner.runner_group = runner_group;
+            runner.ephemeral = true;
+            runner.jit = true;
+        });
+    }
+    let config_script = runner_script_path(&install_path, RunnerScriptKind::Config)?;
+    ensure_clock_in_sync(&config.settings.api_base_url).await?;
+    // Refetches when the cached token is within two minutes of expiring.
+    let token = github_api::cached_registration_token(
+        token_cache,
+        &config.settings.api_base_url,
+        &scope,
+        &pat,
+    )
+    .await?;
+    let url = scope.url(&config.settings.github_base_url);
     info!("Configuring runner {runner_id} for {url}");
-    let mut command = Command::new(config_script);
+    let mut command = config_script_command(&config_script, &install_path, &token.token);
     command
-        .current_dir(&install_path)
+        .envs(&profile.env)
         .arg("--unattended")
         .arg("--replace")
         .arg("--url")
         .arg(&url)
-        .arg("--token")
-        .arg(&token.token)
         .arg("--name")
         .arg(&name);
     if let Some(labels_csv) = labels_arg.as_deref() {
         command.arg("--labels").arg(labels_csv);
     }
+    if let Some(group) = runner_group.as_deref() {
+        command.arg("--runnergroup").arg(group);
+    }
+    if ephemeral {
+        command.arg("--ephemeral");
+    }
     command.arg("--work").arg(&work_dir_path);
-    let status = command.status()?;
+    let (status, failures) = run_config_script(app, runner_id, command).await?;
     if !status.success() {
-        return Err(Error::Runner(format!(
-            "runner config failed with status {}",
-            status
-        )));
+        return Err(Error::Runner(match failures.last() {
+            Some(reason) => format!("runner registration failed: {reason}"),
+            None => format!("runner config failed with status {status}"),
+        }));
     }
     config_store.update_runner(runner_id, |runner| {
         runner.runner_name = name;
         runner.labels = stored_labels;
         runner.work_dir = work_dir;
         runner.scope = Some(scope.clone());
+        runner.runner_group = runner_group;
+        runner.ephemeral = ephemeral;
+        runner.jit = false;
+    })
+}
+
+/// Runs `config.sh`, streaming stdout and stderr as `configure` progress events,
+/// and returns the exit status with the lines that explain a failure.
+async fn run_config_script<R: Runtime>(
+    app: &AppHandle<R>,
+    runner_id: &str,
+    mut command: Command,
+) -> Result<(std::process::ExitStatus, Vec<String>), Error> {
+    use std::io::BufRead;
+
+    let emit = |app: &AppHandle<R>, percent: u64, message: Option<String>| {
+        let _ = app.emit(
+            "progress",
+            ProgressPayload {
+                runner_id: runner_id.to_string(),
+                phase: "configure".to_string(),
+                percent,
+                message,
+                ..Default::default()
+            },
+        );
+    };
+    emit(app, 0, None);
+    // --unattended should never prompt; a closed stdin makes sure it can't hang.
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("piped stdout");
+    let stderr = child.stderr.take().expect("piped stderr");
+    let app_handle = app.clone();
+    let runner = runner_id.to_string();
+    let (status, failures) = tauri::async_runtime::spawn_blocking(move || {
+        let read = |stream: Box<dyn Read + Send>| {
+            let mut failures = Vec::new();
+            for line in io::BufReader::new(stream).lines().map_while(Result::ok) {
+                let line = scrub_sensitive(&line);
+                if let Some(reason) = config_failure_reason(&line) {
+                    failures.push(reason);
+                }
+                let _ = app_handle.emit(
+                    "progress",
+                    ProgressPayload {
+                        runner_id: runner.clone(),
+                        phase: "configure".to_string(),
+                        percent: 0,
+                        message: Some(line),
+                        ..Default::default()
+                    },
+                );
+            }
+            failures
+        };
+        let mut failures = std::thread::scope(|scope| {
+            let stderr_reader = scope.spawn(|| read(Box::new(stderr)));
+            let mut failures = read(Box::new(stdout));
+            failures.extend(stderr_reader.join().unwrap_or_default());
+            failures
+        });
+        failures.dedup();
+        child.wait().map(|status| (status, failures))
     })
+    .await
+    .map_err(|err| Error::Runner(format!("runner config task failed: {err}")))??;
+    if status.success() {
+        emit(app, 100, None);
+    }
+    Ok((status, failures))
+}
+
+/// `config.sh` reports registration problems on lines like
+/// `Http response code: NotFound from 'POST ...'` or `An error occurred: ...`.
+fn config_failure_reason(line: &str) -> Option<String> {
+    const MARKERS: [&str; 6] = [
+        "An error occurred:",
+        "Http response code:",
+        "Failed to",
+        "Cannot configure",
+        "Invalid configuration",
+        "A runner exists with the same name",
+    ];
+    let line = line.trim();
+    let start = MARKERS.iter().filter_map(|marker| line.find(marker)).min()?;
+    Some(line[start..].trim().to_string())
 }
 
 pub fn repair_runner_scope(
     config_store: &ConfigStore,
     runner_id: &str,
 ) -> Result<RunnerProfile, Error> {
-    let profile = crate::config::find_runner(&config_store.get(), runner_id)?;
+    let config = config_store.get();
+    let profile = crate::config::find_runner(&config, runner_id)?;
     if profile.scope.is_some() {
         return Ok(profile);
     }
@@ -172,7 +768,7 @@ pub fn repair_runner_scope(
     })?;
     info!(
         "Repaired missing scope for runner {runner_id}: {}",
-        scope.url()
+        scope.url(&config.settings.github_base_url)
     );
     config_store.update_runner(runner_id, |runner| {
         runner.scope = Some(scope.clone());
@@ -183,6 +779,25 @@ pub fn start_runner(
     config_store: &ConfigStore,
     runner_id: &str,
     child_map: &std::sync::Mutex<HashMap<String, Child>>,
+) -> Result<u32, Error> {
+    spawn_runner(config_store, runner_id, child_map, None)
+}
+
+/// Launches `run.sh` with a JIT config, which registers and configures the runner for one job.
+pub fn start_runner_jit(
+    config_store: &ConfigStore,
+    runner_id: &str,
+    child_map: &std::sync::Mutex<HashMap<String, Child>>,
+    jit_config: &str,
+) -> Result<u32, Error> {
+    spawn_runner(config_store, runner_id, child_map, Some(jit_config))
+}
+
+fn spawn_runner(
+    config_store: &ConfigStore,
+    runner_id: &str,
+    child_map: &std::sync::Mutex<HashMap<String, Child>>,
+    jit_config: Option<&str>,
 ) -> Result<u32, Error> {
     let profile = get_runner_profile(config_store, runner_id)?;
     {
@@ -206,13 +821,19 @@ pub fn start_runner(
     fs::create_dir_all(&log_dir)?;
     let stdout_path = log_dir.join("runner-stdout.log");
     let stderr_path = log_dir.join("runner-stderr.log");
-    let stdout = File::create(stdout_path)?;
-    let stderr = File::create(stderr_path)?;
-    let mut command = Command::new(run_script);
+    let max_bytes = config_store.get().settings.runner_log_max_bytes;
+    let stdout = open_runner_log(&stdout_path, max_bytes)?;
+    let stderr = open_runner_log(&stderr_path, max_bytes)?;
+    let mut command = run_script_command(&run_script, &install_path, &profile.env, jit_config);
     command
-        .current_dir(&install_path)
         .stdout(Stdio::from(stdout))
         .stderr(Stdio::from(stderr));
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Own process group so a stop signal reaches Runner.Listener, not just run.sh.
+        command.process_group(0);
+    }
     let child = command.spawn()?;
     let pid = child.id();
     let mut guard = child_map.lock().expect("runner child mutex poisoned");
@@ -220,19 +841,201 @@ pub fn start_runner(
     Ok(pid)
 }
 
-pub fn stop_runner(
+// Append so output from a previous (possibly crashed) run survives a restart.
+fn open_runner_log(path: &Path, max_bytes: u64) -> Result<File, Error> {
+    let oversized = fs::metadata(path)
+        .map(|meta| max_bytes > 0 && meta.len() > max_bytes)
+        .unwrap_or(false);
+    if oversized {
+        let mut rotated = path.as_os_str().to_owned();
+        rotated.push(".1");
+        fs::rename(path, PathBuf::from(rotated))?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(
+        file,
+        "\n===== RunnerBuddy started runner at {} =====",
+        crate::config::now_iso8601()
+    )?;
+    Ok(file)
+}
+
+/// Enforces the size cap on a runner that stays up for weeks. The runner keeps its
+/// append-mode handle, so an oversized log is copied to `.1` and truncated in place
+/// rather than renamed; output written between the copy and the truncate is lost.
+pub fn rotate_runner_logs(runner_id: &str, max_bytes: u64) -> Result<(), Error> {
+    if max_bytes == 0 {
+        return Ok(());
+    }
+    let log_dir = crate::config::runner_logs_dir(runner_id)?;
+    for name in ["runner-stdout.log", "runner-stderr.log"] {
+        rotate_open_log(&log_dir.join(name), max_bytes)?;
+    }
+    Ok(())
+}
+
+fn rotate_open_log(path: &Path, max_bytes: u64) -> Result<bool, Error> {
+    let oversized = fs::metadata(path)
+        .map(|meta| meta.len() > max_bytes)
+        .unwrap_or(false);
+    if !oversized {
+        return Ok(false);
+    }
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    fs::copy(path, PathBuf::from(rotated))?;
+    OpenOptions::new().write(true).open(path)?.set_len(0)?;
+    Ok(true)
+}
+
+/// Requests a fresh JIT config for the profile, first deleting a leftover offline
+/// registration with the same name (left behind when a JIT runner stops before a job).
+pub async fn request_jit_config(
+    config_store: &ConfigStore,
+    runner_id: &str,
+) -> Result<github_api::JitConfig, Error> {
+    let config = config_store.get();
+    let profile = crate::config::find_runner(&config, runner_id)?;
+    let scope = profile
+        .scope
+        .clone()
+        .ok_or_else(|| Error::Runner("runner scope is required for JIT registration".into()))?;
+    let pat = github_api::resolve_token(&config, &profile.pat_alias)
+        .await?
+        .ok_or_else(|| {
+            Error::Runner("no credentials found in credential store; save a token first".into())
+        })?;
+    let api_base = &config.settings.api_base_url;
+    let registered = github_api::list_registered_runners(api_base, &scope, &pat).await?;
+    if let Some(stale) = registered
+        .items
+        .iter()
+        .find(|runner| runner.name == profile.runner_name && runner.status == "offline")
+    {
+        info!(
+            "Removing stale JIT registration {} (id {})",
+            stale.name, stale.id
+        );
+        github_api::delete_runner(api_base, &scope, stale.id, &pat).await?;
+    }
+    let work_dir = expand_path(&profile.work_dir);
+    let jit = github_api::get_jit_config(
+        api_base,
+        &scope,
+        &profile.runner_name,
+        &profile.labels,
+        profile.runner_group.as_deref(),
+        &work_dir.to_string_lossy(),
+        &pat,
+    )
+    .await?;
+    info!(
+        "Generated JIT config for runner {runner_id} (GitHub runner id {})",
+        jit.runner_id
+    );
+    Ok(jit)
+}
+
+/// Polls with `tokio::time::sleep` so waiting out the grace period never blocks a
+/// runtime worker.
+pub async fn stop_runner(
     runner_id: &str,
     child_map: &std::sync::Mutex<HashMap<String, Child>>,
+    grace: Duration,
 ) -> Result<(), Error> {
-    let mut guard = child_map.lock().expect("runner child mutex poisoned");
-    if let Some(child) = guard.get_mut(runner_id) {
-        let _ = child.kill();
-        let _ = child.wait();
+    let child = child_map
+        .lock()
+        .expect("runner child mutex poisoned")
+        .remove(runner_id);
+    let Some(mut child) = child else {
+        return Ok(());
+    };
+    if matches!(child.try_wait(), Ok(Some(_))) {
+        return Ok(());
     }
-    guard.remove(runner_id);
+    let requested = request_graceful_stop(&child).unwrap_or_else(|err| {
+        warn!("graceful stop request for runner {runner_id} failed: {err}");
+        true
+    });
+    if requested {
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    info!("Runner {runner_id} exited gracefully ({status})");
+                    return Ok(());
+                }
+                Ok(None) => tokio::time::sleep(Duration::from_millis(200)).await,
+                Err(err) => {
+                    warn!("runner process check failed: {err}");
+                    break;
+                }
+            }
+        }
+        warn!(
+            "runner {runner_id} did not exit within {}s; killing",
+            grace.as_secs()
+        );
+    } else {
+        info!("Runner {runner_id} has no graceful stop on this platform; killing");
+    }
+    kill_runner_tree(&mut child);
     Ok(())
 }
 
+/// Returns whether a stop was requested, i.e. whether waiting out the grace
+/// period can help.
+#[cfg(unix)]
+fn request_graceful_stop(child: &Child) -> io::Result<bool> {
+    // The listener finishes or abandons its job cleanly on SIGINT.
+    let status = Command::new("kill")
+        .arg("-INT")
+        .arg("--")
+        .arg(format!("-{}", child.id()))
+        .status()?;
+    if status.success() {
+        Ok(true)
+    } else {
+        Err(io::Error::other(format!("kill exited with {status}")))
+    }
+}
+
+/// Windows has no usable graceful step: `taskkill` without `/F` sends WM_CLOSE,
+/// which a console `Runner.Listener` never receives, and a CTRL_BREAK only reaches
+/// processes sharing the caller's console, which a GUI app does not have.
+#[cfg(not(unix))]
+fn request_graceful_stop(_child: &Child) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// The runner leads its own process group, so SIGKILL the whole group or
+/// `Runner.Listener` and the job's workers outlive `run.sh`.
+#[cfg(unix)]
+fn kill_runner_tree(child: &mut Child) {
+    let killed = Command::new("kill")
+        .arg("-KILL")
+        .arg("--")
+        .arg(format!("-{}", child.id()))
+        .status();
+    if !killed.is_ok_and(|status| status.success()) {
+        let _ = child.kill();
+    }
+    let _ = child.wait();
+}
+
+/// `run.cmd` starts `Runner.Listener` as a child, so kill the whole tree.
+#[cfg(not(unix))]
+fn kill_runner_tree(child: &mut Child) {
+    let _ = Command::new("taskkill")
+        .arg("/PID")
+        .arg(child.id().to_string())
+        .arg("/T")
+        .arg("/F")
+        .status();
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
 pub fn runner_log_dir(profile: &RunnerProfile) -> PathBuf {
     expand_path(&profile.install.install_path).join("_diag")
 }
@@ -274,14 +1077,92 @@ fn detect_platform() -> Result<RunnerPlatform, Error> {
     Ok(RunnerPlatform { os, arch, ext })
 }
 
-fn http_client() -> Result<reqwest::Client, Error> {
-    Ok(reqwest::Client::builder()
-        .user_agent("RunnerBuddy")
-        .build()?)
+/// Checks the extracted `Runner.Listener` was built for this host, so a wrong
+/// asset fails here instead of with an exec format error at start.
+fn verify_listener_platform(install_path: &Path, platform: &RunnerPlatform) -> Result<(), Error> {
+    let listener = if platform.os == "win" {
+        install_path.join("bin").join("Runner.Listener.exe")
+    } else {
+        install_path.join("bin").join("Runner.Listener")
+    };
+    let mut header = Vec::with_capacity(4096);
+    File::open(&listener)
+        .map_err(|err| {
+            Error::Runner(format!(
+                "{} missing after extraction: {err}",
+                listener.display()
+            ))
+        })?
+        .take(4096)
+        .read_to_end(&mut header)?;
+    let Some((os, arch)) = binary_platform(&header) else {
+        warn!(
+            "unrecognized executable format for {}; skipping architecture check",
+            listener.display()
+        );
+        return Ok(());
+    };
+    if os != platform.os || arch != platform.arch {
+        return Err(Error::Runner(format!(
+            "downloaded runner is built for {os}-{arch} but this host needs {}-{}; check the pinned version or download mirror",
+            platform.os, platform.arch
+        )));
+    }
+    Ok(())
+}
+
+/// OS and architecture (in runner asset naming) from an ELF, Mach-O or PE header.
+fn binary_platform(header: &[u8]) -> Option<(&'static str, &'static str)> {
+    let u16_at = |offset: usize, little: bool| {
+        let bytes: [u8; 2] = header.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if little {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    };
+    let u32_le_at = |offset: usize| {
+        let bytes: [u8; 4] = header.get(offset..offset + 4)?.try_into().ok()?;
+        Some(u32::from_le_bytes(bytes))
+    };
+    if header.starts_with(b"\x7fELF") {
+        let little = *header.get(5)? == 1;
+        let arch = match u16_at(18, little)? {
+            0x3e => "x64",
+            0xb7 => "arm64",
+            0x28 => "arm",
+            0x03 => "x86",
+            _ => return None,
+        };
+        return Some(("linux", arch));
+    }
+    // Thin 64-bit Mach-O, as written by little-endian hosts.
+    if header.starts_with(&[0xcf, 0xfa, 0xed, 0xfe]) {
+        let arch = match u32_le_at(4)? {
+            0x0100_0007 => "x64",
+            0x0100_000c => "arm64",
+            _ => return None,
+        };
+        return Some(("osx", arch));
+    }
+    if header.starts_with(b"MZ") {
+        let pe_offset = u32_le_at(0x3c)? as usize;
+        if header.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+            return None;
+        }
+        let arch = match u16_at(pe_offset + 4, true)? {
+            0x8664 => "x64",
+            0xaa64 => "arm64",
+            0x014c => "x86",
+            _ => return None,
+        };
+        return Some(("win", arch));
+    }
+    None
 }
 
 async fn fetch_release(version: Option<String>) -> Result<ReleaseInfo, Error> {
-    let client = http_client()?;
+    let client = http::client()?;
     let url = if let Some(version) = version {
         let version = normalize_version(&version);
         format!(
@@ -290,7 +1171,13 @@ async fn fetch_release(version: Option<String>) -> Result<ReleaseInfo, Error> {
     } else {
         "https://api.github.com/repos/actions/runner/releases/latest".to_string()
     };
-    let resp = client.get(url).send().await?;
+    let timeout = http::request_timeout();
+    let resp = client
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|err| http::map_error(err, "release lookup", timeout))?;
     if !resp.status().is_success() {
         return Err(Error::Runner(format!(
             "release lookup failed: {}",
@@ -300,9 +1187,87 @@ async fn fetch_release(version: Option<String>) -> Result<ReleaseInfo, Error> {
     Ok(resp.json::<ReleaseInfo>().await?)
 }
 
-fn normalize_version(tag: &str) -> String {
-    tag.trim_start_matches('v').to_string()
-}
+#[derive(Debug, Deserialize)]
+struct ReleaseSummary {
+    tag_name: String,
+    #[serde(default)]
+    prerelease: bool,
+    #[serde(default)]
+    draft: bool,
+    published_at: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct RunnerRelease {
+    pub version: String,
+    pub prerelease: bool,
+    pub draft: bool,
+    pub published_at: Option<String>,
+}
+
+/// Newest first, as GitHub returns them; `limit` is capped at one page.
+pub async fn list_runner_versions(limit: u32) -> Result<Vec<RunnerRelease>, Error> {
+    let client = http::client()?;
+    let per_page = limit.clamp(1, 100);
+    let url = format!("https://api.github.com/repos/actions/runner/releases?per_page={per_page}");
+    let timeout = http::request_timeout();
+    let resp = client
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|err| http::map_error(err, "release list", timeout))?;
+    if !resp.status().is_success() {
+        return Err(Error::Runner(format!(
+            "release list failed: {}",
+            resp.status()
+        )));
+    }
+    let releases = resp.json::<Vec<ReleaseSummary>>().await?;
+    Ok(releases.into_iter().map(RunnerRelease::from).collect())
+}
+
+impl From<ReleaseSummary> for RunnerRelease {
+    fn from(release: ReleaseSummary) -> Self {
+        Self {
+            version: normalize_version(&release.tag_name),
+            prerelease: release.prerelease,
+            draft: release.draft,
+            published_at: release.published_at,
+        }
+    }
+}
+
+fn mirror_base(settings: &crate::config::SettingsConfig) -> Option<&str> {
+    settings
+        .runner_download_base
+        .as_deref()
+        .map(str::trim)
+        .filter(|base| !base.is_empty())
+}
+
+/// Root of the host runner archives are fetched from: the mirror when one is
+/// set, otherwise github.com.
+pub(crate) fn download_host_url(settings: &crate::config::SettingsConfig) -> String {
+    mirror_base(settings)
+        .and_then(|base| reqwest::Url::parse(base).ok())
+        .and_then(|url| url.join("/").ok())
+        .map(|url| url.to_string())
+        .unwrap_or_else(|| "https://github.com/".to_string())
+}
+
+/// `{base}/{file}`, with `{version}` in the base replaced so a mirror can keep
+/// GitHub's per-release directories.
+fn mirror_url(base: &str, version: &str, file: &str) -> String {
+    format!(
+        "{}/{file}",
+        base.replace("{version}", version).trim_end_matches('/')
+    )
+}
+
+fn normalize_version(tag: &str) -> String {
+    tag.trim_start_matches('v').to_string()
+}
 
 fn find_asset_urls(
     assets: &[ReleaseAsset],
@@ -325,9 +1290,17 @@ async fn download_with_progress<R: Runtime>(
     runner_id: &str,
     url: &str,
     dest: &Path,
+    note: Option<&str>,
+    cancel: Option<&AtomicBool>,
 ) -> Result<(), Error> {
-    let client = http_client()?;
-    let resp = client.get(url).send().await?;
+    let client = http::client()?;
+    let timeout = http::download_timeout();
+    let resp = client
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|err| http::map_error(err, "runner download", timeout))?;
     if !resp.status().is_success() {
         return Err(Error::Runner(format!(
             "runner download failed: {}",
@@ -338,28 +1311,73 @@ async fn download_with_progress<R: Runtime>(
     let mut stream = resp.bytes_stream();
     let mut file = File::create(dest)?;
     let mut downloaded: u64 = 0;
+    let mut rate = TransferRate::new(Duration::from_secs(5));
+    let mut last_emit: Option<Instant> = None;
+    rate.record(Instant::now(), 0);
     while let Some(chunk) = stream.next().await {
-        let data = chunk?;
-        file.write_all(&data)?;
-        downloaded += data.len() as u64;
-        if total > 0 {
-            let percent = ((downloaded as f64 / total as f64) * 100.0) as u64;
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::SeqCst)) {
+            drop(file);
+            let _ = fs::remove_file(dest);
+            info!("Runner download cancelled for {runner_id}");
             let _ = app.emit(
                 "progress",
                 ProgressPayload {
                     runner_id: runner_id.to_string(),
-                    phase: "download".to_string(),
-                    percent,
+                    phase: "cancelled".to_string(),
+                    percent: 0,
+                    ..Default::default()
                 },
             );
+            return Err(Error::Runner("runner download cancelled".into()));
+        }
+        let data = chunk.map_err(|err| http::map_error(err, "runner download", timeout))?;
+        file.write_all(&data)?;
+        downloaded += data.len() as u64;
+        let now = Instant::now();
+        rate.record(now, downloaded);
+        let finished = total > 0 && downloaded >= total;
+        if !finished
+            && last_emit.is_some_and(|last| now.duration_since(last) < PROGRESS_EMIT_INTERVAL)
+        {
+            continue;
         }
+        last_emit = Some(now);
+        let percent = if total > 0 {
+            ((downloaded as f64 / total as f64) * 100.0) as u64
+        } else {
+            0
+        };
+        let _ = app.emit(
+            "progress",
+            ProgressPayload {
+                runner_id: runner_id.to_string(),
+                phase: "download".to_string(),
+                percent,
+                message: note.map(str::to_string),
+                bytes_done: Some(downloaded),
+                bytes_total: (total > 0).then_some(total),
+                bytes_per_sec: rate.bytes_per_sec(),
+                eta_secs: (total > 0).then(|| rate.eta_secs(downloaded, total)).flatten(),
+                ..Default::default()
+            },
+        );
     }
     Ok(())
 }
 
-async fn verify_sha256(url: &str, archive_path: &Path) -> Result<(), Error> {
-    let client = http_client()?;
-    let resp = client.get(url).send().await?;
+/// Fetches a `.sha256` file; a mirror without one yields `None`.
+async fn fetch_sha256(url: &str) -> Result<Option<String>, Error> {
+    let client = http::client()?;
+    let timeout = http::request_timeout();
+    let resp = client
+        .get(url)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|err| http::map_error(err, "sha256 download", timeout))?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
     if !resp.status().is_success() {
         return Err(Error::Runner(format!(
             "sha256 download failed: {}",
@@ -367,37 +1385,193 @@ async fn verify_sha256(url: &str, archive_path: &Path) -> Result<(), Error> {
         )));
     }
     let body = resp.text().await?;
-    let expected = body.split_whitespace().next().unwrap_or("").to_string();
-    let data = fs::read(archive_path)?;
-    let actual = hex::encode(sha2::Sha256::digest(data));
+    normalize_sha256(body.split_whitespace().next().unwrap_or("")).map(Some)
+}
+
+fn normalize_sha256(hash: &str) -> Result<String, Error> {
+    let hash = hash.trim().to_ascii_lowercase();
+    if hash.len() != 64 || !hash.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return Err(Error::Runner(format!("invalid sha256 checksum: {hash:?}")));
+    }
+    Ok(hash)
+}
+
+fn verify_sha256(archive_path: &Path, expected: &str) -> Result<(), Error> {
+    let mut hasher = sha2::Sha256::new();
+    io::copy(&mut File::open(archive_path)?, &mut hasher)?;
+    let actual = hex::encode(hasher.finalize());
     if expected != actual {
+        let _ = fs::remove_file(archive_path);
         return Err(Error::Runner("sha256 mismatch for runner download".into()));
     }
     Ok(())
 }
 
+fn preserve_dir(install_path: &Path) -> PathBuf {
+    sibling_dir(install_path, "preserve")
+}
+
+fn staging_dir(install_path: &Path) -> PathBuf {
+    sibling_dir(install_path, "staging")
+}
+
+fn sibling_dir(install_path: &Path, purpose: &str) -> PathBuf {
+    let name = install_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "runner".to_string());
+    install_path.with_file_name(format!(".{name}.runnerbuddy-{purpose}"))
+}
+
+/// Extracts into a sibling staging dir and checks the listener there, so a broken
+/// or wrong-platform download never touches the working install. Only then is the
+/// registration carried over and the staged tree swapped into place.
+fn install_archive(
+    archive_path: &Path,
+    install_path: &Path,
+    platform: &RunnerPlatform,
+) -> Result<(), Error> {
+    let staging = staging_dir(install_path);
+    if staging.join(".runner").exists() {
+        // Left by an install whose registration could not be handed back.
+        return Err(Error::Runner(format!(
+            "staging directory {} holds a runner registration; restore it into {} first",
+            staging.display(),
+            install_path.display()
+        )));
+    }
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let staged = extract_archive(archive_path, &staging)
+        .and_then(|_| verify_listener_platform(&staging, platform));
+    if let Err(err) = staged {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+    let carried = if install_path.join(".runner").exists() {
+        info!(
+            "install path {:?} is configured; keeping registration while replacing binaries",
+            install_path
+        );
+        match move_entries(install_path, &staging, &PRESERVED_ENTRIES) {
+            Ok(carried) => carried,
+            Err(err) => {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(err);
+            }
+        }
+    } else {
+        if install_path.exists() {
+            warn!("install path {:?} exists; replacing it", install_path);
+        }
+        Vec::new()
+    };
+    if let Err(err) = swap_into_place(&staging, install_path) {
+        // Hand the registration back so it is never lost with the staged tree.
+        if let Err(restore_err) = move_entries(&staging, install_path, &carried) {
+            return Err(Error::Runner(format!(
+                "{err}; the runner registration could not be restored ({restore_err}) and was left in {}",
+                staging.display()
+            )));
+        }
+        let _ = fs::remove_dir_all(&staging);
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Moves the named entries that exist in `from` into `to`, replacing what is
+/// there. On failure the entries already moved are put back.
+fn move_entries<S: AsRef<str>>(from: &Path, to: &Path, entries: &[S]) -> Result<Vec<String>, Error> {
+    let mut moved = Vec::new();
+    for entry in entries.iter().map(AsRef::as_ref) {
+        let source = from.join(entry);
+        if !source.exists() {
+            continue;
+        }
+        let target = to.join(entry);
+        let result = remove_path(&target).and_then(|_| fs::rename(&source, &target));
+        if let Err(err) = result {
+            for entry in moved.iter().rev() {
+                let _ = fs::rename(to.join(entry), from.join(entry));
+            }
+            return Err(err.into());
+        }
+        moved.push(entry.to_string());
+    }
+    Ok(moved)
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else if path.exists() {
+        fs::remove_file(path)
+    } else {
+        Ok(())
+    }
+}
+
+/// Renames the old install aside, moves `staging` into its place and only then
+/// deletes the old tree; the old install comes back if the final rename fails.
+fn swap_into_place(staging: &Path, install_path: &Path) -> Result<(), Error> {
+    let previous = preserve_dir(install_path);
+    if previous.exists() {
+        fs::remove_dir_all(&previous)?;
+    }
+    if install_path.exists() {
+        fs::rename(install_path, &previous)?;
+    }
+    if let Err(err) = fs::rename(staging, install_path) {
+        if previous.exists() {
+            let _ = fs::rename(&previous, install_path);
+        }
+        return Err(err.into());
+    }
+    if previous.exists() {
+        fs::remove_dir_all(&previous)?;
+    }
+    Ok(())
+}
+
 fn extract_archive(archive_path: &Path, dest: &Path) -> Result<(), Error> {
     let name = archive_path
         .file_name()
         .and_then(|name| name.to_str())
         .unwrap_or_default()
         .to_string();
+    fs::create_dir_all(dest)?;
     if name.ends_with(".tar.gz") {
         let file = File::open(archive_path)?;
         let decompressor = flate2::read::GzDecoder::new(file);
         let mut archive = tar::Archive::new(decompressor);
-        archive.unpack(dest)?;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            entry_destination(dest, &path)?;
+            // `unpack_in` also refuses to write through symlinks that leave `dest`.
+            if !entry.unpack_in(dest)? {
+                return Err(Error::Runner("unsafe path in archive".into()));
+            }
+        }
         return Ok(());
     }
     if name.ends_with(".zip") {
         let file = File::open(archive_path)?;
         let mut zip = zip::ZipArchive::new(file)
             .map_err(|err| Error::Runner(format!("zip open failed: {err}")))?;
+        let root = dest.canonicalize()?;
         for i in 0..zip.len() {
             let mut file = zip
                 .by_index(i)
                 .map_err(|err| Error::Runner(format!("zip entry failed: {err}")))?;
-            let outpath = dest.join(file.mangled_name());
+            let outpath = entry_destination(dest, Path::new(file.name()))?;
+            let parent = outpath.parent().unwrap_or(dest);
+            fs::create_dir_all(parent)?;
+            if !parent.canonicalize()?.starts_with(&root) {
+                return Err(Error::Runner("unsafe path in archive".into()));
+            }
             if file.name().ends_with('/') {
                 fs::create_dir_all(&outpath)?;
             } else {
@@ -406,6 +1580,12 @@ fn extract_archive(archive_path: &Path, dest: &Path) -> Result<(), Error> {
                 }
                 let mut outfile = File::create(&outpath)?;
                 io::copy(&mut file, &mut outfile)?;
+                // Keep the executable bit on scripts like run.sh; setuid/setgid are dropped.
+                #[cfg(unix)]
+                if let Some(mode) = file.unix_mode() {
+                    use std::os::unix::fs::PermissionsExt;
+                    fs::set_permissions(&outpath, fs::Permissions::from_mode(mode & 0o777))?;
+                }
             }
         }
         return Ok(());
@@ -415,6 +1595,130 @@ fn extract_archive(archive_path: &Path, dest: &Path) -> Result<(), Error> {
     )))
 }
 
+/// Resolves an archive entry under `dest`, rejecting absolute paths and `..`.
+fn entry_destination(dest: &Path, entry: &Path) -> Result<PathBuf, Error> {
+    let mut relative = PathBuf::new();
+    for component in entry.components() {
+        match component {
+            std::path::Component::Normal(part) => relative.push(part),
+            std::path::Component::CurDir => {}
+            _ => return Err(Error::Runner("unsafe path in archive".into())),
+        }
+    }
+    Ok(dest.join(relative))
+}
+
+/// `config.sh`/`config.cmd` invocation with the registration or removal token
+/// passed through the environment rather than on the command line.
+pub fn config_script_command(config_script: &Path, install_path: &Path, token: &str) -> Command {
+    let mut command = Command::new(config_script);
+    command.current_dir(install_path).env(TOKEN_ENV, token);
+    command
+}
+
+/// `run.sh`/`run.cmd` invocation with the profile's environment; a JIT config is
+/// passed through the environment rather than on the command line.
+fn run_script_command(
+    run_script: &Path,
+    install_path: &Path,
+    env: &std::collections::BTreeMap<String, String>,
+    jit_config: Option<&str>,
+) -> Command {
+    let mut command = Command::new(run_script);
+    command.current_dir(install_path).envs(env);
+    if let Some(jit_config) = jit_config {
+        command.env(JIT_CONFIG_ENV, jit_config);
+    }
+    command
+}
+
+/// Names must be shell-style identifiers and values single-line, so an entry can
+/// be written into a unit file, plist or `.env` without escaping surprises.
+pub fn validate_env_var(key: &str, value: &str) -> Result<(), Error> {
+    let mut chars = key.chars();
+    let valid_key = chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+    if !valid_key {
+        return Err(Error::Config(format!("invalid environment variable name: {key:?}")));
+    }
+    if value.chars().any(|ch| matches!(ch, '\n' | '\r' | '\0')) {
+        return Err(Error::Config(format!(
+            "environment variable {key} must be a single line"
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, Deserialize)]
+pub struct EnvEntry {
+    pub key: String,
+    pub value: String,
+}
+
+/// Entries from the runner's `.env`, which it loads into every job's environment.
+pub fn read_env_file(install_path: &Path) -> Result<Vec<EnvEntry>, Error> {
+    let contents = match fs::read_to_string(install_path.join(".env")) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(contents.lines().filter_map(parse_env_line).collect())
+}
+
+/// Writes the runner's `.env`. With `merge`, existing lines (comments included)
+/// are kept and only the given keys are replaced or appended; otherwise the file
+/// is rewritten with exactly `entries`.
+pub fn write_env_file(install_path: &Path, entries: &[EnvEntry], merge: bool) -> Result<(), Error> {
+    for entry in entries {
+        validate_env_var(&entry.key, &entry.value)?;
+    }
+    let path = install_path.join(".env");
+    let existing = if merge {
+        match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        }
+    } else {
+        String::new()
+    };
+    let mut pending: Vec<&EnvEntry> = entries.iter().collect();
+    let mut lines = Vec::new();
+    for line in existing.lines() {
+        let replaced = parse_env_line(line).and_then(|current| {
+            pending
+                .iter()
+                .position(|entry| entry.key == current.key)
+                .map(|index| pending.remove(index))
+        });
+        match replaced {
+            Some(entry) => lines.push(format!("{}={}", entry.key, entry.value)),
+            None => lines.push(line.to_string()),
+        }
+    }
+    lines.extend(pending.iter().map(|entry| format!("{}={}", entry.key, entry.value)));
+    let mut contents = lines.join("\n");
+    if !contents.is_empty() {
+        contents.push('\n');
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+fn parse_env_line(line: &str) -> Option<EnvEntry> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (key, value) = line.split_once('=')?;
+    Some(EnvEntry {
+        key: key.trim().to_string(),
+        value: value.to_string(),
+    })
+}
+
 enum RunnerScriptKind {
     Config,
     Run,
@@ -450,45 +1754,276 @@ fn runner_script_path(install_path: &Path, kind: RunnerScriptKind) -> Result<Pat
     Ok(script)
 }
 
-#[derive(serde::Serialize, Clone)]
+#[derive(Debug, serde::Serialize)]
+pub struct IntegrityCheck {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct IntegrityReport {
+    pub install_path: String,
+    pub ok: bool,
+    pub checks: Vec<IntegrityCheck>,
+}
+
+/// Static check of the files a runner needs to configure and start; unlike
+/// `discover_verify_runner` nothing is executed.
+pub fn verify_install_integrity(install_path: &Path) -> IntegrityReport {
+    let mut checks = Vec::new();
+    let mut check = |name: &str, result: Result<String, String>| {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        checks.push(IntegrityCheck {
+            name: name.to_string(),
+            ok,
+            detail,
+        });
+    };
+    if !install_path.is_dir() {
+        check(
+            "install_path",
+            Err(format!("{} does not exist", install_path.display())),
+        );
+    } else {
+        check("install_path", Ok(install_path.display().to_string()));
+        let (config_script, run_script, listener) = if cfg!(target_os = "windows") {
+            ("config.cmd", "run.cmd", "Runner.Listener.exe")
+        } else {
+            ("config.sh", "run.sh", "Runner.Listener")
+        };
+        check(config_script, executable_file(&install_path.join(config_script)));
+        check(run_script, executable_file(&install_path.join(run_script)));
+        check(
+            "bin/listener",
+            executable_file(&install_path.join("bin").join(listener)),
+        );
+        check(".runner", runner_file_status(&install_path.join(".runner")));
+    }
+    IntegrityReport {
+        install_path: install_path.to_string_lossy().to_string(),
+        ok: checks.iter().all(|check| check.ok),
+        checks,
+    }
+}
+
+fn executable_file(path: &Path) -> Result<String, String> {
+    let metadata = fs::metadata(path).map_err(|_| format!("{} is missing", path.display()))?;
+    if !metadata.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(format!("{} is not executable", path.display()));
+        }
+    }
+    Ok(path.display().to_string())
+}
+
+fn runner_file_status(path: &Path) -> Result<String, String> {
+    let data = fs::read_to_string(path)
+        .map_err(|_| "missing; the runner has not been configured".to_string())?;
+    // The runner writes its JSON files with a UTF-8 BOM.
+    serde_json::from_str::<serde_json::Value>(data.trim_start_matches('\u{feff}'))
+        .map_err(|err| format!("unreadable: {err}"))?
+        .get("agentName")
+        .and_then(|name| name.as_str())
+        .map(|name| format!("registered as {name}"))
+        .ok_or_else(|| "no agentName; registration looks incomplete".to_string())
+}
+
+#[derive(serde::Serialize, Clone, Default)]
 pub struct ProgressPayload {
     pub runner_id: String,
     pub phase: String,
     pub percent: u64,
+    /// A line of script output, for phases that have no meaningful percentage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_done: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_total: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_per_sec: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eta_secs: Option<u64>,
 }
 
-pub fn classify_runner_status(log_dir: &Path) -> Result<crate::state::RunnerStatus, Error> {
-    let latest = latest_log_file(log_dir).ok().flatten();
-    if latest.is_none() {
-        return Ok(crate::state::RunnerStatus::Idle);
-    }
-    let path = latest.unwrap();
-    let content = read_file_tail(&path, LOG_TAIL_BYTES)?.unwrap_or_default();
-    let mut last_start = None;
-    let mut last_end = None;
-    for (index, line) in content.lines().rev().take(2000).enumerate() {
-        let line = scrub_sensitive(line);
-        if last_start.is_none() && (line.contains("Running job:") || line.contains("Job started")) {
-            last_start = Some(index);
+/// Throughput over the last few seconds of a download, so a stall or a burst
+/// shows up quickly instead of being averaged over the whole transfer.
+struct TransferRate {
+    samples: std::collections::VecDeque<(Instant, u64)>,
+    window: Duration,
+}
+
+impl TransferRate {
+    fn new(window: Duration) -> Self {
+        Self {
+            samples: std::collections::VecDeque::new(),
+            window,
         }
-        if last_end.is_none() && (line.contains("Job completed") || line.contains("Job finished")) {
-            last_end = Some(index);
+    }
+
+    fn record(&mut self, at: Instant, bytes_done: u64) {
+        self.samples.push_back((at, bytes_done));
+        while self.samples.len() > 2
+            && at.duration_since(self.samples[0].0) > self.window
+        {
+            self.samples.pop_front();
         }
-        if last_start.is_some() && last_end.is_some() {
-            break;
+    }
+
+    fn bytes_per_sec(&self) -> Option<u64> {
+        let (first_at, first_bytes) = *self.samples.front()?;
+        let (last_at, last_bytes) = *self.samples.back()?;
+        let elapsed = last_at.duration_since(first_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
         }
+        Some(((last_bytes - first_bytes) as f64 / elapsed) as u64)
     }
-    match (last_start, last_end) {
-        (Some(start), Some(end)) => {
-            if start < end {
-                Ok(crate::state::RunnerStatus::Running)
-            } else {
-                Ok(crate::state::RunnerStatus::Idle)
+
+    fn eta_secs(&self, bytes_done: u64, bytes_total: u64) -> Option<u64> {
+        let rate = self.bytes_per_sec().filter(|rate| *rate > 0)?;
+        Some(bytes_total.saturating_sub(bytes_done).div_ceil(rate))
+    }
+}
+
+/// Status of a live runner from its latest log, plus the job it is running if any.
+pub fn classify_runner_activity(
+    log_dir: &Path,
+) -> Result<(crate::state::RunnerStatus, Option<crate::state::CurrentJob>), Error> {
+    let Some(path) = latest_log_file(log_dir).ok().flatten() else {
+        return Ok((crate::state::RunnerStatus::Idle, None));
+    };
+    let content = read_file_tail(&path, LOG_TAIL_BYTES)?.unwrap_or_default();
+    Ok(activity_from_job_markers(&content))
+}
+
+enum JobMarker {
+    Started,
+    Finished,
+}
+
+fn job_marker(line: &str) -> Option<JobMarker> {
+    if line.contains("Running job:") || line.contains("Job started") {
+        return Some(JobMarker::Started);
+    }
+    // Runner stdout reports "Job <name> completed with result: <result>".
+    if line.contains("Job completed")
+        || line.contains("Job finished")
+        || (line.contains("Job ") && line.contains("completed with result"))
+    {
+        return Some(JobMarker::Finished);
+    }
+    None
+}
+
+// Whichever marker appears last in the log wins, so stale completions from
+// earlier jobs cannot mask a job that is running now (and vice versa).
+fn activity_from_job_markers(
+    content: &str,
+) -> (crate::state::RunnerStatus, Option<crate::state::CurrentJob>) {
+    // Walking backwards, lines after the start marker are seen first.
+    let mut run_url = None;
+    for line in content.lines().rev().take(2000) {
+        let line = scrub_sensitive(line);
+        match job_marker(&line) {
+            Some(JobMarker::Started) => {
+                let job = running_job_name(&line).map(|name| {
+                    let run_url = run_url.or_else(|| actions_run_url(&line));
+                    crate::state::CurrentJob {
+                        name,
+                        run_id: run_url.as_ref().and_then(|(_, id)| *id),
+                        url: run_url.map(|(url, _)| url),
+                    }
+                });
+                return (crate::state::RunnerStatus::Running, job);
+            }
+            Some(JobMarker::Finished) => return (crate::state::RunnerStatus::Idle, None),
+            None => {
+                if let Some(found) = actions_run_url(&line) {
+                    run_url = Some(found);
+                }
             }
         }
-        (Some(_), None) => Ok(crate::state::RunnerStatus::Running),
-        _ => Ok(crate::state::RunnerStatus::Idle),
     }
+    (crate::state::RunnerStatus::Idle, None)
+}
+
+/// Counts jobs finished in the listener diag logs (`Runner_*.log`) past `cursor` and
+/// returns the new cursor. Diag files are never rewritten, only superseded by newer
+/// timestamped ones, so files sorting before the cursor were already counted.
+pub fn count_completed_jobs(
+    log_dir: &Path,
+    cursor: Option<&crate::state::JobLogCursor>,
+) -> Result<(u64, Option<crate::state::JobLogCursor>), Error> {
+    let entries = match fs::read_dir(log_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((0, cursor.cloned())),
+        Err(err) => return Err(err.into()),
+    };
+    let mut files: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("Runner_") && name.ends_with(".log"))
+        .collect();
+    files.sort();
+
+    let mut completed = 0;
+    let mut next = cursor.cloned();
+    for name in files {
+        let start = match cursor {
+            Some(cursor) if name < cursor.file => continue,
+            Some(cursor) if name == cursor.file => cursor.offset,
+            _ => 0,
+        };
+        let mut file = File::open(log_dir.join(&name))?;
+        // A file shorter than the cursor was replaced; read it from the top.
+        let start = if start > file.metadata()?.len() { 0 } else { start };
+        file.seek(SeekFrom::Start(start))?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        // Leave a partially written last line for the next pass.
+        let consumed = buf.iter().rposition(|byte| *byte == b'\n').map_or(0, |pos| pos + 1);
+        completed += String::from_utf8_lossy(&buf[..consumed])
+            .lines()
+            .filter(|line| matches!(job_marker(line), Some(JobMarker::Finished)))
+            .count() as u64;
+        next = Some(crate::state::JobLogCursor {
+            file: name,
+            offset: start + consumed as u64,
+        });
+    }
+    Ok((completed, next))
+}
+
+/// Job name from a runner stdout line like `2024-05-01 10:00:00Z: Running job: build`.
+fn running_job_name(line: &str) -> Option<String> {
+    let (_, name) = line.split_once("Running job:")?;
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// First `.../actions/runs/<id>...` URL in a line, with the run id when it parses.
+fn actions_run_url(line: &str) -> Option<(String, Option<u64>)> {
+    let start = line.find("https://").or_else(|| line.find("http://"))?;
+    let url: String = line[start..]
+        .chars()
+        .take_while(|ch| !ch.is_whitespace() && !matches!(ch, '"' | '\'' | ')' | ']' | ','))
+        .collect();
+    let (_, rest) = url.split_once("/actions/runs/")?;
+    let run_id = rest
+        .split(|ch: char| !ch.is_ascii_digit())
+        .next()
+        .and_then(|digits| digits.parse().ok());
+    Some((url, run_id))
 }
 
 pub struct LogBaseline {
@@ -539,3 +2074,634 @@ pub fn has_ready_marker_since(
 fn get_runner_profile(config_store: &ConfigStore, runner_id: &str) -> Result<RunnerProfile, Error> {
     crate::config::find_runner(&config_store.get(), runner_id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_command_keeps_token_out_of_argv() {
+        let token = "AABBCCDDEEFFGGHHIIJJKKLLMMNN";
+        let mut command =
+            config_script_command(Path::new("/opt/runner/config.sh"), Path::new("/opt/runner"), token);
+        command.arg("remove");
+        assert!(command
+            .get_args()
+            .all(|arg| !arg.to_string_lossy().contains(token)));
+        assert!(command.get_envs().any(|(key, value)| {
+            key == TOKEN_ENV && value.is_some_and(|value| value == token)
+        }));
+    }
+
+    #[test]
+    fn run_command_keeps_jit_config_out_of_argv() {
+        let jit_config = "eyJydW5uZXIiOiJzZWNyZXQifQ==";
+        let env = std::collections::BTreeMap::from([(
+            JIT_CONFIG_ENV.to_string(),
+            "stale".to_string(),
+        )]);
+        let command = run_script_command(
+            Path::new("/opt/runner/run.sh"),
+            Path::new("/opt/runner"),
+            &env,
+            Some(jit_config),
+        );
+        assert!(command.get_args().all(|arg| {
+            let arg = arg.to_string_lossy();
+            arg != "--jitconfig" && !arg.contains(jit_config)
+        }));
+        assert!(command.get_envs().any(|(key, value)| {
+            key == JIT_CONFIG_ENV && value.is_some_and(|value| value == jit_config)
+        }));
+    }
+
+    #[test]
+    fn config_failure_reason_picks_registration_errors() {
+        assert_eq!(
+            config_failure_reason(
+                "[2024-01-01 10:00:00Z ERR  GitHubActionsService] Http response code: NotFound from 'POST https://api.github.com/actions/runner-registration'"
+            )
+            .as_deref(),
+            Some("Http response code: NotFound from 'POST https://api.github.com/actions/runner-registration'")
+        );
+        assert_eq!(
+            config_failure_reason("An error occurred: Not configured. Run config.(sh/cmd) to configure the runner.")
+                .as_deref(),
+            Some("An error occurred: Not configured. Run config.(sh/cmd) to configure the runner.")
+        );
+        assert!(config_failure_reason("√ Connected to GitHub").is_none());
+    }
+
+    #[test]
+    fn env_vars_must_be_identifiers_with_single_line_values() {
+        assert!(validate_env_var("http_proxy", "http://proxy:3128").is_ok());
+        assert!(validate_env_var("_PATH2", "").is_ok());
+        assert!(validate_env_var("2FAST", "x").is_err());
+        assert!(validate_env_var("MY-VAR", "x").is_err());
+        assert!(validate_env_var("", "x").is_err());
+        assert!(validate_env_var("OK", "line\nInjected=1").is_err());
+    }
+
+    #[test]
+    fn env_file_merge_keeps_other_lines() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        fs::write(
+            dir.path().join(".env"),
+            "LANG=C.UTF-8\n# tools\nJAVA_HOME=/usr/lib/jvm/17\n",
+        )
+        .expect("write .env");
+        let entry = |key: &str, value: &str| EnvEntry {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        write_env_file(
+            dir.path(),
+            &[entry("JAVA_HOME", "/usr/lib/jvm/21"), entry("http_proxy", "http://proxy:3128")],
+            true,
+        )
+        .expect("merge .env");
+        assert_eq!(
+            fs::read_to_string(dir.path().join(".env")).unwrap(),
+            "LANG=C.UTF-8\n# tools\nJAVA_HOME=/usr/lib/jvm/21\nhttp_proxy=http://proxy:3128\n"
+        );
+        assert_eq!(read_env_file(dir.path()).unwrap().len(), 3);
+
+        write_env_file(dir.path(), &[entry("ONLY", "1")], false).expect("replace .env");
+        assert_eq!(read_env_file(dir.path()).unwrap(), vec![entry("ONLY", "1")]);
+        assert!(write_env_file(dir.path(), &[entry("BAD KEY", "1")], true).is_err());
+    }
+
+    #[test]
+    fn ldconfig_output_detects_libicu() {
+        let with_icu = "1234 libs found in cache `/etc/ld.so.cache'\n\tlibicuuc.so.70 (libc6,x86-64) => /lib/x86_64-linux-gnu/libicuuc.so.70\n";
+        let without_icu = "2 libs found in cache `/etc/ld.so.cache'\n\tlibicuio.so.70 (libc6,x86-64) => /lib/x86_64-linux-gnu/libicuio.so.70\n";
+        assert!(ldconfig_lists_libicu(with_icu));
+        assert!(!ldconfig_lists_libicu(without_icu));
+    }
+
+    #[test]
+    fn binary_headers_identify_platform() {
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[5] = 1;
+        elf[18] = 0x3e;
+        assert_eq!(binary_platform(&elf), Some(("linux", "x64")));
+        elf[18] = 0xb7;
+        assert_eq!(binary_platform(&elf), Some(("linux", "arm64")));
+
+        let mut macho = vec![0xcf, 0xfa, 0xed, 0xfe];
+        macho.extend_from_slice(&0x0100_000cu32.to_le_bytes());
+        assert_eq!(binary_platform(&macho), Some(("osx", "arm64")));
+        macho[4..8].copy_from_slice(&0x0100_0007u32.to_le_bytes());
+        assert_eq!(binary_platform(&macho), Some(("osx", "x64")));
+
+        let mut pe = vec![0u8; 0x90];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe[0x84..0x86].copy_from_slice(&0x8664u16.to_le_bytes());
+        assert_eq!(binary_platform(&pe), Some(("win", "x64")));
+        pe[0x84..0x86].copy_from_slice(&0xaa64u16.to_le_bytes());
+        assert_eq!(binary_platform(&pe), Some(("win", "arm64")));
+
+        assert_eq!(binary_platform(b"#!/bin/bash\n"), None);
+        assert_eq!(binary_platform(b"\x7fEL"), None);
+    }
+
+    #[test]
+    fn archive_checksum_is_compared_case_insensitively() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let archive = dir.path().join("runner.tar.gz");
+        fs::write(&archive, b"runner").expect("write archive");
+        let actual = hex::encode(sha2::Sha256::digest(b"runner"));
+        let expected = normalize_sha256(&format!(" {}\n", actual.to_ascii_uppercase()))
+            .expect("valid hash");
+        assert!(verify_sha256(&archive, &expected).is_ok());
+
+        let wrong = normalize_sha256(&"0f".repeat(32)).expect("valid hash");
+        assert!(verify_sha256(&archive, &wrong).is_err());
+        assert!(!archive.exists(), "mismatched archive is removed");
+        assert!(normalize_sha256("abc123").is_err());
+    }
+
+    #[test]
+    fn mirror_urls_use_file_names_and_version_placeholder() {
+        assert_eq!(
+            mirror_url(
+                "https://mirror.internal/runner/",
+                "2.321.0",
+                "actions-runner-linux-x64-2.321.0.tar.gz"
+            ),
+            "https://mirror.internal/runner/actions-runner-linux-x64-2.321.0.tar.gz"
+        );
+        assert_eq!(
+            mirror_url(
+                "https://mirror.internal/actions/runner/releases/download/v{version}",
+                "2.321.0",
+                "actions-runner-linux-x64-2.321.0.tar.gz.sha256"
+            ),
+            "https://mirror.internal/actions/runner/releases/download/v2.321.0/actions-runner-linux-x64-2.321.0.tar.gz.sha256"
+        );
+    }
+
+    #[test]
+    fn release_list_entries_are_normalized() {
+        let releases: Vec<ReleaseSummary> = serde_json::from_str(
+            r#"[
+                {"tag_name": "v2.322.0", "prerelease": true, "draft": false, "published_at": "2025-01-10T00:00:00Z", "assets": []},
+                {"tag_name": "v2.321.0", "published_at": null}
+            ]"#,
+        )
+        .expect("parse releases");
+        let releases: Vec<RunnerRelease> = releases.into_iter().map(RunnerRelease::from).collect();
+        assert_eq!(releases[0].version, "2.322.0");
+        assert!(releases[0].prerelease);
+        assert_eq!(releases[1].version, "2.321.0");
+        assert!(!releases[1].prerelease && !releases[1].draft);
+        assert!(releases[1].published_at.is_none());
+    }
+
+    #[test]
+    fn download_host_defaults_to_github_and_follows_mirror() {
+        let mut settings = crate::config::SettingsConfig::default();
+        assert_eq!(download_host_url(&settings), "https://github.com/");
+        settings.runner_download_base =
+            Some("https://mirror.internal:8443/runner/v{version}".to_string());
+        assert_eq!(download_host_url(&settings), "https://mirror.internal:8443/");
+    }
+
+    #[test]
+    fn transfer_rate_uses_recent_window() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new(Duration::from_secs(5));
+        rate.record(start, 0);
+        assert_eq!(rate.bytes_per_sec(), None);
+        // A slow first stretch falls out of the window once it is old enough.
+        rate.record(start + Duration::from_secs(10), 1_000);
+        rate.record(start + Duration::from_secs(12), 5_000);
+        rate.record(start + Duration::from_secs(14), 9_000);
+        assert_eq!(rate.bytes_per_sec(), Some(2_000));
+        assert_eq!(rate.eta_secs(9_000, 20_000), Some(6));
+        assert_eq!(rate.eta_secs(20_000, 20_000), Some(0));
+    }
+
+    #[test]
+    fn extraction_rejects_entries_outside_destination() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let dest = dir.path().join("runner");
+
+        let tar_path = dir.path().join("evil.tar.gz");
+        {
+            let encoder = flate2::write::GzEncoder::new(
+                File::create(&tar_path).expect("create tar"),
+                flate2::Compression::default(),
+            );
+            let mut builder = tar::Builder::new(encoder);
+            let data = b"pwned";
+            let mut header = tar::Header::new_old();
+            // `set_path` refuses `..`, so write the name field directly.
+            header.as_old_mut().name[..9].copy_from_slice(b"../escape");
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, &data[..]).expect("append entry");
+            builder.into_inner().expect("finish tar").finish().expect("finish gzip");
+        }
+        let err = extract_archive(&tar_path, &dest).expect_err("tar traversal rejected");
+        assert!(err.to_string().contains("unsafe path in archive"));
+        assert!(!dir.path().join("escape").exists());
+
+        let zip_path = dir.path().join("evil.zip");
+        {
+            let mut zip = zip::ZipWriter::new(File::create(&zip_path).expect("create zip"));
+            zip.start_file("bin/ok.txt", zip::write::SimpleFileOptions::default())
+                .expect("start ok entry");
+            zip.write_all(b"ok").expect("write ok entry");
+            zip.start_file("../escape", zip::write::SimpleFileOptions::default())
+                .expect("start evil entry");
+            zip.write_all(b"pwned").expect("write evil entry");
+            zip.finish().expect("finish zip");
+        }
+        let err = extract_archive(&zip_path, &dest).expect_err("zip traversal rejected");
+        assert!(err.to_string().contains("unsafe path in archive"));
+        assert!(dest.join("bin").join("ok.txt").exists());
+        assert!(!dir.path().join("escape").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn integrity_report_lists_missing_and_non_executable_files() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let install = dir.path().join("runner");
+        fs::create_dir_all(install.join("bin")).expect("bin dir");
+        for (file, mode) in [("config.sh", 0o755), ("run.sh", 0o644), ("bin/Runner.Listener", 0o755)] {
+            let path = install.join(file);
+            fs::write(&path, b"").expect("write file");
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).expect("chmod");
+        }
+        fs::write(install.join(".runner"), "\u{feff}{\"agentName\": \"build-01\"}").expect("write .runner");
+        let report = verify_install_integrity(&install);
+        assert!(!report.ok);
+        let failed: Vec<&str> = report
+            .checks
+            .iter()
+            .filter(|check| !check.ok)
+            .map(|check| check.name.as_str())
+            .collect();
+        assert_eq!(failed, vec!["run.sh"]);
+        let runner_check = report.checks.iter().find(|check| check.name == ".runner").unwrap();
+        assert_eq!(runner_check.detail, "registered as build-01");
+
+        let report = verify_install_integrity(&dir.path().join("gone"));
+        assert_eq!(report.checks.len(), 1);
+        assert!(!report.ok);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zip_extraction_applies_unix_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let zip_path = dir.path().join("runner.zip");
+        {
+            let mut zip = zip::ZipWriter::new(File::create(&zip_path).expect("create zip"));
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("run.sh", options.unix_permissions(0o4755))
+                .expect("start script");
+            zip.write_all(b"#!/bin/sh\n").expect("write script");
+            zip.start_file("README.md", options.unix_permissions(0o644))
+                .expect("start readme");
+            zip.write_all(b"docs").expect("write readme");
+            zip.finish().expect("finish zip");
+        }
+        let dest = dir.path().join("runner");
+        extract_archive(&zip_path, &dest).expect("extract zip");
+        let mode = |name: &str| fs::metadata(dest.join(name)).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode("run.sh"), 0o755);
+        assert_eq!(mode("README.md"), 0o644);
+    }
+
+    #[test]
+    fn version_comparison_is_numeric() {
+        assert!(version_is_newer("2.321.0", "2.320.1"));
+        assert!(version_is_newer("2.400.0", "2.99.0"));
+        assert!(!version_is_newer("2.320.0", "2.320.0"));
+        assert!(!version_is_newer("2.319.9", "2.320.0"));
+    }
+
+    #[test]
+    fn job_status_follows_the_most_recent_marker() {
+        use crate::state::RunnerStatus;
+
+        let running = "Running job: build\nJob build completed with result: Succeeded\nListening for Jobs\nRunning job: test\n";
+        assert_eq!(activity_from_job_markers(running).0, RunnerStatus::Running);
+
+        let idle = "Running job: build\nRunning job: test\nJob test completed with result: Failed\n";
+        assert_eq!(activity_from_job_markers(idle), (RunnerStatus::Idle, None));
+
+        let diag = "[INFO Worker] Job started\n[INFO Worker] Job completed\n[INFO Worker] Job started\n[INFO Listener] heartbeat\n";
+        assert_eq!(activity_from_job_markers(diag), (RunnerStatus::Running, None));
+
+        assert_eq!(activity_from_job_markers("Listening for Jobs\n").0, RunnerStatus::Idle);
+    }
+
+    #[test]
+    fn completed_jobs_are_counted_once_across_rotation() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let first = dir.path().join("Runner_20240501-100000-utc.log");
+        fs::write(
+            &first,
+            "Running job: build\nJob build completed with result: Succeeded\nRunning job: test\n",
+        )
+        .expect("write diag");
+        fs::write(dir.path().join("Worker_20240501-100001-utc.log"), "Job completed\n")
+            .expect("write worker");
+
+        let (count, cursor) = count_completed_jobs(dir.path(), None).expect("count");
+        assert_eq!(count, 1);
+        let (count, cursor) = count_completed_jobs(dir.path(), cursor.as_ref()).expect("recount");
+        assert_eq!(count, 0);
+
+        let mut file = OpenOptions::new().append(true).open(&first).expect("open diag");
+        file.write_all(b"Job test completed with result: Failed\nJob lint compl")
+            .expect("append diag");
+        fs::write(
+            dir.path().join("Runner_20240502-080000-utc.log"),
+            "Running job: deploy\nJob deploy completed with result: Succeeded\n",
+        )
+        .expect("write rolled diag");
+        let (count, cursor) = count_completed_jobs(dir.path(), cursor.as_ref()).expect("rotated");
+        assert_eq!(count, 2);
+        assert_eq!(cursor.as_ref().unwrap().file, "Runner_20240502-080000-utc.log");
+
+        fs::remove_file(&first).expect("remove old diag");
+        let (count, _) = count_completed_jobs(dir.path(), cursor.as_ref()).expect("pruned");
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn running_job_carries_name_and_run_url() {
+        let log = "2024-05-01 10:00:00Z: Running job: build\n\
+                   Job build completed with result: Succeeded\n\
+                   2024-05-01 10:05:00Z: Running job: build-and-test\n\
+                   [INFO Worker] Job url: https://github.com/acme/app/actions/runs/123456/job/789\n";
+        let (status, job) = activity_from_job_markers(log);
+        assert_eq!(status, crate::state::RunnerStatus::Running);
+        let job = job.expect("current job");
+        assert_eq!(job.name, "build-and-test");
+        assert_eq!(job.run_id, Some(123456));
+        assert_eq!(
+            job.url.as_deref(),
+            Some("https://github.com/acme/app/actions/runs/123456/job/789")
+        );
+
+        let job = activity_from_job_markers("Running job: lint\n").1.expect("job");
+        assert_eq!(job.run_id, None);
+        assert_eq!(job.url, None);
+    }
+
+    #[test]
+    fn pinned_download_warns_when_another_version_is_requested() {
+        assert_eq!(
+            pinned_download_version(Some("2.319.0"), None),
+            (Some("2.319.0".to_string()), None)
+        );
+        assert_eq!(
+            pinned_download_version(Some("2.319.0"), Some("v2.319.0".to_string())),
+            (Some("v2.319.0".to_string()), None)
+        );
+        let (version, warning) = pinned_download_version(Some("2.319.0"), Some("2.320.0".to_string()));
+        assert_eq!(version.as_deref(), Some("2.320.0"));
+        assert_eq!(
+            warning.as_deref(),
+            Some("runner is pinned to 2.319.0; installing 2.320.0 as requested")
+        );
+        assert_eq!(
+            pinned_download_version(None, Some("2.320.0".to_string())),
+            (Some("2.320.0".to_string()), None)
+        );
+    }
+
+    #[test]
+    fn runner_log_appends_and_rotates_past_threshold() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("runner-stdout.log");
+        fs::write(&path, "crash output\n").expect("write log");
+
+        drop(open_runner_log(&path, 1024).expect("open log"));
+        let content = fs::read_to_string(&path).expect("read log");
+        assert!(content.starts_with("crash output\n"));
+        assert!(content.contains("===== RunnerBuddy started runner at "));
+
+        drop(open_runner_log(&path, 8).expect("rotate log"));
+        let rotated = fs::read_to_string(dir.path().join("runner-stdout.log.1")).expect("read rotated");
+        assert!(rotated.starts_with("crash output\n"));
+        assert!(!fs::read_to_string(&path).expect("read log").contains("crash output"));
+    }
+
+    #[test]
+    fn oversized_log_rotates_in_place_under_an_open_writer() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("runner-stdout.log");
+        let mut writer = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .expect("open log");
+        writer.write_all(b"old output\n").expect("write log");
+
+        assert!(!rotate_open_log(&path, 1024).expect("under cap"));
+        assert!(rotate_open_log(&path, 4).expect("over cap"));
+        writer.write_all(b"new output\n").expect("write after rotation");
+
+        let rotated = fs::read_to_string(dir.path().join("runner-stdout.log.1")).expect("read rotated");
+        assert_eq!(rotated, "old output\n");
+        assert_eq!(fs::read_to_string(&path).expect("read log"), "new output\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stop_runner_signals_before_killing() {
+        use std::os::unix::process::CommandExt;
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg("trap 'exit 0' INT; while true; do sleep 0.1; done")
+            .process_group(0)
+            .spawn()
+            .expect("spawn child");
+        let child_map = std::sync::Mutex::new(HashMap::from([("r1".to_string(), child)]));
+        std::thread::sleep(Duration::from_millis(200));
+        let started = Instant::now();
+        stop_runner("r1", &child_map, Duration::from_secs(10))
+            .await
+            .expect("stop runner");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(child_map.lock().unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stop_runner_kills_the_whole_process_group() {
+        use std::os::unix::process::CommandExt;
+        let dir = tempfile::tempdir().expect("tempdir");
+        let pid_file = dir.path().join("listener.pid");
+        // Stands in for run.sh and a Runner.Listener that both ignore SIGINT.
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(format!(
+                "trap '' INT; sleep 30 & echo $! > {}; wait",
+                pid_file.display()
+            ))
+            .process_group(0)
+            .spawn()
+            .expect("spawn child");
+        let child_map = std::sync::Mutex::new(HashMap::from([("r1".to_string(), child)]));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !pid_file.exists() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let listener = fs::read_to_string(&pid_file).expect("read pid").trim().to_string();
+
+        stop_runner("r1", &child_map, Duration::from_millis(500))
+            .await
+            .expect("stop runner");
+        std::thread::sleep(Duration::from_millis(200));
+        let state = Command::new("ps")
+            .args(["-o", "stat=", "-p", &listener])
+            .output()
+            .expect("run ps");
+        let state = String::from_utf8_lossy(&state.stdout);
+        // Gone, or a zombie waiting for an init that does not reap.
+        assert!(state.trim().is_empty() || state.trim().starts_with('Z'), "listener still alive: {state}");
+    }
+
+    fn write_runner_archive(path: &Path, listener: &[u8]) {
+        let file = File::create(path).expect("create archive");
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let body: &[u8] = b"#!/bin/sh\necho new\n";
+        for (name, data) in [("run.sh", body), ("bin/Runner.Listener", listener)] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, data)
+                .expect("append entry");
+        }
+        builder
+            .into_inner()
+            .expect("finish tar")
+            .finish()
+            .expect("finish gzip");
+    }
+
+    #[test]
+    fn reinstall_keeps_runner_registration() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let archive = dir.path().join("actions-runner-linux-x64-2.400.0.tar.gz");
+        write_runner_archive(&archive, b"#!/bin/sh\n");
+        let install = dir.path().join("runner");
+        fs::create_dir_all(install.join("_work/repo")).expect("create work dir");
+        fs::write(install.join(".runner"), r#"{"agentName":"keep-me"}"#).expect("write .runner");
+        fs::write(install.join(".credentials"), "{}").expect("write .credentials");
+        fs::write(install.join("_work/repo/file.txt"), "work").expect("write work file");
+        fs::write(install.join("stale.dll"), "old").expect("write stale binary");
+
+        install_archive(&archive, &install, &LINUX_X64).expect("install archive");
+
+        assert_eq!(
+            fs::read_to_string(install.join(".runner")).expect("read .runner"),
+            r#"{"agentName":"keep-me"}"#
+        );
+        assert!(install.join(".credentials").exists());
+        assert!(install.join("_work/repo/file.txt").exists());
+        assert!(install.join("run.sh").exists());
+        assert!(!install.join("stale.dll").exists());
+        assert!(!preserve_dir(&install).exists());
+        assert!(!staging_dir(&install).exists());
+    }
+
+    const LINUX_X64: RunnerPlatform = RunnerPlatform {
+        os: "linux",
+        arch: "x64",
+        ext: "tar.gz",
+    };
+
+    #[test]
+    fn wrong_platform_download_leaves_the_install_untouched() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let archive = dir.path().join("actions-runner-linux-x64-2.400.0.tar.gz");
+        let mut pe = vec![0u8; 0x90];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe[0x84..0x86].copy_from_slice(&0x8664u16.to_le_bytes());
+        write_runner_archive(&archive, &pe);
+        let install = dir.path().join("runner");
+        fs::create_dir_all(install.join("bin")).expect("create bin dir");
+        fs::write(install.join(".runner"), r#"{"agentName":"keep-me"}"#).expect("write .runner");
+        fs::write(install.join("bin/Runner.Listener"), "working").expect("write listener");
+
+        let err = install_archive(&archive, &install, &LINUX_X64).expect_err("platform mismatch");
+        assert!(err.to_string().contains("built for win-x64"));
+        assert_eq!(
+            fs::read_to_string(install.join("bin/Runner.Listener")).expect("read listener"),
+            "working"
+        );
+        assert!(install.join(".runner").exists());
+        assert!(!install.join("run.sh").exists());
+        assert!(!staging_dir(&install).exists());
+    }
+
+    #[test]
+    fn install_refuses_to_clear_a_staging_dir_holding_a_registration() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let archive = dir.path().join("actions-runner-linux-x64-2.400.0.tar.gz");
+        write_runner_archive(&archive, b"#!/bin/sh\n");
+        let install = dir.path().join("runner");
+        fs::create_dir_all(&install).expect("create install dir");
+        let staging = staging_dir(&install);
+        fs::create_dir_all(&staging).expect("create staging dir");
+        fs::write(staging.join(".runner"), r#"{"agentName":"stranded"}"#).expect("write .runner");
+
+        let err = install_archive(&archive, &install, &LINUX_X64).expect_err("stranded registration");
+        assert!(err.to_string().contains(&staging.display().to_string()));
+        assert!(staging.join(".runner").exists());
+    }
+
+    #[test]
+    fn prune_keeps_newest_archives_and_skips_active_downloads() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let epoch = std::time::UNIX_EPOCH;
+        for (index, name) in ["a.tar.gz", "b.tar.gz", "c.tar.gz", "d.tar.gz"].iter().enumerate() {
+            let mut file = File::create(dir.path().join(name)).expect("create archive");
+            file.write_all(&vec![0u8; 10 * (index + 1)]).expect("write archive");
+            file.set_modified(epoch + Duration::from_secs(1_000 + index as u64))
+                .expect("set mtime");
+        }
+
+        let usage = downloads_usage(dir.path()).expect("usage");
+        assert_eq!(usage.total_bytes, 100);
+        let names: Vec<_> = usage.archives.iter().map(|archive| archive.name.as_str()).collect();
+        assert_eq!(names, ["d.tar.gz", "c.tar.gz", "b.tar.gz", "a.tar.gz"]);
+
+        let lease = ArchiveLease::acquire(&dir.path().join("a.tar.gz")).expect("lease");
+        assert!(ArchiveLease::acquire(&dir.path().join("a.tar.gz")).is_err());
+        let result = prune_downloads(dir.path(), 2).expect("prune");
+        assert_eq!(result.removed, ["b.tar.gz"]);
+        assert_eq!(result.freed_bytes, 20);
+        assert!(dir.path().join("a.tar.gz").exists());
+
+        drop(lease);
+        let result = prune_downloads(dir.path(), 2).expect("prune");
+        assert_eq!(result.removed, ["a.tar.gz"]);
+        assert!(prune_downloads(&dir.path().join("missing"), 0)
+            .expect("missing dir")
+            .removed
+            .is_empty());
+    }
+}
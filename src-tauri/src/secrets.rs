@@ -1,46 +1,494 @@
+use crate::config::data_dir;
 use crate::errors::Error;
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
 use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+/// Credential material that is wiped from memory when dropped.
+pub type Secret = Zeroizing<String>;
 
 const SERVICE: &str = "RunnerBuddy";
 const LEGACY_ACCOUNT: &str = "pat";
+const PROBE_ACCOUNT: &str = "backend-probe";
+const SECRETS_FILE: &str = "secrets.enc";
+const BACKEND_ENV: &str = "RUNNERBUDDY_SECRET_BACKEND";
+const PASSPHRASE_ENV: &str = "RUNNERBUDDY_SECRETS_PASSPHRASE";
+const FILE_VERSION: u32 = 1;
+const KDF_ROUNDS: u32 = 200_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretBackend {
+    Keyring,
+    File,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SecretBackendInfo {
+    pub backend: SecretBackend,
+    pub location: Option<String>,
+    pub passphrase: bool,
+}
+
+/// The OS keyring when one is reachable, otherwise the encrypted file under the
+/// data dir. `RUNNERBUDDY_SECRET_BACKEND=file` forces the file store. Only a
+/// missing or unusable keyring backend selects the file store, and that choice is
+/// kept for the process; any other probe error is returned and probed again later,
+/// so a transient failure never hides the secrets already in the keyring.
+fn backend() -> Result<SecretBackend, Error> {
+    static BACKEND: OnceLock<SecretBackend> = OnceLock::new();
+    if let Some(backend) = BACKEND.get() {
+        return Ok(*backend);
+    }
+    if std::env::var(BACKEND_ENV).is_ok_and(|value| value.eq_ignore_ascii_case("file")) {
+        info!("Secrets stored in encrypted file ({BACKEND_ENV}=file)");
+        return Ok(*BACKEND.get_or_init(|| SecretBackend::File));
+    }
+    let probe = Entry::new(SERVICE, PROBE_ACCOUNT).and_then(|entry| entry.get_password());
+    let backend = match probe {
+        Ok(_) | Err(keyring::Error::NoEntry) => SecretBackend::Keyring,
+        Err(err @ (keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_))) => {
+            warn!("OS credential store unavailable ({err}); falling back to encrypted secrets file");
+            SecretBackend::File
+        }
+        Err(err) => return Err(keyring_error(err)),
+    };
+    Ok(*BACKEND.get_or_init(|| backend))
+}
+
+pub fn backend_info() -> Result<SecretBackendInfo, Error> {
+    let backend = backend()?;
+    Ok(SecretBackendInfo {
+        backend,
+        location: match backend {
+            SecretBackend::Keyring => None,
+            SecretBackend::File => secrets_file_path()
+                .ok()
+                .map(|path| path.to_string_lossy().to_string()),
+        },
+        passphrase: backend == SecretBackend::File && passphrase().is_some(),
+    })
+}
+
+fn keyring_error(err: keyring::Error) -> Error {
+    Error::Secrets(err.to_string())
+}
+
+fn get_secret(account: &str) -> Result<Option<Secret>, Error> {
+    match backend()? {
+        SecretBackend::Keyring => {
+            match Entry::new(SERVICE, account).and_then(|entry| entry.get_password()) {
+                Ok(value) => Ok(Some(Zeroizing::new(value))),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(err) => Err(keyring_error(err)),
+            }
+        }
+        SecretBackend::File => {
+            let mut entries = read_entries(&secrets_file_path()?)?;
+            Ok(entries.remove(account))
+        }
+    }
+}
+
+fn set_secret(account: &str, value: &str) -> Result<(), Error> {
+    match backend()? {
+        SecretBackend::Keyring => Entry::new(SERVICE, account)
+            .and_then(|entry| entry.set_password(value))
+            .map_err(keyring_error),
+        SecretBackend::File => update_entries(&secrets_file_path()?, |entries| {
+            entries.insert(account.to_string(), Zeroizing::new(value.to_string()));
+            true
+        }),
+    }
+}
+
+fn delete_secret(account: &str) -> Result<(), Error> {
+    match backend()? {
+        SecretBackend::Keyring => {
+            match Entry::new(SERVICE, account).and_then(|entry| entry.delete_password()) {
+                Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(err) => Err(keyring_error(err)),
+            }
+        }
+        SecretBackend::File => update_entries(&secrets_file_path()?, |entries| {
+            entries.remove(account).is_some()
+        }),
+    }
+}
 
-fn entry(alias: &str) -> Result<Entry, Error> {
-    Entry::new(SERVICE, &format!("pat:{alias}"))
-        .map_err(|err| Error::Secrets(err.to_string()))
+fn pat_account(alias: &str) -> String {
+    format!("pat:{alias}")
 }
 
-fn legacy_entry() -> Result<Entry, Error> {
-    Entry::new(SERVICE, LEGACY_ACCOUNT).map_err(|err| Error::Secrets(err.to_string()))
+fn app_key_account(alias: &str) -> String {
+    format!("app-key:{alias}")
 }
 
 pub fn save_pat(alias: &str, pat: &str) -> Result<(), Error> {
-    entry(alias)?
-        .set_password(pat)
-        .map_err(|err| Error::Secrets(err.to_string()))
-}
-
-pub fn load_pat(alias: &str) -> Result<Option<String>, Error> {
-    match entry(alias)?.get_password() {
-        Ok(value) => Ok(Some(value)),
-        Err(keyring::Error::NoEntry) => {
-            if alias == "default" {
-                match legacy_entry()?.get_password() {
-                    Ok(value) => Ok(Some(value)),
-                    Err(keyring::Error::NoEntry) => Ok(None),
-                    Err(err) => Err(Error::Secrets(err.to_string())),
+    set_secret(&pat_account(alias), pat)
+}
+
+pub fn load_pat(alias: &str) -> Result<Option<Secret>, Error> {
+    match get_secret(&pat_account(alias))? {
+        Some(value) => Ok(Some(value)),
+        None if alias == "default" => get_secret(LEGACY_ACCOUNT),
+        None => Ok(None),
+    }
+}
+
+pub fn clear_pat(alias: &str) -> Result<(), Error> {
+    delete_secret(&pat_account(alias))?;
+    // `load_pat` falls back to the legacy entry for the default alias, so clear it
+    // too or the old token would resurface.
+    if alias == "default" {
+        delete_secret(LEGACY_ACCOUNT)?;
+    }
+    Ok(())
+}
+
+fn move_pat(old: &str, new: &str) -> Result<bool, Error> {
+    let Some(pat) = load_pat(old)? else {
+        return Ok(false);
+    };
+    save_pat(new, &pat)?;
+    if let Err(err) = clear_pat(old) {
+        let _ = delete_secret(&pat_account(new));
+        return Err(err);
+    }
+    Ok(true)
+}
+
+pub fn save_app_key(alias: &str, pem: &str) -> Result<(), Error> {
+    set_secret(&app_key_account(alias), pem)
+}
+
+pub fn load_app_key(alias: &str) -> Result<Option<Secret>, Error> {
+    get_secret(&app_key_account(alias))
+}
+
+pub fn clear_app_key(alias: &str) -> Result<(), Error> {
+    delete_secret(&app_key_account(alias))
+}
+
+fn move_app_key(old: &str, new: &str) -> Result<bool, Error> {
+    let Some(pem) = load_app_key(old)? else {
+        return Ok(false);
+    };
+    save_app_key(new, &pem)?;
+    if let Err(err) = clear_app_key(old) {
+        let _ = clear_app_key(new);
+        return Err(err);
+    }
+    Ok(true)
+}
+
+/// Which credentials `move_alias` found under the old alias.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MovedSecrets {
+    pub pat: bool,
+    pub app_key: bool,
+}
+
+impl MovedSecrets {
+    pub fn any(&self) -> bool {
+        self.pat || self.app_key
+    }
+}
+
+/// Moves every credential stored under `old` to `new`. If a later move fails, the
+/// ones already moved are put back so the alias is never left half renamed.
+pub fn move_alias(old: &str, new: &str) -> Result<MovedSecrets, Error> {
+    let pat = move_pat(old, new)?;
+    match move_app_key(old, new) {
+        Ok(app_key) => Ok(MovedSecrets { pat, app_key }),
+        Err(err) => {
+            if pat {
+                if let Err(undo) = move_pat(new, old) {
+                    warn!("failed to restore PAT for alias {old} after a failed rename: {undo}");
                 }
-            } else {
-                Ok(None)
             }
+            Err(err)
         }
-        Err(err) => Err(Error::Secrets(err.to_string())),
     }
 }
 
-pub fn clear_pat(alias: &str) -> Result<(), Error> {
-    match entry(alias)?.delete_password() {
-        Ok(()) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(err) => Err(Error::Secrets(err.to_string())),
+fn secrets_file_path() -> Result<PathBuf, Error> {
+    Ok(data_dir()?.join(SECRETS_FILE))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum KeySource {
+    Machine,
+    Passphrase,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u32,
+    key_source: KeySource,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+struct KeyMaterial {
+    source: KeySource,
+    value: Secret,
+}
+
+fn passphrase() -> Option<Secret> {
+    std::env::var(PASSPHRASE_ENV)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .map(Zeroizing::new)
+}
+
+/// Passphrase from the environment when set, otherwise a key bound to this
+/// machine's id. The machine key only keeps the file useless when copied elsewhere.
+fn key_material() -> Result<KeyMaterial, Error> {
+    match passphrase() {
+        Some(value) => Ok(KeyMaterial {
+            source: KeySource::Passphrase,
+            value,
+        }),
+        None => machine_key_material(),
+    }
+}
+
+/// Refuses to fall back to a constant key when no machine id or hostname exists.
+fn machine_key_material() -> Result<KeyMaterial, Error> {
+    let machine_id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+        .or_else(|| {
+            hostname::get()
+                .ok()
+                .map(|name| name.to_string_lossy().trim().to_string())
+                .filter(|name| !name.is_empty())
+        })
+        .ok_or_else(|| {
+            Error::Secrets(format!(
+                "no machine id or hostname to derive the secrets file key from; set {PASSPHRASE_ENV}"
+            ))
+        })?;
+    Ok(KeyMaterial {
+        source: KeySource::Machine,
+        value: Zeroizing::new(format!("{SERVICE}:{machine_id}")),
+    })
+}
+
+fn material_for(source: KeySource) -> Result<KeyMaterial, Error> {
+    match source {
+        KeySource::Machine => machine_key_material(),
+        KeySource::Passphrase => passphrase()
+            .map(|value| KeyMaterial { source, value })
+            .ok_or_else(|| {
+                Error::Secrets(format!(
+                    "secrets file is passphrase-protected; set {PASSPHRASE_ENV} to unlock it"
+                ))
+            }),
+    }
+}
+
+fn derive_key(material: &str, salt: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(material.as_bytes(), salt, KDF_ROUNDS, key.as_mut());
+    key
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], Error> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|err| Error::Secrets(format!("failed to gather randomness: {err}")))?;
+    Ok(bytes)
+}
+
+fn read_entries(path: &Path) -> Result<BTreeMap<String, Secret>, Error> {
+    let raw = match std::fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let file: EncryptedFile = serde_json::from_str(&raw)?;
+    if file.version != FILE_VERSION {
+        return Err(Error::Secrets(format!(
+            "unsupported secrets file version {}",
+            file.version
+        )));
+    }
+    decrypt_entries(&file, &material_for(file.key_source)?)
+}
+
+fn decrypt_entries(
+    file: &EncryptedFile,
+    material: &KeyMaterial,
+) -> Result<BTreeMap<String, Secret>, Error> {
+    let decode = |value: &str| {
+        hex::decode(value).map_err(|err| Error::Secrets(format!("corrupt secrets file: {err}")))
+    };
+    let salt = decode(&file.salt)?;
+    let nonce = decode(&file.nonce)?;
+    if nonce.len() != 12 {
+        return Err(Error::Secrets("corrupt secrets file: bad nonce".into()));
+    }
+    let key = derive_key(&material.value, &salt);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+    let plaintext = Zeroizing::new(
+        cipher
+            .decrypt(Nonce::from_slice(&nonce), decode(&file.ciphertext)?.as_slice())
+            .map_err(|_| {
+                Error::Secrets(format!(
+                    "unable to decrypt secrets file; it was written on another machine or with a different {PASSPHRASE_ENV}"
+                ))
+            })?,
+    );
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Read-modify-write of the secrets file; `update` returns whether anything
+/// changed. Writers are serialized so concurrent updates can't drop each other's
+/// entries.
+fn update_entries(
+    path: &Path,
+    update: impl FnOnce(&mut BTreeMap<String, Secret>) -> bool,
+) -> Result<(), Error> {
+    static WRITE_LOCK: Mutex<()> = Mutex::new(());
+    let _guard = WRITE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut entries = read_entries(path)?;
+    if update(&mut entries) {
+        write_entries(path, &entries, &key_material()?)?;
+    }
+    Ok(())
+}
+
+fn write_entries(
+    path: &Path,
+    entries: &BTreeMap<String, Secret>,
+    material: &KeyMaterial,
+) -> Result<(), Error> {
+    let salt = random_bytes::<16>()?;
+    let nonce = random_bytes::<12>()?;
+    let key = derive_key(&material.value, &salt);
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+    let plaintext = Zeroizing::new(serde_json::to_vec(entries)?);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| Error::Secrets("failed to encrypt secrets file".into()))?;
+    let file = EncryptedFile {
+        version: FILE_VERSION,
+        key_source: material.source,
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce),
+        ciphertext: hex::encode(ciphertext),
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("enc.tmp");
+    // A leftover from an interrupted write would make `create_new` fail.
+    let _ = std::fs::remove_file(&tmp);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut out = options.open(&tmp)?;
+    out.write_all(&serde_json::to_vec_pretty(&file)?)?;
+    out.sync_all()?;
+    drop(out);
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_file_round_trips_and_rejects_wrong_key() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(SECRETS_FILE);
+        let material = KeyMaterial {
+            source: KeySource::Passphrase,
+            value: Zeroizing::new("correct horse".to_string()),
+        };
+        let mut entries = BTreeMap::new();
+        entries.insert(
+            pat_account("default"),
+            Zeroizing::new("ghp_supersecretvalue".to_string()),
+        );
+        write_entries(&path, &entries, &material).expect("write");
+
+        let raw = std::fs::read_to_string(&path).expect("read");
+        assert!(!raw.contains("ghp_supersecretvalue"));
+        let file: EncryptedFile = serde_json::from_str(&raw).expect("parse");
+        assert_eq!(file.key_source, KeySource::Passphrase);
+
+        let decrypted = decrypt_entries(&file, &material).expect("decrypt");
+        assert_eq!(
+            decrypted.get("pat:default").map(|value| value.as_str()),
+            Some("ghp_supersecretvalue")
+        );
+
+        let wrong = KeyMaterial {
+            source: KeySource::Passphrase,
+            value: Zeroizing::new("battery staple".to_string()),
+        };
+        assert!(decrypt_entries(&file, &wrong).is_err());
+    }
+
+    #[test]
+    fn concurrent_updates_keep_every_entry() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(SECRETS_FILE);
+        std::thread::scope(|scope| {
+            for index in 0..3 {
+                let path = &path;
+                scope.spawn(move || {
+                    update_entries(path, |entries| {
+                        entries.insert(
+                            pat_account(&format!("alias-{index}")),
+                            Zeroizing::new(index.to_string()),
+                        );
+                        true
+                    })
+                    .expect("update");
+                });
+            }
+        });
+        let entries = read_entries(&path).expect("read");
+        assert_eq!(entries.len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secrets_file_is_created_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join(SECRETS_FILE);
+        std::fs::write(path.with_extension("enc.tmp"), "stale").expect("stale tmp");
+        let material = KeyMaterial {
+            source: KeySource::Passphrase,
+            value: Zeroizing::new("correct horse".to_string()),
+        };
+        write_entries(&path, &BTreeMap::new(), &material).expect("write");
+        let mode = std::fs::metadata(&path).expect("metadata").permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!path.with_extension("enc.tmp").exists());
     }
 }
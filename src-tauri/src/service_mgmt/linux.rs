@@ -1,54 +1,90 @@
-use crate::config::{RunnerProfile, ServiceProvider};
+use crate::config::{RestartPolicy, RunnerProfile, ServiceProvider};
 use crate::errors::Error;
-use crate::service_mgmt::ServiceStatus;
+use crate::service_mgmt::{
+    is_root, privileged, privileged_install, service_user, ServiceRestarts, ServiceStatus,
+};
 use crate::util::expand_path;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use tracing::warn;
+use tracing::{info, warn};
+
+const SYSTEM_UNIT_DIR: &str = "/etc/systemd/system";
+const LINGER_DIR: &str = "/var/lib/systemd/linger";
 
 pub fn install(profile: &RunnerProfile) -> Result<(), Error> {
-    let unit_path = unit_path(&profile.runner_id)?;
-    if let Some(parent) = unit_path.parent() {
-        fs::create_dir_all(parent)?;
+    write_unit(profile)?;
+    let unit_name = unit_name(&profile.runner_id);
+    managed_systemctl(profile, &["daemon-reload"])?;
+    managed_systemctl(profile, &["enable", &unit_name])?;
+    managed_systemctl(profile, &["start", &unit_name])?;
+    if !profile.service.system_scope {
+        ensure_linger();
     }
+    Ok(())
+}
+
+/// Rewrites an installed unit from the profile and reloads systemd so changed
+/// settings such as the restart policy apply on the next (re)start.
+pub fn refresh_unit(profile: &RunnerProfile) -> Result<(), Error> {
+    if !unit_path(profile)?.exists() {
+        return Ok(());
+    }
+    write_unit(profile)?;
+    managed_systemctl(profile, &["daemon-reload"])?;
+    Ok(())
+}
+
+fn write_unit(profile: &RunnerProfile) -> Result<(), Error> {
+    let unit_path = unit_path(profile)?;
     let unit = systemd_unit_content(profile);
-    fs::write(&unit_path, unit)?;
-    systemctl(&["--user", "daemon-reload"])?;
-    systemctl(&["--user", "enable", &unit_name(&profile.runner_id)])?;
-    systemctl(&["--user", "start", &unit_name(&profile.runner_id)])?;
+    if profile.service.system_scope {
+        write_system_unit(&unit_path, &unit)?;
+    } else {
+        if let Some(parent) = unit_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&unit_path, unit)?;
+    }
     Ok(())
 }
 
 pub fn uninstall(profile: &RunnerProfile) -> Result<(), Error> {
-    let unit_path = unit_path(&profile.runner_id)?;
+    let unit_path = unit_path(profile)?;
     let unit_name = unit_name(&profile.runner_id);
-    let _ = systemctl(&["--user", "stop", &unit_name]);
-    let _ = systemctl(&["--user", "disable", &unit_name]);
+    let _ = managed_systemctl(profile, &["stop", &unit_name]);
+    let _ = managed_systemctl(profile, &["disable", &unit_name]);
     if unit_path.exists() {
-        fs::remove_file(unit_path)?;
+        if profile.service.system_scope {
+            privileged(&["rm", "-f", &unit_path.to_string_lossy()])?;
+        } else {
+            fs::remove_file(unit_path)?;
+        }
     }
-    systemctl(&["--user", "daemon-reload"])?;
+    managed_systemctl(profile, &["daemon-reload"])?;
     Ok(())
 }
 
 pub fn enable_on_boot(profile: &RunnerProfile, enabled: bool) -> Result<(), Error> {
     let unit_name = unit_name(&profile.runner_id);
     if enabled {
-        systemctl(&["--user", "enable", &unit_name])?;
+        managed_systemctl(profile, &["enable", &unit_name])?;
+        if !profile.service.system_scope {
+            ensure_linger();
+        }
     } else {
-        systemctl(&["--user", "disable", &unit_name])?;
+        managed_systemctl(profile, &["disable", &unit_name])?;
     }
     Ok(())
 }
 
 pub fn start(profile: &RunnerProfile) -> Result<(), Error> {
-    systemctl(&["--user", "start", &unit_name(&profile.runner_id)])?;
+    managed_systemctl(profile, &["start", &unit_name(&profile.runner_id)])?;
     Ok(())
 }
 
 pub fn stop(profile: &RunnerProfile) -> Result<(), Error> {
-    systemctl(&["--user", "stop", &unit_name(&profile.runner_id)])?;
+    managed_systemctl(profile, &["stop", &unit_name(&profile.runner_id)])?;
     Ok(())
 }
 
@@ -57,13 +93,17 @@ pub fn status(profile: &RunnerProfile) -> Result<ServiceStatus, Error> {
         return external_status(profile);
     }
     let unit = unit_name(&profile.runner_id);
-    let installed = unit_path(&profile.runner_id)?.exists();
-    let running = systemctl_status(&["--user", "is-active", &unit]).unwrap_or(false);
-    let enabled = systemctl_status(&["--user", "is-enabled", &unit]).unwrap_or(false);
+    let installed = unit_path(profile)?.exists();
+    let scope = scope_flag(profile);
+    let running = systemctl_status(&[scope, "is-active", &unit]).unwrap_or(false);
+    let enabled = systemctl_status(&[scope, "is-enabled", &unit]).unwrap_or(false);
     Ok(ServiceStatus {
         installed,
         running,
         enabled,
+        linger: (!profile.service.system_scope).then(linger_enabled),
+        launchd_kind: None,
+        restarts: installed.then(|| unit_restarts(scope, &unit)).flatten(),
     })
 }
 
@@ -80,6 +120,9 @@ pub fn external_status(profile: &RunnerProfile) -> Result<ServiceStatus, Error>
                 installed,
                 running: false,
                 enabled: false,
+                linger: None,
+                launchd_kind: None,
+                restarts: None,
             });
         }
     };
@@ -89,6 +132,9 @@ pub fn external_status(profile: &RunnerProfile) -> Result<ServiceStatus, Error>
         installed,
         running,
         enabled,
+        linger: None,
+        launchd_kind: None,
+        restarts: installed.then(|| unit_restarts("--user", &unit)).flatten(),
     })
 }
 
@@ -99,6 +145,20 @@ pub fn external_disable(profile: &RunnerProfile) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn external_enable(profile: &RunnerProfile) -> Result<(), Error> {
+    let unit = external_unit_name(profile)?;
+    if let Some(path) = external_unit_path(profile).filter(|path| !path.exists()) {
+        return Err(Error::Service(format!(
+            "external unit {} no longer exists",
+            path.display()
+        )));
+    }
+    systemctl(&["--user", "daemon-reload"])?;
+    systemctl(&["--user", "enable", &unit])?;
+    systemctl(&["--user", "start", &unit])?;
+    Ok(())
+}
+
 pub fn external_remove_artifacts(profile: &RunnerProfile) -> Result<(), Error> {
     let _ = external_disable(profile);
     if let Some(path) = external_unit_path(profile) {
@@ -110,35 +170,99 @@ pub fn external_remove_artifacts(profile: &RunnerProfile) -> Result<(), Error> {
     Ok(())
 }
 
+/// Removes a user unit whose runner directory no longer exists.
+pub fn remove_orphaned_service(unit: &str, unit_path: Option<&Path>) -> Result<(), Error> {
+    let _ = systemctl(&["--user", "stop", unit]);
+    let _ = systemctl(&["--user", "disable", unit]);
+    if let Some(path) = unit_path.filter(|path| path.exists()) {
+        fs::remove_file(path)?;
+    }
+    systemctl(&["--user", "daemon-reload"])?;
+    Ok(())
+}
+
 pub fn systemd_unit_content(profile: &RunnerProfile) -> String {
     let install_path = expand_path(&profile.install.install_path);
     let run_script = install_path.join("run.sh");
+    let (after, user, wanted_by) = if profile.service.system_scope {
+        (
+            "network-online.target",
+            format!("User={}\n", service_user(profile)),
+            "multi-user.target",
+        )
+    } else {
+        ("network.target", String::new(), "default.target")
+    };
+    let service = &profile.service;
+    let mut start_limits = String::new();
+    if let Some(interval) = service.start_limit_interval_secs {
+        start_limits.push_str(&format!("StartLimitIntervalSec={interval}\n"));
+    }
+    if let Some(burst) = service.start_limit_burst {
+        start_limits.push_str(&format!("StartLimitBurst={burst}\n"));
+    }
+    let mut limits = String::new();
+    if let Some(memory_max) = service.memory_max.as_deref().filter(|value| !value.is_empty()) {
+        limits.push_str(&format!("MemoryMax={memory_max}\n"));
+    }
+    if let Some(cpu_quota) = service.cpu_quota.as_deref().filter(|value| !value.is_empty()) {
+        limits.push_str(&format!("CPUQuota={cpu_quota}\n"));
+    }
+    let environment: String = profile
+        .env
+        .iter()
+        .map(|(key, value)| format!("Environment=\"{}\"\n", systemd_escape(&format!("{key}={value}"))))
+        .collect();
     format!(
         r#"[Unit]
 Description=RunnerBuddy GitHub Actions Runner ({runner_id})
-After=network.target
-
+After={after}
+{start_limits}
 [Service]
 Type=simple
-WorkingDirectory={install_path}
+{user}{environment}WorkingDirectory={install_path}
 ExecStart={run_script}
-Restart=always
-RestartSec=5
-
+Restart={restart}
+RestartSec={restart_sec}
+{limits}
 [Install]
-WantedBy=default.target
+WantedBy={wanted_by}
 "#,
         runner_id = profile.runner_id,
         install_path = install_path.to_string_lossy(),
-        run_script = run_script.to_string_lossy()
+        run_script = run_script.to_string_lossy(),
+        restart = restart_policy(profile).as_systemd(),
+        restart_sec = service.restart_sec
     )
 }
 
-fn unit_name(runner_id: &str) -> String {
+/// An ephemeral runner deregisters after its one job, so restarting it would only
+/// fail over and over; like launchd's `KeepAlive`, it is never restarted.
+fn restart_policy(profile: &RunnerProfile) -> RestartPolicy {
+    if profile.ephemeral {
+        RestartPolicy::No
+    } else {
+        profile.service.restart_policy
+    }
+}
+
+/// Quotes a value for a double-quoted unit setting; `%` would otherwise start a
+/// systemd specifier.
+fn systemd_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+}
+
+pub(crate) fn unit_name(runner_id: &str) -> String {
     format!("runnerbuddy-{runner_id}.service")
 }
 
-fn unit_path(runner_id: &str) -> Result<PathBuf, Error> {
+fn unit_path(profile: &RunnerProfile) -> Result<PathBuf, Error> {
+    if profile.service.system_scope {
+        return Ok(PathBuf::from(SYSTEM_UNIT_DIR).join(unit_name(&profile.runner_id)));
+    }
     let user_dirs = directories::UserDirs::new()
         .ok_or_else(|| Error::Service("unable to resolve user home".into()))?;
     Ok(user_dirs
@@ -146,7 +270,76 @@ fn unit_path(runner_id: &str) -> Result<PathBuf, Error> {
         .join(".config")
         .join("systemd")
         .join("user")
-        .join(unit_name(runner_id)))
+        .join(unit_name(&profile.runner_id)))
+}
+
+fn scope_flag(profile: &RunnerProfile) -> &'static str {
+    if profile.service.system_scope {
+        "--system"
+    } else {
+        "--user"
+    }
+}
+
+fn current_user() -> Option<String> {
+    std::env::var("USER")
+        .ok()
+        .filter(|user| !user.is_empty())
+        .or_else(|| {
+            Command::new("id")
+                .arg("-un")
+                .output()
+                .ok()
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                .filter(|user| !user.is_empty())
+        })
+}
+
+fn linger_enabled() -> bool {
+    current_user()
+        .map(|user| Path::new(LINGER_DIR).join(user).exists())
+        .unwrap_or(false)
+}
+
+/// User services only start at boot when lingering is on. Failure is logged rather
+/// than returned since the unit itself was enabled; `status` reports `linger`.
+fn ensure_linger() {
+    if linger_enabled() {
+        return;
+    }
+    let Some(user) = current_user() else {
+        warn!("unable to resolve the current user; systemd lingering not enabled");
+        return;
+    };
+    match Command::new("loginctl").args(["enable-linger", &user]).status() {
+        Ok(status) if status.success() => info!("Enabled systemd lingering for {user}"),
+        Ok(status) => warn!(
+            "loginctl enable-linger {user} failed ({status}); the runner will only start after {user} logs in"
+        ),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => warn!(
+            "loginctl not found; enable lingering for {user} manually or the runner will only start after login"
+        ),
+        Err(err) => warn!("failed to run loginctl enable-linger: {err}"),
+    }
+}
+
+fn write_system_unit(unit_path: &Path, unit: &str) -> Result<(), Error> {
+    if is_root() {
+        fs::write(unit_path, unit)?;
+        return Ok(());
+    }
+    privileged_install(unit, unit_path, &[])
+}
+
+fn managed_systemctl(profile: &RunnerProfile, args: &[&str]) -> Result<(), Error> {
+    if !profile.service.system_scope {
+        let mut user_args = vec!["--user"];
+        user_args.extend_from_slice(args);
+        return systemctl(&user_args);
+    }
+    let mut system_args = vec!["systemctl"];
+    system_args.extend_from_slice(args);
+    privileged(&system_args)
 }
 
 fn external_unit_name(profile: &RunnerProfile) -> Result<String, Error> {
@@ -177,6 +370,32 @@ fn systemctl(args: &[&str]) -> Result<(), Error> {
     Ok(())
 }
 
+fn unit_restarts(scope: &str, unit: &str) -> Option<ServiceRestarts> {
+    let output = Command::new("systemctl")
+        .args([scope, "show", "-p", "NRestarts", "-p", "ExecMainStatus", unit])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    parse_unit_restarts(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Reads `systemctl show -p NRestarts -p ExecMainStatus` output.
+fn parse_unit_restarts(output: &str) -> Option<ServiceRestarts> {
+    let mut count = None;
+    let mut last_exit_code = None;
+    for line in output.lines() {
+        match line.split_once('=') {
+            Some(("NRestarts", value)) => count = value.trim().parse().ok(),
+            Some(("ExecMainStatus", value)) => last_exit_code = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+    Some(ServiceRestarts {
+        count: count?,
+        last_exit_code,
+    })
+}
+
 fn systemctl_status(args: &[&str]) -> Option<bool> {
     Command::new("systemctl")
         .args(args)
@@ -188,31 +407,90 @@ fn systemctl_status(args: &[&str]) -> Option<bool> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{InstallConfig, InstallMode, RunnerProfile, RunnerServiceConfig};
+    use crate::config::test_profile;
 
     #[test]
     fn unit_contains_exec_start() {
-        let profile = RunnerProfile {
-            runner_id: "abc".to_string(),
-            display_name: "Test".to_string(),
-            scope: None,
-            runner_name: "runner".to_string(),
-            labels: vec!["self-hosted".to_string()],
-            work_dir: "/tmp".to_string(),
-            install: InstallConfig {
-                mode: InstallMode::Managed,
-                install_path: "/tmp/runner".to_string(),
-                adopted_from_path: None,
-                migration_status: crate::config::MigrationStatus::None,
-            },
-            runner_version: None,
-            pat_alias: "default".to_string(),
-            service: RunnerServiceConfig::default(),
-            created_at: "2024-01-01T00:00:00Z".to_string(),
-            last_seen_at: None,
-        };
+        let profile = test_profile();
         let unit = systemd_unit_content(&profile);
         assert!(unit.contains("ExecStart="));
         assert!(unit.contains("runnerbuddy-abc.service"));
     }
+
+    #[test]
+    fn system_scope_unit_runs_as_configured_user() {
+        let mut profile = test_profile();
+        assert!(!systemd_unit_content(&profile).contains("User="));
+        profile.service.system_scope = true;
+        profile.service.system_user = Some("ci".to_string());
+        let unit = systemd_unit_content(&profile);
+        assert!(unit.contains("User=ci\n"));
+        assert!(unit.contains("WantedBy=multi-user.target"));
+        assert_eq!(
+            unit_path(&profile).expect("unit path"),
+            PathBuf::from("/etc/systemd/system/runnerbuddy-abc.service")
+        );
+    }
+
+    #[test]
+    fn unit_renders_restart_policy_and_start_limits() {
+        let mut profile = RunnerProfile {
+            ephemeral: true,
+            ..test_profile()
+        };
+        let unit = systemd_unit_content(&profile);
+        assert!(unit.contains("Restart=no\nRestartSec=5\n"));
+        assert!(!unit.contains("StartLimit"));
+
+        profile.ephemeral = false;
+        assert!(systemd_unit_content(&profile).contains("Restart=always\nRestartSec=5\n"));
+
+        profile.service.restart_policy = RestartPolicy::OnFailure;
+        profile.service.restart_sec = 30;
+        profile.service.start_limit_interval_secs = Some(600);
+        profile.service.start_limit_burst = Some(3);
+        let unit = systemd_unit_content(&profile);
+        assert!(unit.contains("Restart=on-failure\nRestartSec=30\n"));
+        let unit_section = unit.split("[Service]").next().unwrap();
+        assert!(unit_section.contains("StartLimitIntervalSec=600\n"));
+        assert!(unit_section.contains("StartLimitBurst=3\n"));
+    }
+
+    #[test]
+    fn unit_renders_resource_limits_only_when_set() {
+        let mut profile = test_profile();
+        let unit = systemd_unit_content(&profile);
+        assert!(!unit.contains("MemoryMax="));
+        assert!(!unit.contains("CPUQuota="));
+
+        profile.service.memory_max = Some("4G".to_string());
+        profile.service.cpu_quota = Some("150%".to_string());
+        let unit = systemd_unit_content(&profile);
+        let service_section = unit.split("[Service]").nth(1).unwrap();
+        assert!(service_section.contains("MemoryMax=4G\n"));
+        assert!(service_section.contains("CPUQuota=150%\n"));
+    }
+
+    #[test]
+    fn unit_quotes_environment_entries() {
+        let mut profile = test_profile();
+        assert!(!systemd_unit_content(&profile).contains("Environment="));
+        profile
+            .env
+            .insert("http_proxy".to_string(), "http://proxy:3128".to_string());
+        profile
+            .env
+            .insert("GREETING".to_string(), r#"say "hi" at 100%"#.to_string());
+        let unit = systemd_unit_content(&profile);
+        assert!(unit.contains("Environment=\"http_proxy=http://proxy:3128\"\n"));
+        assert!(unit.contains(r#"Environment="GREETING=say \"hi\" at 100%%""#));
+    }
+
+    #[test]
+    fn parses_restart_counters() {
+        let restarts = parse_unit_restarts("NRestarts=4\nExecMainStatus=127\n").expect("counters");
+        assert_eq!(restarts.count, 4);
+        assert_eq!(restarts.last_exit_code, Some(127));
+        assert_eq!(parse_unit_restarts("ExecMainStatus=0\n"), None);
+    }
 }
@@ -1,6 +1,9 @@
-use crate::config::{RunnerProfile, ServiceProvider};
+use crate::config::{KeepAlive, ProcessType, RunnerProfile, ServiceProvider};
 use crate::errors::Error;
-use crate::service_mgmt::ServiceStatus;
+use crate::service_mgmt::{
+    is_root, privileged, privileged_install, service_user, LaunchdKind, ServiceRestarts,
+    ServiceStatus,
+};
 use crate::util::expand_path;
 use plist::Value;
 use std::fs;
@@ -8,54 +11,93 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use tracing::warn;
 
+const DAEMON_DIR: &str = "/Library/LaunchDaemons";
+const DAEMON_OWNER: [&str; 4] = ["-o", "root", "-g", "wheel"];
+
 pub fn install(profile: &RunnerProfile) -> Result<(), Error> {
-    let plist_path = plist_path(&profile.runner_id)?;
+    let plist_path = plist_path(profile)?;
     let log_dir = crate::config::runner_logs_dir(&profile.runner_id)?;
     fs::create_dir_all(&log_dir)?;
-    let plist = launchd_plist_content(profile, &log_dir);
-    fs::write(&plist_path, plist)?;
-    let _ = bootout(&profile.runner_id);
-    bootstrap(&plist_path)?;
+    write_plist(profile, &plist_path, &launchd_plist_content(profile, &log_dir))?;
+    let _ = bootout(profile);
+    bootstrap(profile, &plist_path)?;
     Ok(())
 }
 
 pub fn uninstall(profile: &RunnerProfile) -> Result<(), Error> {
-    let plist_path = plist_path(&profile.runner_id)?;
-    let _ = bootout(&profile.runner_id);
+    let plist_path = plist_path(profile)?;
+    let _ = bootout(profile);
     if plist_path.exists() {
-        fs::remove_file(plist_path)?;
+        if profile.service.system_scope {
+            privileged(&["rm", "-f", &plist_path.to_string_lossy()])?;
+        } else {
+            fs::remove_file(plist_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Rewrites an installed plist and, when the job is loaded, boots it out and back
+/// in: launchd only reads keep-alive, priority and the environment at load.
+/// Restart policy and start limits are systemd settings and have no launchd
+/// equivalent here.
+pub fn refresh_unit(profile: &RunnerProfile) -> Result<(), Error> {
+    let plist_path = plist_path(profile)?;
+    if !plist_path.exists() {
+        return Ok(());
+    }
+    let log_dir = crate::config::runner_logs_dir(&profile.runner_id)?;
+    write_plist(profile, &plist_path, &launchd_plist_content(profile, &log_dir))?;
+    if status(profile)?.installed {
+        let _ = bootout(profile);
+        bootstrap(profile, &plist_path)?;
     }
     Ok(())
 }
 
 pub fn enable_on_boot(profile: &RunnerProfile, enabled: bool) -> Result<(), Error> {
-    let label = label_for(&profile.runner_id);
-    let scope = launchctl_scope(&label)?;
-    launchctl_status(
-        &[if enabled { "enable" } else { "disable" }, &scope],
+    let target = service_target(profile)?;
+    managed_launchctl(
+        profile,
+        &[if enabled { "enable" } else { "disable" }, &target],
         "enable/disable",
     )
 }
 
 pub fn start(profile: &RunnerProfile) -> Result<(), Error> {
-    let label = label_for(&profile.runner_id);
-    let scope = launchctl_scope(&label)?;
-    launchctl_status(&["kickstart", "-k", &scope], "kickstart")
+    let plist_path = plist_path(profile)?;
+    if !plist_path.exists() {
+        let target = service_target(profile)?;
+        return managed_launchctl(profile, &["kickstart", "-k", &target], "kickstart");
+    }
+    let current = status(profile)?;
+    if current.running {
+        // Booting it out would kill the job it is running.
+        return Ok(());
+    }
+    // A loaded but stopped job is reloaded; RunAtLoad starts it.
+    if current.installed {
+        let _ = bootout(profile);
+    }
+    bootstrap(profile, &plist_path)
 }
 
 pub fn stop(profile: &RunnerProfile) -> Result<(), Error> {
-    let label = label_for(&profile.runner_id);
-    let scope = launchctl_scope(&label)?;
-    launchctl_status(&["stop", &scope], "stop")
+    let target = service_target(profile)?;
+    managed_launchctl(profile, &["stop", &target], "stop")
 }
 
 pub fn status(profile: &RunnerProfile) -> Result<ServiceStatus, Error> {
     if profile.service.provider == ServiceProvider::External {
         return external_status(profile);
     }
-    let label = label_for(&profile.runner_id);
-    let scope = launchctl_scope(&label)?;
-    let output = Command::new("launchctl").arg("print").arg(&scope).output();
+    let launchd_kind = Some(if profile.service.system_scope {
+        LaunchdKind::Daemon
+    } else {
+        LaunchdKind::Agent
+    });
+    let target = service_target(profile)?;
+    let output = Command::new("launchctl").arg("print").arg(&target).output();
     match output {
         Ok(output) if output.status.success() => {
             let stdout = String::from_utf8_lossy(&output.stdout);
@@ -64,12 +106,18 @@ pub fn status(profile: &RunnerProfile) -> Result<ServiceStatus, Error> {
                 installed: true,
                 running,
                 enabled: true,
+                linger: None,
+                launchd_kind,
+                restarts: parse_launchctl_restarts(&stdout),
             })
         }
         _ => Ok(ServiceStatus {
             installed: false,
             running: false,
             enabled: false,
+            linger: None,
+            launchd_kind,
+            restarts: None,
         }),
     }
 }
@@ -88,6 +136,9 @@ pub fn external_status(profile: &RunnerProfile) -> Result<ServiceStatus, Error>
                 installed,
                 running: false,
                 enabled: installed,
+                linger: None,
+                launchd_kind: None,
+                restarts: None,
             });
         }
     };
@@ -103,12 +154,18 @@ pub fn external_status(profile: &RunnerProfile) -> Result<ServiceStatus, Error>
                 installed: true,
                 running,
                 enabled,
+                linger: None,
+                launchd_kind: None,
+                restarts: parse_launchctl_restarts(&stdout),
             })
         }
         _ => Ok(ServiceStatus {
             installed,
             running: false,
             enabled: installed,
+            linger: None,
+            launchd_kind: None,
+            restarts: None,
         }),
     }
 }
@@ -119,6 +176,18 @@ pub fn external_disable(profile: &RunnerProfile) -> Result<(), Error> {
     launchctl_status(&["bootout", &scope], "bootout")
 }
 
+pub fn external_enable(profile: &RunnerProfile) -> Result<(), Error> {
+    let plist_path = external_plist_path(profile)
+        .ok_or_else(|| Error::Service("missing external launchd plist path".into()))?;
+    if !plist_path.exists() {
+        return Err(Error::Service(format!(
+            "external plist {} no longer exists",
+            plist_path.display()
+        )));
+    }
+    bootstrap_user(&plist_path)
+}
+
 pub fn external_remove_artifacts(profile: &RunnerProfile) -> Result<(), Error> {
     let _ = external_disable(profile);
     if let Some(path) = external_plist_path(profile) {
@@ -129,12 +198,61 @@ pub fn external_remove_artifacts(profile: &RunnerProfile) -> Result<(), Error> {
     Ok(())
 }
 
+/// Removes a LaunchAgent whose runner directory no longer exists.
+pub fn remove_orphaned_service(label: &str, plist_path: Option<&Path>) -> Result<(), Error> {
+    let scope = launchctl_scope(label)?;
+    let _ = launchctl_status(&["bootout", &scope], "bootout");
+    if let Some(path) = plist_path.filter(|path| path.exists()) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
 pub fn launchd_plist_content(profile: &RunnerProfile, log_dir: &Path) -> String {
     let install_path = expand_path(&profile.install.install_path);
     let run_script = install_path.join("run.sh");
     let stdout = log_dir.join("runner-stdout.log");
     let stderr = log_dir.join("runner-stderr.log");
     let label = label_for(&profile.runner_id);
+    // Daemons start as root unless told otherwise; run the runner as its owner.
+    let user_name = if profile.service.system_scope {
+        format!(
+            "  <key>UserName</key>\n  <string>{}</string>\n",
+            xml_escape(&service_user(profile))
+        )
+    } else {
+        String::new()
+    };
+    let environment = if profile.env.is_empty() {
+        String::new()
+    } else {
+        let entries: String = profile
+            .env
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "    <key>{}</key>\n    <string>{}</string>\n",
+                    xml_escape(key),
+                    xml_escape(value)
+                )
+            })
+            .collect();
+        format!("  <key>EnvironmentVariables</key>\n  <dict>\n{entries}  </dict>\n")
+    };
+    let keep_alive = keep_alive_fragment(profile);
+    let throttle = profile
+        .service
+        .throttle_interval_secs
+        .map(|secs| format!("  <key>ThrottleInterval</key>\n  <integer>{secs}</integer>\n"))
+        .unwrap_or_default();
+    let nice = profile
+        .service
+        .nice
+        .map(|nice| format!("  <key>Nice</key>\n  <integer>{nice}</integer>\n"))
+        .unwrap_or_default();
+    let process_type = process_type_value(profile.service.process_type)
+        .map(|value| format!("  <key>ProcessType</key>\n  <string>{value}</string>\n"))
+        .unwrap_or_default();
     format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
 <!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
@@ -148,11 +266,9 @@ pub fn launchd_plist_content(profile: &RunnerProfile, log_dir: &Path) -> String
   </array>
   <key>WorkingDirectory</key>
   <string>{workdir}</string>
-  <key>RunAtLoad</key>
-  <true/>
-  <key>KeepAlive</key>
+{user_name}{environment}  <key>RunAtLoad</key>
   <true/>
-  <key>StandardOutPath</key>
+{keep_alive}{throttle}{nice}{process_type}  <key>StandardOutPath</key>
   <string>{stdout}</string>
   <key>StandardErrorPath</key>
   <string>{stderr}</string>
@@ -167,6 +283,66 @@ pub fn launchd_plist_content(profile: &RunnerProfile, log_dir: &Path) -> String
     )
 }
 
+/// Reads `runs` and `last exit code` from `launchctl print`; every run after the
+/// first is a relaunch.
+fn parse_launchctl_restarts(output: &str) -> Option<ServiceRestarts> {
+    let mut runs = None;
+    let mut last_exit_code = None;
+    for line in output.lines() {
+        match line.trim().split_once(" = ") {
+            Some(("runs", value)) => runs = value.trim().parse::<u32>().ok(),
+            // e.g. `last exit code = 78: EX_CONFIG` or `(never exited)`.
+            Some(("last exit code", value)) => {
+                last_exit_code = value.split(':').next().and_then(|code| code.trim().parse().ok())
+            }
+            _ => {}
+        }
+    }
+    Some(ServiceRestarts {
+        count: runs?.saturating_sub(1),
+        last_exit_code,
+    })
+}
+
+/// An ephemeral runner exits after its one job and must not be relaunched, so it
+/// never keeps alive regardless of the configured policy.
+fn keep_alive_fragment(profile: &RunnerProfile) -> String {
+    let keep_alive = if profile.ephemeral {
+        KeepAlive::Never
+    } else {
+        profile.service.keep_alive
+    };
+    let value = match keep_alive {
+        KeepAlive::Always => "<true/>".to_string(),
+        KeepAlive::Never => "<false/>".to_string(),
+        KeepAlive::OnFailure => {
+            "<dict>\n    <key>SuccessfulExit</key>\n    <false/>\n  </dict>".to_string()
+        }
+        KeepAlive::OnCrash => {
+            "<dict>\n    <key>Crashed</key>\n    <true/>\n  </dict>".to_string()
+        }
+    };
+    format!("  <key>KeepAlive</key>\n  {value}\n")
+}
+
+/// The plist `ProcessType` string, or `None` when launchd should pick.
+fn process_type_value(process_type: ProcessType) -> Option<&'static str> {
+    match process_type {
+        ProcessType::Default => None,
+        ProcessType::Background => Some("Background"),
+        ProcessType::Standard => Some("Standard"),
+        ProcessType::Adaptive => Some("Adaptive"),
+        ProcessType::Interactive => Some("Interactive"),
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 fn launchctl_scope(label: &str) -> Result<String, Error> {
     let uid = user_uid()?;
     Ok(format!("gui/{uid}/{label}"))
@@ -177,6 +353,30 @@ fn launchctl_user_scope() -> Result<String, Error> {
     Ok(format!("gui/{uid}"))
 }
 
+/// `system` for LaunchDaemons, `gui/<uid>` for the user's LaunchAgents.
+fn managed_domain(profile: &RunnerProfile) -> Result<String, Error> {
+    if profile.service.system_scope {
+        Ok("system".to_string())
+    } else {
+        launchctl_user_scope()
+    }
+}
+
+fn service_target(profile: &RunnerProfile) -> Result<String, Error> {
+    let domain = managed_domain(profile)?;
+    Ok(format!("{domain}/{}", label_for(&profile.runner_id)))
+}
+
+/// launchctl for a RunnerBuddy-managed job; the system domain needs root.
+fn managed_launchctl(profile: &RunnerProfile, args: &[&str], context: &str) -> Result<(), Error> {
+    if !profile.service.system_scope {
+        return launchctl_status(args, context);
+    }
+    let mut system_args = vec!["launchctl"];
+    system_args.extend_from_slice(args);
+    privileged(&system_args)
+}
+
 fn launchctl_status(args: &[&str], context: &str) -> Result<(), Error> {
     let status = Command::new("launchctl").args(args).status()?;
     if !status.success() {
@@ -217,14 +417,32 @@ fn parse_launchd_label(path: &Path) -> Option<String> {
         .map(|value| value.to_string())
 }
 
-fn plist_path(runner_id: &str) -> Result<PathBuf, Error> {
+fn plist_path(profile: &RunnerProfile) -> Result<PathBuf, Error> {
+    let file_name = format!("{}.plist", label_for(&profile.runner_id));
+    if profile.service.system_scope {
+        return Ok(PathBuf::from(DAEMON_DIR).join(file_name));
+    }
     let user_dirs = directories::UserDirs::new()
         .ok_or_else(|| Error::Service("unable to resolve user home".into()))?;
     Ok(user_dirs
         .home_dir()
         .join("Library")
         .join("LaunchAgents")
-        .join(format!("{}.plist", label_for(runner_id))))
+        .join(file_name))
+}
+
+/// launchd refuses daemon plists that are not owned by root:wheel, so those are
+/// staged and installed with sudo.
+fn write_plist(profile: &RunnerProfile, plist_path: &Path, content: &str) -> Result<(), Error> {
+    if !profile.service.system_scope {
+        fs::write(plist_path, content)?;
+        return Ok(());
+    }
+    if is_root() {
+        fs::write(plist_path, content)?;
+        return Ok(());
+    }
+    privileged_install(content, plist_path, &DAEMON_OWNER)
 }
 
 fn user_uid() -> Result<String, Error> {
@@ -235,46 +453,142 @@ fn user_uid() -> Result<String, Error> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-fn bootstrap(plist_path: &Path) -> Result<(), Error> {
-    let scope = launchctl_user_scope()?;
+fn bootstrap(profile: &RunnerProfile, plist_path: &Path) -> Result<(), Error> {
+    let domain = managed_domain(profile)?;
     let plist = plist_path.to_string_lossy();
-    launchctl_status(&["bootstrap", &scope, plist.as_ref()], "bootstrap")
+    managed_launchctl(profile, &["bootstrap", &domain, plist.as_ref()], "bootstrap")
 }
 
-fn bootout(runner_id: &str) -> Result<(), Error> {
-    let label = label_for(runner_id);
-    let scope = launchctl_scope(&label)?;
-    launchctl_status(&["bootout", &scope], "bootout")
+fn bootout(profile: &RunnerProfile) -> Result<(), Error> {
+    let target = service_target(profile)?;
+    managed_launchctl(profile, &["bootout", &target], "bootout")
+}
+
+fn bootstrap_user(plist_path: &Path) -> Result<(), Error> {
+    let scope = launchctl_user_scope()?;
+    let plist = plist_path.to_string_lossy();
+    launchctl_status(&["bootstrap", &scope, plist.as_ref()], "bootstrap")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{InstallConfig, InstallMode, RunnerProfile, RunnerServiceConfig};
+    use crate::config::test_profile;
 
     #[test]
     fn plist_contains_label() {
-        let profile = RunnerProfile {
-            runner_id: "abc".to_string(),
-            display_name: "Test".to_string(),
-            scope: None,
-            runner_name: "runner".to_string(),
-            labels: vec!["self-hosted".to_string()],
-            work_dir: "/tmp".to_string(),
-            install: InstallConfig {
-                mode: InstallMode::Managed,
-                install_path: "/tmp/runner".to_string(),
-                adopted_from_path: None,
-                migration_status: crate::config::MigrationStatus::None,
-            },
-            runner_version: None,
-            pat_alias: "default".to_string(),
-            service: RunnerServiceConfig::default(),
-            created_at: "2024-01-01T00:00:00Z".to_string(),
-            last_seen_at: None,
-        };
+        let profile = test_profile();
         let log_dir = PathBuf::from("/tmp");
         let plist = launchd_plist_content(&profile, &log_dir);
         assert!(plist.contains("com.runnerbuddy.runner.abc"));
+        assert!(!plist.contains("EnvironmentVariables"));
+    }
+
+    #[test]
+    fn daemon_plist_runs_as_configured_user() {
+        let mut profile = test_profile();
+        let agent = launchd_plist_content(&profile, &PathBuf::from("/tmp"));
+        assert!(!agent.contains("UserName"));
+        assert!(plist_path(&profile)
+            .expect("agent path")
+            .ends_with("Library/LaunchAgents/com.runnerbuddy.runner.abc.plist"));
+
+        profile.service.system_scope = true;
+        profile.service.system_user = Some("ci".to_string());
+        let daemon = launchd_plist_content(&profile, &PathBuf::from("/tmp"));
+        assert!(daemon.contains("<key>UserName</key>\n  <string>ci</string>"));
+        assert!(plist::Value::from_reader_xml(daemon.as_bytes()).is_ok());
+        assert_eq!(
+            plist_path(&profile).expect("daemon path"),
+            PathBuf::from("/Library/LaunchDaemons/com.runnerbuddy.runner.abc.plist")
+        );
+        assert_eq!(service_target(&profile).expect("target"), "system/com.runnerbuddy.runner.abc");
+    }
+
+    #[test]
+    fn daemon_plists_are_installed_as_root_wheel() {
+        let staged = crate::service_mgmt::stage_private("<plist/>").expect("stage");
+        let dest = PathBuf::from("/Library/LaunchDaemons/com.runnerbuddy.runner.abc.plist");
+        let args = crate::service_mgmt::install_args(staged.path(), &dest, &DAEMON_OWNER);
+        assert_eq!(
+            &args[..7],
+            ["install", "-o", "root", "-g", "wheel", "-m", "0644"]
+        );
+        assert_eq!(args[7], staged.path().to_string_lossy());
+        assert_eq!(args[8], dest.to_string_lossy());
+        assert!(!args[7].ends_with("com.runnerbuddy.runner.abc.plist"));
+    }
+
+    #[test]
+    fn plist_includes_escaped_environment_and_priority() {
+        let mut profile = test_profile();
+        profile
+            .env
+            .insert("FLAGS".to_string(), "a<b & c>d".to_string());
+        let plist = launchd_plist_content(&profile, &PathBuf::from("/tmp"));
+        assert!(plist.contains("<key>EnvironmentVariables</key>"));
+        assert!(plist.contains("<key>FLAGS</key>\n    <string>a&lt;b &amp; c&gt;d</string>"));
+        assert!(plist::Value::from_reader_xml(plist.as_bytes()).is_ok());
+        assert!(!plist.contains("<key>Nice</key>"));
+        assert!(!plist.contains("<key>ProcessType</key>"));
+
+        profile.service.nice = Some(10);
+        profile.service.process_type = crate::config::ProcessType::Background;
+        let plist = launchd_plist_content(&profile, &PathBuf::from("/tmp"));
+        let value = plist::Value::from_reader_xml(plist.as_bytes()).expect("valid plist");
+        let dict = value.as_dictionary().expect("dict");
+        assert_eq!(dict.get("Nice"), Some(&plist::Value::Integer(10.into())));
+        assert_eq!(
+            dict.get("ProcessType").and_then(|value| value.as_string()),
+            Some("Background")
+        );
+    }
+
+    #[test]
+    fn plist_renders_keep_alive_and_throttle() {
+        let mut profile = test_profile();
+        let keep_alive = |profile: &RunnerProfile| {
+            let plist = launchd_plist_content(profile, &PathBuf::from("/tmp"));
+            let value = plist::Value::from_reader_xml(plist.as_bytes()).expect("valid plist");
+            value
+                .as_dictionary()
+                .and_then(|dict| dict.get("KeepAlive"))
+                .cloned()
+                .expect("KeepAlive key")
+        };
+        assert_eq!(keep_alive(&profile), plist::Value::Boolean(true));
+        assert!(!launchd_plist_content(&profile, &PathBuf::from("/tmp")).contains("ThrottleInterval"));
+
+        profile.service.keep_alive = KeepAlive::OnFailure;
+        profile.service.throttle_interval_secs = Some(30);
+        let on_failure = keep_alive(&profile);
+        let on_failure = on_failure.as_dictionary().expect("dict form");
+        assert_eq!(on_failure.get("SuccessfulExit"), Some(&plist::Value::Boolean(false)));
+        assert!(launchd_plist_content(&profile, &PathBuf::from("/tmp"))
+            .contains("<key>ThrottleInterval</key>\n  <integer>30</integer>"));
+
+        profile.service.keep_alive = KeepAlive::OnCrash;
+        let on_crash = keep_alive(&profile);
+        assert_eq!(
+            on_crash.as_dictionary().and_then(|dict| dict.get("Crashed")),
+            Some(&plist::Value::Boolean(true))
+        );
+
+        profile.service.keep_alive = KeepAlive::Always;
+        profile.ephemeral = true;
+        assert_eq!(keep_alive(&profile), plist::Value::Boolean(false));
+    }
+
+    #[test]
+    fn parses_launchctl_restart_counters() {
+        let output = "com.runnerbuddy.runner.abc = {\n\tactive count = 0\n\truns = 6\n\tlast exit code = 78: EX_CONFIG\n}\n";
+        let restarts = parse_launchctl_restarts(output).expect("counters");
+        assert_eq!(restarts.count, 5);
+        assert_eq!(restarts.last_exit_code, Some(78));
+
+        let fresh = parse_launchctl_restarts("\truns = 1\n\tlast exit code = (never exited)\n")
+            .expect("counters");
+        assert_eq!(fresh.count, 0);
+        assert_eq!(fresh.last_exit_code, None);
     }
 }
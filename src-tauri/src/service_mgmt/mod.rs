@@ -1,8 +1,188 @@
-#[derive(Debug, serde::Serialize, Clone)]
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, serde::Serialize, Clone, PartialEq, Eq)]
 pub struct ServiceStatus {
     pub installed: bool,
     pub running: bool,
     pub enabled: bool,
+    /// Linux `--user` services only: whether systemd lingering lets them start at
+    /// boot without a login session.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub linger: Option<bool>,
+    /// macOS RunnerBuddy services only: LaunchDaemon (boot time) or LaunchAgent (login).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launchd_kind: Option<LaunchdKind>,
+    /// systemd/launchd restart counters, used to spot crash loops.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restarts: Option<ServiceRestarts>,
+}
+
+#[derive(Debug, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceRestarts {
+    /// Restarts since the job was loaded (systemd `NRestarts`, launchd `runs - 1`).
+    pub count: u32,
+    pub last_exit_code: Option<i32>,
+}
+
+/// A service that restarts this often within `CRASH_LOOP_WINDOW` is crash-looping.
+const CRASH_LOOP_RESTARTS: u32 = 3;
+const CRASH_LOOP_WINDOW: Duration = Duration::from_secs(300);
+const CRASH_LOOP_PREFIX: &str = "service is crash-looping";
+
+/// Tracks restart counters between status polls; the counters themselves only say
+/// how often a service restarted since it was loaded, not how recently.
+#[derive(Default)]
+pub struct CrashLoopDetector {
+    samples: HashMap<String, VecDeque<(Instant, u32)>>,
+    /// Message of each loop in progress, fixed when the loop is first seen so the
+    /// runtime error (and its notification) is raised once per episode.
+    episodes: HashMap<String, String>,
+}
+
+impl CrashLoopDetector {
+    /// Records a restart counter and returns an error message while the service is
+    /// restarting faster than the threshold allows. The message stays the same until
+    /// the loop ends.
+    pub fn observe(
+        &mut self,
+        runner_id: &str,
+        restarts: ServiceRestarts,
+        now: Instant,
+    ) -> Option<String> {
+        let samples = self.samples.entry(runner_id.to_string()).or_default();
+        // A lower counter means the job was reloaded; earlier samples no longer compare.
+        if samples.back().is_some_and(|(_, count)| *count > restarts.count) {
+            samples.clear();
+        }
+        samples.push_back((now, restarts.count));
+        while samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > CRASH_LOOP_WINDOW)
+        {
+            samples.pop_front();
+        }
+        let oldest = samples.front().map_or(restarts.count, |(_, count)| *count);
+        let recent = restarts.count - oldest;
+        if recent < CRASH_LOOP_RESTARTS {
+            self.episodes.remove(runner_id);
+            return None;
+        }
+        let message = self.episodes.entry(runner_id.to_string()).or_insert_with(|| {
+            let exit = restarts
+                .last_exit_code
+                .map(|code| format!(" (last exit code {code})"))
+                .unwrap_or_default();
+            format!(
+                "{CRASH_LOOP_PREFIX}: restarted {recent} times in the last {} minutes{exit}; check the runner logs",
+                CRASH_LOOP_WINDOW.as_secs() / 60
+            )
+        });
+        Some(message.clone())
+    }
+
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.samples.retain(|id, _| keep(id));
+        self.episodes.retain(|id, _| keep(id));
+    }
+}
+
+/// Whether a runtime error came from `CrashLoopDetector`.
+pub fn is_crash_loop_error(message: &str) -> bool {
+    message.starts_with(CRASH_LOOP_PREFIX)
+}
+
+#[derive(Debug, serde::Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+pub enum LaunchdKind {
+    Agent,
+    Daemon,
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub(crate) fn is_root() -> bool {
+    static ROOT: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *ROOT.get_or_init(|| {
+        std::process::Command::new("id")
+            .arg("-u")
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "0")
+            .unwrap_or(false)
+    })
+}
+
+/// Runs a command as root, through non-interactive `sudo` when the app itself is
+/// not root, since there is no terminal to prompt for a password.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn privileged(args: &[&str]) -> Result<(), crate::errors::Error> {
+    use std::process::Command;
+    let status = if is_root() {
+        Command::new(args[0]).args(&args[1..]).status()?
+    } else {
+        Command::new("sudo").arg("-n").args(args).status()?
+    };
+    if !status.success() {
+        return Err(crate::errors::Error::Service(format!(
+            "`{}` failed; system-wide services need RunnerBuddy to run as root or passwordless sudo",
+            args.join(" ")
+        )));
+    }
+    Ok(())
+}
+
+/// Writes `content` to `dest` as root with mode 0644 (plus `owner_args`, e.g.
+/// `-o root -g wheel`). The content is staged in a fresh 0600 temp file with a
+/// random name, so no other local user can pre-create or swap it before root
+/// copies it into place.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn privileged_install(
+    content: &str,
+    dest: &std::path::Path,
+    owner_args: &[&str],
+) -> Result<(), crate::errors::Error> {
+    let staged = stage_private(content)?;
+    let args = install_args(staged.path(), dest, owner_args);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    privileged(&args)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn stage_private(content: &str) -> Result<tempfile::NamedTempFile, crate::errors::Error> {
+    use std::io::Write;
+    let mut staged = tempfile::Builder::new()
+        .prefix("runnerbuddy-")
+        .tempfile()?;
+    staged.write_all(content.as_bytes())?;
+    staged.flush()?;
+    Ok(staged)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn install_args(staged: &std::path::Path, dest: &std::path::Path, owner_args: &[&str]) -> Vec<String> {
+    let mut args = vec!["install".to_string()];
+    args.extend(owner_args.iter().map(|arg| arg.to_string()));
+    args.extend([
+        "-m".to_string(),
+        "0644".to_string(),
+        staged.to_string_lossy().into_owned(),
+        dest.to_string_lossy().into_owned(),
+    ]);
+    args
+}
+
+/// Account a system-wide service runs as: the configured user, else whoever
+/// invoked the app (through sudo, if that is how it was started).
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn service_user(profile: &crate::config::RunnerProfile) -> String {
+    profile
+        .service
+        .system_user
+        .clone()
+        .filter(|user| !user.trim().is_empty())
+        .or_else(|| std::env::var("SUDO_USER").ok())
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "root".to_string())
 }
 
 #[cfg(target_os = "macos")]
@@ -24,6 +204,7 @@ mod platform {
     use super::ServiceStatus;
     use crate::config::RunnerProfile;
     use crate::errors::Error;
+    use std::path::Path;
 
     pub fn install(_profile: &RunnerProfile) -> Result<(), Error> {
         Err(Error::Unsupported("service install unsupported on this OS".into()))
@@ -35,6 +216,10 @@ mod platform {
         ))
     }
 
+    pub fn refresh_unit(_profile: &RunnerProfile) -> Result<(), Error> {
+        Ok(())
+    }
+
     pub fn enable_on_boot(_profile: &RunnerProfile, _enabled: bool) -> Result<(), Error> {
         Err(Error::Unsupported(
             "service enable unsupported on this OS".into(),
@@ -65,12 +250,85 @@ mod platform {
         ))
     }
 
+    pub fn external_enable(_profile: &RunnerProfile) -> Result<(), Error> {
+        Err(Error::Unsupported(
+            "external service enable unsupported on this OS".into(),
+        ))
+    }
+
     pub fn external_remove_artifacts(_profile: &RunnerProfile) -> Result<(), Error> {
         Err(Error::Unsupported(
             "external service removal unsupported on this OS".into(),
         ))
     }
+
+    pub fn remove_orphaned_service(_id: &str, _path: Option<&Path>) -> Result<(), Error> {
+        Err(Error::Unsupported(
+            "orphaned service removal unsupported on this OS".into(),
+        ))
+    }
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
 pub use platform::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    #[test]
+    fn staged_install_files_are_private_and_randomly_named() {
+        use std::os::unix::fs::PermissionsExt;
+        let first = stage_private("[Unit]\n").expect("stage");
+        let second = stage_private("[Unit]\n").expect("stage");
+        assert_ne!(first.path(), second.path());
+        let mode = std::fs::metadata(first.path()).expect("metadata").permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(first.path()).expect("read"), "[Unit]\n");
+
+        let args = install_args(
+            first.path(),
+            std::path::Path::new("/etc/systemd/system/x.service"),
+            &[],
+        );
+        assert_eq!(&args[..3], ["install", "-m", "0644"]);
+        assert_eq!(args[3], first.path().to_string_lossy());
+        assert_eq!(args[4], "/etc/systemd/system/x.service");
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn crash_loop_needs_restarts_within_the_window() {
+        let mut detector = CrashLoopDetector::default();
+        let now = Instant::now();
+        let restarts = |count| ServiceRestarts {
+            count,
+            last_exit_code: Some(1),
+        };
+        assert_eq!(detector.observe("a", restarts(10), now), None);
+        assert_eq!(detector.observe("a", restarts(11), now + Duration::from_secs(30)), None);
+        let message = detector
+            .observe("a", restarts(13), now + Duration::from_secs(60))
+            .expect("crash loop");
+        assert!(message.contains("restarted 3 times"));
+        assert!(message.contains("last exit code 1"));
+        assert!(is_crash_loop_error(&message));
+        // Further restarts in the same loop keep the message unchanged.
+        assert_eq!(
+            detector.observe("a", restarts(15), now + Duration::from_secs(90)),
+            Some(message)
+        );
+
+        // Old restarts age out of the window: the counter keeps climbing, but only
+        // one restart happened within the last window.
+        let later = now + CRASH_LOOP_WINDOW + Duration::from_secs(120);
+        assert_eq!(detector.observe("a", restarts(16), later), None);
+
+        // A reloaded job resets its counter without looking like a loop.
+        assert_eq!(detector.observe("a", restarts(0), later), None);
+        assert_eq!(detector.observe("b", restarts(2), now), None);
+    }
+}
@@ -1,7 +1,9 @@
 use crate::config::{RunnerProfile, ServiceProvider};
+use crate::discovery::parse_windows_service_name;
 use crate::errors::Error;
 use crate::service_mgmt::ServiceStatus;
 use crate::util::expand_path;
+use std::path::Path;
 use std::process::Command;
 
 fn svc_command(profile: &RunnerProfile, action: &str) -> Command {
@@ -35,18 +37,94 @@ fn parse_service_status(output: &std::process::Output) -> ServiceStatus {
         installed,
         running,
         enabled: installed,
+        linger: None,
+        launchd_kind: None,
+        restarts: None,
     }
 }
 
 pub fn install(profile: &RunnerProfile) -> Result<(), Error> {
-    svc_run(profile, "install")
+    svc_run(profile, "install")?;
+    if !profile.env.is_empty() {
+        apply_environment(profile)?;
+    }
+    Ok(())
 }
 
 pub fn uninstall(profile: &RunnerProfile) -> Result<(), Error> {
     svc_run(profile, "uninstall")
 }
 
-pub fn enable_on_boot(_profile: &RunnerProfile, _enabled: bool) -> Result<(), Error> {
+fn service_name(profile: &RunnerProfile) -> Result<String, Error> {
+    let output = svc_output(profile, "status")?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_windows_service_name(&stdout).ok_or_else(|| {
+        Error::Service("unable to resolve the Windows service name from svc.cmd status".into())
+    })
+}
+
+/// Only the environment applies to the Windows service; restart policy and start
+/// limits are systemd settings.
+pub fn refresh_unit(profile: &RunnerProfile) -> Result<(), Error> {
+    apply_environment(profile)
+}
+
+/// Writes the profile env to the service's `Environment` registry value, which
+/// the service control manager passes to the process on its next start.
+fn apply_environment(profile: &RunnerProfile) -> Result<(), Error> {
+    let name = service_name(profile)?;
+    let key = format!(r"HKLM\SYSTEM\CurrentControlSet\Services\{name}");
+    if profile.env.is_empty() {
+        // Fails harmlessly when no value was ever set.
+        let _ = Command::new("reg")
+            .args(["delete", &key, "/v", "Environment", "/f"])
+            .output();
+        return Ok(());
+    }
+    let data = profile
+        .env
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(r"\0");
+    let output = Command::new("reg")
+        .args([
+            "add",
+            &key,
+            "/v",
+            "Environment",
+            "/t",
+            "REG_MULTI_SZ",
+            "/d",
+            &data,
+            "/f",
+        ])
+        .output()?;
+    if !output.status.success() {
+        return Err(Error::Service(format!(
+            "failed to set the environment for service {name}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+pub fn enable_on_boot(profile: &RunnerProfile, enabled: bool) -> Result<(), Error> {
+    let name = service_name(profile)?;
+    let start_type = if enabled { "auto" } else { "demand" };
+    let output = Command::new("sc")
+        .arg("config")
+        .arg(&name)
+        .arg("start=")
+        .arg(start_type)
+        .output()?;
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        return Err(Error::Service(format!(
+            "sc config {name} start= {start_type} failed: {}",
+            stdout.trim()
+        )));
+    }
     Ok(())
 }
 
@@ -62,13 +140,61 @@ pub fn status(profile: &RunnerProfile) -> Result<ServiceStatus, Error> {
     if profile.service.provider == ServiceProvider::External {
         return external_status(profile);
     }
-    let output = svc_output(profile, "status")?;
-    Ok(parse_service_status(&output))
+    query_status(profile)
 }
 
 pub fn external_status(profile: &RunnerProfile) -> Result<ServiceStatus, Error> {
+    query_status(profile)
+}
+
+/// Resolves the service name through `svc.cmd status`, then asks the service
+/// control manager for the real state and start type.
+fn query_status(profile: &RunnerProfile) -> Result<ServiceStatus, Error> {
     let output = svc_output(profile, "status")?;
-    Ok(parse_service_status(&output))
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some(name) = parse_windows_service_name(&stdout) else {
+        return Ok(parse_service_status(&output));
+    };
+    let query = sc_output(&["query", &name])?;
+    if !query.status.success() {
+        return Ok(ServiceStatus {
+            installed: false,
+            running: false,
+            enabled: false,
+            linger: None,
+            launchd_kind: None,
+            restarts: None,
+        });
+    }
+    let state = parse_sc_field(&String::from_utf8_lossy(&query.stdout), "STATE");
+    let config = sc_output(&["qc", &name])?;
+    let start_type = parse_sc_field(&String::from_utf8_lossy(&config.stdout), "START_TYPE");
+    Ok(ServiceStatus {
+        installed: true,
+        running: matches!(state.as_deref(), Some("RUNNING" | "START_PENDING")),
+        enabled: start_type.as_deref() == Some("AUTO_START"),
+        linger: None,
+        launchd_kind: None,
+        restarts: None,
+    })
+}
+
+fn sc_output(args: &[&str]) -> Result<std::process::Output, Error> {
+    Ok(Command::new("sc").args(args).output()?)
+}
+
+/// Reads the symbolic value of an `sc` field such as `STATE : 4  RUNNING`.
+fn parse_sc_field(output: &str, field: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        if !key.trim().eq_ignore_ascii_case(field) {
+            return None;
+        }
+        value
+            .split_whitespace()
+            .find(|token| !token.chars().all(|c| c.is_ascii_digit()))
+            .map(|token| token.to_ascii_uppercase())
+    })
 }
 
 pub fn external_disable(profile: &RunnerProfile) -> Result<(), Error> {
@@ -76,7 +202,38 @@ pub fn external_disable(profile: &RunnerProfile) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn external_enable(profile: &RunnerProfile) -> Result<(), Error> {
+    svc_run(profile, "start")
+}
+
 pub fn external_remove_artifacts(profile: &RunnerProfile) -> Result<(), Error> {
     let _ = svc_run(profile, "stop");
     svc_run(profile, "uninstall")
 }
+
+/// Deletes a runner service whose install directory (and so `svc.cmd`) is gone.
+pub fn remove_orphaned_service(name: &str, _artifact_path: Option<&Path>) -> Result<(), Error> {
+    let _ = sc_output(&["stop", name]);
+    let output = sc_output(&["delete", name])?;
+    if !output.status.success() {
+        return Err(Error::Service(format!(
+            "sc delete {name} failed: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_sc_field;
+
+    #[test]
+    fn parses_sc_state_and_start_type() {
+        let query = "SERVICE_NAME: actions.runner.acme.repo.runner1\n        TYPE               : 10  WIN32_OWN_PROCESS\n        STATE              : 2  START_PENDING\n";
+        assert_eq!(parse_sc_field(query, "STATE").as_deref(), Some("START_PENDING"));
+        let qc = "        START_TYPE         : 2   AUTO_START  (DELAYED)\n";
+        assert_eq!(parse_sc_field(qc, "START_TYPE").as_deref(), Some("AUTO_START"));
+        assert!(parse_sc_field(qc, "STATE").is_none());
+    }
+}
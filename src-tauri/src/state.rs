@@ -1,12 +1,22 @@
 use crate::config::Config;
 use crate::discovery::DiscoveryCandidate;
+use crate::errors::Error;
+use crate::github_api::{ListingCache, RegistrationTokenCache};
 use crate::logging::{LogPaths, LogSetup};
-use serde::Serialize;
+use crate::service_mgmt::ServiceStatus;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::process::Child;
-use std::sync::Mutex;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::warn;
 
-#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+const RUNTIME_FILE: &str = "runtime.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum RunnerStatus {
     Offline,
@@ -14,12 +24,35 @@ pub enum RunnerStatus {
     Running,
 }
 
-#[derive(Debug, Serialize, Clone)]
+/// The job a runner is executing, as far as its logs tell.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CurrentJob {
+    pub name: String,
+    pub run_id: Option<u64>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RuntimeState {
     pub status: RunnerStatus,
     pub pid: Option<u32>,
     pub last_heartbeat: Option<u64>,
     pub last_error: Option<String>,
+    /// Only set while `status` is `Running`.
+    #[serde(default)]
+    pub current_job: Option<CurrentJob>,
+    /// Jobs finished since RunnerBuddy started counting for this runner.
+    #[serde(default)]
+    pub jobs_completed: u64,
+    /// How far the job counter has read the listener diag logs.
+    #[serde(default)]
+    pub job_log_cursor: Option<JobLogCursor>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct JobLogCursor {
+    pub file: String,
+    pub offset: u64,
 }
 
 impl Default for RuntimeState {
@@ -29,10 +62,21 @@ impl Default for RuntimeState {
             pid: None,
             last_heartbeat: None,
             last_error: None,
+            current_job: None,
+            jobs_completed: 0,
+            job_log_cursor: None,
         }
     }
 }
 
+/// Watchdog bookkeeping for a runner that exited without being asked to stop.
+#[derive(Debug, Default)]
+pub struct RestartBackoff {
+    pub attempts: u32,
+    pub next_attempt: Option<Instant>,
+    pub last_restart: Option<Instant>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct AppSnapshot {
     pub config: Config,
@@ -43,22 +87,132 @@ pub struct AppState {
     pub config: crate::config::ConfigStore,
     pub runtime: Mutex<HashMap<String, RuntimeState>>,
     pub runner_children: Mutex<HashMap<String, Child>>,
+    /// Exit status of runner processes that ended on their own (not via stop).
+    pub runner_exits: Mutex<HashMap<String, ExitStatus>>,
+    pub restart_backoff: Mutex<HashMap<String, RestartBackoff>>,
+    /// Active `logs_follow_start` tasks keyed by follow id.
+    pub log_followers: Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>>,
     pub discovery_cache: Mutex<HashMap<String, DiscoveryCandidate>>,
+    pub service_status_cache: Mutex<HashMap<String, (Instant, ServiceStatus)>>,
     pub last_seen_updates: Mutex<HashMap<String, u64>>,
+    /// Cancellation flags for in-flight runner downloads, keyed by runner id.
+    pub download_cancels: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    /// Newest runner release seen by the last update check.
+    pub latest_runner_version: Mutex<Option<String>>,
+    pub registration_tokens: RegistrationTokenCache,
+    pub github_listings: ListingCache,
+    pub status_monitor_paused: AtomicBool,
+    pub notify_limiter: Mutex<crate::notifications::NotifyLimiter>,
+    pub metrics_server: Mutex<Option<crate::metrics::MetricsServer>>,
+    /// Where `runtime` is persisted between launches; `None` if the data dir is unavailable.
+    pub runtime_path: Option<PathBuf>,
     pub log_paths: LogPaths,
+    pub log_filter: crate::logging::LogFilterHandle,
     _log_guard: tracing_appender::non_blocking::WorkerGuard,
 }
 
 impl AppState {
     pub fn new(config: crate::config::ConfigStore, log_setup: LogSetup) -> Self {
+        let runtime_path = match crate::config::data_dir() {
+            Ok(dir) => Some(dir.join(RUNTIME_FILE)),
+            Err(err) => {
+                warn!("runtime state will not persist: {err}");
+                None
+            }
+        };
+        let runtime = runtime_path
+            .as_deref()
+            .map(load_runtime)
+            .unwrap_or_default();
         Self {
             config,
-            runtime: Mutex::new(HashMap::new()),
+            runtime: Mutex::new(runtime),
             runner_children: Mutex::new(HashMap::new()),
+            runner_exits: Mutex::new(HashMap::new()),
+            restart_backoff: Mutex::new(HashMap::new()),
+            log_followers: Mutex::new(HashMap::new()),
             discovery_cache: Mutex::new(HashMap::new()),
+            service_status_cache: Mutex::new(HashMap::new()),
             last_seen_updates: Mutex::new(HashMap::new()),
+            download_cancels: Mutex::new(HashMap::new()),
+            latest_runner_version: Mutex::new(None),
+            registration_tokens: Mutex::new(HashMap::new()),
+            github_listings: Mutex::new(HashMap::new()),
+            status_monitor_paused: AtomicBool::new(false),
+            notify_limiter: Mutex::new(Default::default()),
+            metrics_server: Mutex::new(None),
+            runtime_path,
             log_paths: log_setup.paths,
+            log_filter: log_setup.filter,
             _log_guard: log_setup.guard,
         }
     }
+
+    /// Callers pass the locked map so concurrent writers are serialized.
+    pub fn persist_runtime(&self, runtime: &HashMap<String, RuntimeState>) {
+        let Some(path) = self.runtime_path.as_deref() else {
+            return;
+        };
+        if let Err(err) = save_runtime(path, runtime) {
+            warn!("failed to persist runtime state: {err}");
+        }
+    }
+}
+
+/// Last known runtime per runner; a missing or unreadable file yields an empty map.
+pub fn load_runtime(path: &Path) -> HashMap<String, RuntimeState> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return HashMap::new(),
+        Err(err) => {
+            warn!("failed to read runtime state {:?}: {err}", path);
+            return HashMap::new();
+        }
+    };
+    serde_json::from_str(&data).unwrap_or_else(|err| {
+        warn!("ignoring unreadable runtime state {:?}: {err}", path);
+        HashMap::new()
+    })
+}
+
+pub fn save_runtime(path: &Path, runtime: &HashMap<String, RuntimeState>) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let data = serde_json::to_string_pretty(runtime)?;
+    crate::config::write_atomic(path, data.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_round_trips_and_tolerates_bad_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(RUNTIME_FILE);
+        assert!(load_runtime(&path).is_empty());
+
+        let mut runtime = HashMap::new();
+        runtime.insert(
+            "abc".to_string(),
+            RuntimeState {
+                status: RunnerStatus::Idle,
+                pid: Some(42),
+                last_heartbeat: Some(1_700_000_000),
+                last_error: Some("exit code 1".to_string()),
+                current_job: None,
+                jobs_completed: 7,
+                job_log_cursor: None,
+            },
+        );
+        save_runtime(&path, &runtime).unwrap();
+        let loaded = load_runtime(&path);
+        assert_eq!(loaded["abc"].status, RunnerStatus::Idle);
+        assert_eq!(loaded["abc"].last_error.as_deref(), Some("exit code 1"));
+        assert_eq!(loaded["abc"].jobs_completed, 7);
+
+        fs::write(&path, "{not json").unwrap();
+        assert!(load_runtime(&path).is_empty());
+    }
 }
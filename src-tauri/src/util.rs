@@ -1,8 +1,9 @@
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{self, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 
 pub const LOG_TAIL_BYTES: usize = 1024 * 1024;
+const LINE_LOOKBACK_BYTES: u64 = 64 * 1024;
 
 pub fn default_runner_name() -> String {
     fn normalize(value: Option<String>) -> Option<String> {
@@ -54,9 +55,85 @@ pub fn read_file_tail(path: &Path, max_bytes: usize) -> Result<Option<String>, s
         Err(err) => return Err(err),
     };
     let len = file.metadata()?.len();
-    let start = len.saturating_sub(max_bytes as u64);
+    let mut start = len.saturating_sub(max_bytes as u64);
+    if start > 0 {
+        start = line_start_before(&mut file, start)?;
+    }
     file.seek(SeekFrom::Start(start))?;
     let mut buf = Vec::new();
     file.read_to_end(&mut buf)?;
-    Ok(Some(String::from_utf8_lossy(&buf).to_string()))
+    // Only reachable for a single line longer than the lookback: never start mid-character.
+    let partial = buf.iter().take_while(|byte| (**byte & 0xC0) == 0x80).count();
+    Ok(Some(String::from_utf8_lossy(&buf[partial..]).to_string()))
+}
+
+// Moves the cut back to the start of the line it lands in, so a log marker that
+// straddles the tail boundary is read whole instead of as a truncated fragment.
+fn line_start_before(file: &mut File, offset: u64) -> io::Result<u64> {
+    let lookback_start = offset.saturating_sub(LINE_LOOKBACK_BYTES);
+    file.seek(SeekFrom::Start(lookback_start))?;
+    let mut window = vec![0; (offset - lookback_start) as usize];
+    file.read_exact(&mut window)?;
+    match window.iter().rposition(|byte| *byte == b'\n') {
+        Some(pos) => Ok(lookback_start + pos as u64 + 1),
+        None if lookback_start == 0 => Ok(0),
+        None => Ok(offset),
+    }
+}
+
+/// Total size of regular files under `root`, descending at most `max_depth`
+/// levels. Symlinks are not followed, so link cycles can't loop the walk.
+pub fn dir_size(root: &Path, max_depth: u32) -> u64 {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return 0;
+    };
+    let mut total = 0;
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_file() {
+            total += entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+        } else if file_type.is_dir() && max_depth > 0 {
+            total += dir_size(&entry.path(), max_depth - 1);
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_file_tail_keeps_lines_split_by_the_boundary() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("Runner_1.log");
+        std::fs::write(&path, "older line\nRunning job: déploiement\nIdle\n").expect("write log");
+
+        let tail = read_file_tail(&path, 12).expect("read tail").expect("file exists");
+        assert_eq!(tail, "Running job: déploiement\nIdle\n");
+
+        let whole = read_file_tail(&path, 1024).expect("read tail").expect("file exists");
+        assert!(whole.starts_with("older line\n"));
+        assert!(read_file_tail(&dir.path().join("missing.log"), 12)
+            .expect("read tail")
+            .is_none());
+    }
+
+    #[test]
+    fn dir_size_counts_files_without_following_symlinks() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir_all(dir.path().join("bin").join("deep")).expect("create dirs");
+        std::fs::write(dir.path().join("run.sh"), "12345").expect("write run.sh");
+        std::fs::write(dir.path().join("bin").join("lib"), "123").expect("write lib");
+        std::fs::write(dir.path().join("bin").join("deep").join("x"), "12").expect("write x");
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("bin").join("loop"))
+            .expect("create symlink");
+
+        assert_eq!(dir_size(dir.path(), 8), 10);
+        assert_eq!(dir_size(dir.path(), 1), 8);
+        assert_eq!(dir_size(&dir.path().join("missing"), 8), 0);
+    }
 }
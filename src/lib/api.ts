@@ -3,6 +3,9 @@ import { invoke } from "@tauri-apps/api/core";
 export type RunnerStatus = "offline" | "idle" | "running";
 
 export type AdoptionDefault = "adopt" | "move_verify_delete";
+export type LogFormat = "text" | "json";
+
+export type AuthMethod = "pat" | { app: { app_id: number; installation_id: number } };
 
 export type RunnerScope =
   | { type: "repo"; owner: string; repo: string }
@@ -12,6 +15,7 @@ export type RunnerScope =
 export interface GitHubRegistrationToken {
   token: string;
   expires_at: string;
+  expires_in_secs?: number | null;
 }
 
 export interface GitHubRepoPermissions {
@@ -34,6 +38,38 @@ export interface GitHubOrgInfo {
   url: string;
 }
 
+export interface GitHubRegisteredRunner {
+  id: number;
+  name: string;
+  status: "online" | "offline" | string;
+  busy: boolean;
+  labels: string[];
+}
+
+export interface GitHubRunnerGroup {
+  id: number;
+  name: string;
+  visibility?: string | null;
+  default: boolean;
+}
+
+export interface PatCheckResult {
+  ok: boolean;
+  granted: string[];
+  missing: string[];
+}
+
+export interface GitHubRateLimit {
+  remaining: number;
+  limit: number;
+  reset_at: number;
+}
+
+export interface GitHubListing<T> {
+  items: T[];
+  rate_limit?: GitHubRateLimit | null;
+}
+
 export type InstallMode = "managed" | "adopted";
 
 export type ServiceProvider = "runnerbuddy" | "external" | "unknown";
@@ -57,8 +93,26 @@ export interface RunnerServiceConfig {
     id?: string | null;
     path?: string | null;
   } | null;
+  system_scope?: boolean;
+  system_user?: string | null;
+  restart_policy?: RestartPolicy;
+  restart_sec?: number;
+  start_limit_interval_secs?: number | null;
+  start_limit_burst?: number | null;
+  memory_max?: string | null;
+  cpu_quota?: string | null;
+  keep_alive?: KeepAlive;
+  throttle_interval_secs?: number | null;
+  nice?: number | null;
+  process_type?: ProcessType;
 }
 
+export type RestartPolicy = "always" | "on-failure" | "no";
+
+export type KeepAlive = "always" | "on-failure" | "on-crash" | "never";
+
+export type ProcessType = "default" | "background" | "standard" | "adaptive" | "interactive";
+
 export interface RunnerProfile {
   runner_id: string;
   display_name: string;
@@ -68,6 +122,14 @@ export interface RunnerProfile {
   work_dir: string;
   install: InstallConfig;
   runner_version?: string | null;
+  runner_group?: string | null;
+  ephemeral: boolean;
+  ephemeral_restart: boolean;
+  jit: boolean;
+  auto_restart: boolean;
+  start_on_app_launch: boolean;
+  version_pinned: boolean;
+  env?: Record<string, string>;
   pat_alias: string;
   service: RunnerServiceConfig;
   created_at: string;
@@ -83,22 +145,94 @@ export interface SettingsConfig {
   auto_updates_enabled: boolean;
   auto_check_updates_on_launch: boolean;
   adoption_default: AdoptionDefault;
+  github_base_url: string;
+  api_base_url: string;
+  proxy_url?: string | null;
+  ca_bundle_path?: string | null;
+  danger_accept_invalid_certs: boolean;
+  connect_timeout_secs: number;
+  request_timeout_secs: number;
+  download_timeout_secs: number;
+  runner_stop_grace_secs: number;
+  runner_log_max_bytes: number;
+  status_poll_interval_secs: number;
+  scrub_patterns: string[];
+  require_checksum: boolean;
+  runner_download_base?: string | null;
+  runner_download_version?: string | null;
+  scan_paths: string[];
+  scan_depth: number;
+  downloads_keep: number;
+  max_concurrent_runners: number;
+  notify_job_started: boolean;
+  notify_job_finished: boolean;
+  notify_runner_errors: boolean;
+  metrics_enabled: boolean;
+  metrics_port: number;
+  log_format: LogFormat;
+  app_log_max_bytes: number;
+  app_log_keep_files: number;
+  app_log_keep_days: number;
+}
+
+export interface ProxyInfo {
+  source: "settings" | "environment" | "none";
+  url?: string | null;
+  no_proxy?: string | null;
+}
+
+export interface NetworkProxyStatus {
+  proxy: ProxyInfo;
+  api_base_url: string;
+  reachable: boolean;
+  error?: string | null;
 }
 
 export interface Config {
   schema_version: number;
   selected_runner_id?: string | null;
   pat_default_alias: string;
+  auth_methods?: Record<string, AuthMethod>;
+  pat_aliases?: string[];
   onboarding: OnboardingConfig;
   settings: SettingsConfig;
   runners: RunnerProfile[];
 }
 
+export interface CurrentJob {
+  name: string;
+  run_id?: number | null;
+  url?: string | null;
+}
+
 export interface RuntimeState {
   status: RunnerStatus;
   pid?: number | null;
   last_heartbeat?: number | null;
   last_error?: string | null;
+  current_job?: CurrentJob | null;
+  jobs_completed?: number;
+}
+
+export interface DashboardSummary {
+  total: number;
+  by_status: Record<RunnerStatus, number>;
+  by_provider: Record<ServiceProvider, number>;
+  outdated?: number | null;
+  latest_version?: string | null;
+  selected_runner_id?: string | null;
+  errored: number;
+  jobs_completed: number;
+}
+
+export interface QuitRequestedPayload {
+  runner_ids: string[];
+}
+
+export interface BulkOutcome {
+  ok: boolean;
+  skipped: boolean;
+  error?: string | null;
 }
 
 export interface AppSnapshot {
@@ -123,6 +257,12 @@ export interface ServiceStatus {
   installed: boolean;
   running: boolean;
   enabled: boolean;
+  linger?: boolean;
+  launchd_kind?: "agent" | "daemon";
+  restarts?: {
+    count: number;
+    last_exit_code?: number | null;
+  };
 }
 
 export interface LogSource {
@@ -131,14 +271,80 @@ export interface LogSource {
   path: string;
 }
 
+export type LogLevel = "verbose" | "info" | "warn" | "error";
+
 export interface LogLine {
+  raw: string;
+  ts?: string | null;
+  level?: LogLevel | null;
+  message: string;
+}
+
+export interface MergedLogLine extends LogLine {
+  source: string;
+}
+
+export interface LogChunk {
+  lines: LogLine[];
+  start_offset: number;
+  end_offset: number;
+}
+
+export interface LogMatch {
+  source: string;
+  line_number: number;
   line: string;
 }
 
+export interface DiagnosticsExport {
+  path: string;
+  files: string[];
+  truncated: string[];
+  omitted: string[];
+}
+
+export interface ConnectivityProbe {
+  target: string;
+  url: string;
+  ok: boolean;
+  status?: number | null;
+  latency_ms: number;
+  error?: string | null;
+}
+
+export interface EnvironmentCheck {
+  name: string;
+  ok: boolean;
+  detail: string;
+}
+
+export interface LogLinePayload {
+  follow_id: string;
+  runner_id: string;
+  source: string;
+  line: LogLine;
+}
+
 export interface ProgressPayload {
   runner_id: string;
   phase: string;
   percent: number;
+  message?: string;
+  bytes_done?: number;
+  bytes_total?: number;
+  bytes_per_sec?: number;
+  eta_secs?: number;
+}
+
+export interface DiscoveryProgressPayload {
+  scanned: number;
+  total: number;
+}
+
+export interface DrainPayload {
+  runner_id: string;
+  phase: "waiting" | "stopping" | "stopped" | "timeout";
+  elapsed_secs: number;
 }
 
 export interface DiscoveryCandidate {
@@ -152,11 +358,18 @@ export interface DiscoveryCandidate {
   service_id?: string | null;
   service_path?: string | null;
   last_log_time?: string | null;
+  size_bytes?: number | null;
+}
+
+export interface OrphanedService {
+  service_id: string;
+  artifact_path?: string | null;
+  missing_path: string;
 }
 
 export type RunnerDeleteMode = "configonly" | "localdelete" | "unregisteranddelete";
 
-export type ServiceMigrationStrategy = "replacewithrunnerbuddy";
+export type ServiceMigrationStrategy = "replacewithrunnerbuddy" | "removeexternal";
 
 const call = <T>(command: string, args?: Record<string, unknown>): Promise<T> =>
   invoke<T>(command, args);
@@ -172,6 +385,19 @@ export const updateSettings = (
   patch: Partial<SettingsConfig>
 ): Promise<SettingsSnapshot> => call("settings_update", { patch });
 
+export interface ConfigImportResult {
+  imported_runners: number;
+  missing_aliases: string[];
+}
+
+export const exportConfig = (path: string): Promise<void> => call("config_export", { path });
+
+export const exportInventory = (format: "csv" | "json", path: string): Promise<number> =>
+  call("export_inventory", { format, path });
+
+export const importConfig = (path: string, merge: boolean): Promise<ConfigImportResult> =>
+  call("config_import", { path, merge });
+
 export const completeOnboarding = (): Promise<SettingsSnapshot> =>
   call("onboarding_complete");
 
@@ -197,9 +423,42 @@ export const updateRunnerProfile = (
     work_dir?: string;
     scope?: RunnerScope | null;
     pat_alias?: string;
+    ephemeral_restart?: boolean;
+    auto_restart?: boolean;
+    start_on_app_launch?: boolean;
+    version_pinned?: boolean;
+    restart_policy?: RestartPolicy;
+    restart_sec?: number;
+    start_limit_interval_secs?: number;
+    start_limit_burst?: number;
+    memory_max?: string;
+    cpu_quota?: string;
+    keep_alive?: KeepAlive;
+    throttle_interval_secs?: number;
+    nice?: number;
+    process_type?: ProcessType;
   }
 ): Promise<RunnerProfile> => call("runners_update_profile", { runnerId, patch });
 
+export const setRunnerEnv = (
+  runnerId: string,
+  env: Record<string, string>
+): Promise<RunnerProfile> => call("runners_set_env", { runnerId, env });
+
+export interface EnvEntry {
+  key: string;
+  value: string;
+}
+
+export const getRunnerEnvFile = (runnerId: string): Promise<EnvEntry[]> =>
+  call("runners_get_env_file", { runnerId });
+
+export const setRunnerEnvFile = (
+  runnerId: string,
+  entries: EnvEntry[],
+  merge = true
+): Promise<EnvEntry[]> => call("runners_set_env_file", { runnerId, entries, merge });
+
 export const deleteRunnerProfile = (
   runnerId: string,
   mode: RunnerDeleteMode
@@ -217,8 +476,39 @@ export const importGhToken = (alias: string): Promise<void> =>
 export const clearPat = (alias: string): Promise<void> =>
   call("auth_clear_pat", { alias });
 
-export const checkPat = (alias: string): Promise<boolean> =>
-  call("auth_check_pat", { alias });
+export const saveGithubApp = (
+  alias: string,
+  appId: number,
+  installationId: number,
+  privateKey: string
+): Promise<void> => call("auth_save_app", { alias, appId, installationId, privateKey });
+
+export const clearGithubApp = (alias: string): Promise<void> =>
+  call("auth_clear_app", { alias });
+
+export interface AliasInfo {
+  alias: string;
+  auth_method: AuthMethod;
+  stored: boolean;
+  is_default: boolean;
+  runner_ids: string[];
+}
+
+export interface SecretBackendInfo {
+  backend: "keyring" | "file";
+  location?: string | null;
+  passphrase: boolean;
+}
+
+export const getSecretBackend = (): Promise<SecretBackendInfo> => call("auth_secret_backend");
+
+export const listAliases = (): Promise<AliasInfo[]> => call("auth_list_aliases");
+
+export const renameAlias = (oldAlias: string, newAlias: string): Promise<void> =>
+  call("auth_rename_alias", { oldAlias, newAlias });
+
+export const checkPat = (alias: string, scope?: RunnerScope | null): Promise<PatCheckResult> =>
+  call("auth_check_pat", { alias, scope: scope ?? null });
 
 export const setDefaultPatAlias = (alias: string): Promise<void> =>
   call("auth_set_default_alias", { alias });
@@ -229,19 +519,104 @@ export const githubGetRegistrationToken = (
 ): Promise<GitHubRegistrationToken> =>
   call("github_get_registration_token", { scope, alias });
 
-export const githubListRepos = (alias: string): Promise<GitHubRepoInfo[]> =>
-  call("github_list_repos", { alias });
+export const githubListRepos = (
+  alias: string,
+  adminOnly = false,
+  refresh = false
+): Promise<GitHubListing<GitHubRepoInfo>> =>
+  call("github_list_repos", { alias, adminOnly, refresh });
+
+export const githubSearchRepos = (
+  alias: string,
+  query: string,
+  adminOnly = false
+): Promise<GitHubListing<GitHubRepoInfo>> =>
+  call("github_search_repos", { alias, query, adminOnly });
+
+export const githubListOrgs = (
+  alias: string,
+  refresh = false
+): Promise<GitHubListing<GitHubOrgInfo>> => call("github_list_orgs", { alias, refresh });
+
+export const githubListRunners = (
+  scope: RunnerScope,
+  alias: string
+): Promise<GitHubListing<GitHubRegisteredRunner>> => call("github_list_runners", { scope, alias });
+
+export const githubListRunnerGroups = (
+  scope: RunnerScope,
+  alias: string
+): Promise<GitHubListing<GitHubRunnerGroup>> =>
+  call("github_list_runner_groups", { scope, alias });
+
+export const networkProxyStatus = (): Promise<NetworkProxyStatus> =>
+  call("network_proxy_status");
 
-export const githubListOrgs = (alias: string): Promise<GitHubOrgInfo[]> =>
-  call("github_list_orgs", { alias });
+export const testGithubConnectivity = (): Promise<ConnectivityProbe[]> =>
+  call("test_github_connectivity");
+
+export const githubRateLimit = (alias: string): Promise<GitHubRateLimit> =>
+  call("github_rate_limit", { alias });
 
 export const repairRunnerScope = (runnerId: string): Promise<RunnerProfile> =>
   call("runner_repair_scope", { runnerId });
 
 export const downloadRunner = (
   runnerId: string,
-  version?: string
-): Promise<RunnerProfile> => call("runner_download", { runnerId, version });
+  version?: string,
+  expectedSha256?: string
+): Promise<RunnerProfile> => call("runner_download", { runnerId, version, expectedSha256 });
+
+export interface RunnerRelease {
+  version: string;
+  prerelease: boolean;
+  draft: boolean;
+  published_at?: string | null;
+}
+
+export const listRunnerVersions = (limit?: number): Promise<RunnerRelease[]> =>
+  call("list_runner_versions", { limit: limit ?? null });
+
+export interface DownloadedArchive {
+  name: string;
+  size_bytes: number;
+  modified?: number | null;
+}
+
+export interface DownloadsUsage {
+  path: string;
+  total_bytes: number;
+  archives: DownloadedArchive[];
+}
+
+export interface PruneResult {
+  removed: string[];
+  freed_bytes: number;
+}
+
+export const downloadsUsage = (): Promise<DownloadsUsage> => call("downloads_usage");
+
+export const pruneDownloads = (keep: number): Promise<PruneResult> =>
+  call("prune_downloads", { keep });
+
+export const cancelRunnerDownload = (runnerId: string): Promise<boolean> =>
+  call("runner_download_cancel", { runnerId });
+
+export const installRunnerDependencies = (runnerId: string): Promise<void> =>
+  call("runner_install_dependencies", { runnerId });
+
+export interface RunnerUpdateInfo {
+  current?: string | null;
+  latest: string;
+  update_available: boolean;
+  pinned: boolean;
+}
+
+export const checkRunnerUpdate = (runnerId: string): Promise<RunnerUpdateInfo> =>
+  call("runner_check_update", { runnerId });
+
+export const updateRunner = (runnerId: string): Promise<RunnerProfile> =>
+  call("runner_update", { runnerId });
 
 export const configureRunner = (params: {
   runnerId: string;
@@ -249,13 +624,21 @@ export const configureRunner = (params: {
   name: string;
   labels: string[];
   workDir: string;
+  runnerGroup?: string | null;
+  ephemeral?: boolean;
+  jit?: boolean;
 }): Promise<RunnerProfile> =>
   call("runner_configure", {
     runnerId: params.runnerId,
-    scope: params.scope,
-    name: params.name,
-    labels: params.labels,
-    workDir: params.workDir,
+    options: {
+      scope: params.scope,
+      name: params.name,
+      labels: params.labels,
+      work_dir: params.workDir,
+      runner_group: params.runnerGroup ?? null,
+      ephemeral: params.ephemeral ?? false,
+      jit: params.jit ?? false,
+    },
   });
 
 export const startRunner = (runnerId: string): Promise<RuntimeState> =>
@@ -264,23 +647,48 @@ export const startRunner = (runnerId: string): Promise<RuntimeState> =>
 export const stopRunner = (runnerId: string): Promise<RuntimeState> =>
   call("runner_stop", { runnerId });
 
+export const drainRunner = (
+  runnerId: string,
+  timeoutSecs?: number
+): Promise<RuntimeState> => call("runner_drain", { runnerId, timeoutSecs });
+
+export const setStatusMonitorPaused = (paused: boolean): Promise<void> =>
+  call("status_monitor_set_paused", { paused });
+
 export const fetchRunnerStatus = (runnerId: string): Promise<RuntimeState> =>
   call("runner_status", { runnerId });
 
 export const fetchRunnerStatusAll = (): Promise<Record<string, RuntimeState>> =>
   call("runner_status_all");
 
-export const installService = (runnerId: string): Promise<void> =>
-  call("service_install", { runnerId });
+export const getDashboard = (): Promise<DashboardSummary> => call("app_dashboard");
+
+export const quitApp = (force = false): Promise<void> => call("app_quit", { force });
+
+export const startAllRunners = (): Promise<Record<string, BulkOutcome>> =>
+  call("runners_start_all");
+
+export const stopAllRunners = (): Promise<Record<string, BulkOutcome>> =>
+  call("runners_stop_all");
+
+export const installService = (
+  runnerId: string,
+  options: { systemScope?: boolean; systemUser?: string } = {}
+): Promise<void> =>
+  call("service_install", {
+    runnerId,
+    systemScope: options.systemScope ?? null,
+    systemUser: options.systemUser ?? null,
+  });
 
 export const setRunOnBoot = (runnerId: string, enabled: boolean): Promise<void> =>
   call("service_enable_on_boot", { runnerId, enabled });
 
-export const fetchServiceStatus = (runnerId: string): Promise<ServiceStatus> =>
-  call("service_status", { runnerId });
+export const fetchServiceStatus = (runnerId: string, force = false): Promise<ServiceStatus> =>
+  call("service_status", { runnerId, force });
 
-export const fetchServiceStatusAll = (): Promise<Record<string, ServiceStatus>> =>
-  call("service_status_all");
+export const fetchServiceStatusAll = (force = false): Promise<Record<string, ServiceStatus>> =>
+  call("service_status_all", { force });
 
 export const listLogSources = (runnerId: string): Promise<LogSource[]> =>
   call("logs_list_sources", { runnerId });
@@ -288,10 +696,74 @@ export const listLogSources = (runnerId: string): Promise<LogSource[]> =>
 export const tailLogs = (
   runnerId: string,
   source: string,
-  limit?: number
-): Promise<LogLine[]> => call("logs_tail", { runnerId, source, limit });
+  limit?: number,
+  offsets?: { fromOffset?: number; beforeOffset?: number }
+): Promise<LogChunk> =>
+  call("logs_tail", {
+    runnerId,
+    source,
+    limit,
+    fromOffset: offsets?.fromOffset ?? null,
+    beforeOffset: offsets?.beforeOffset ?? null,
+  });
+
+export const mergedLogs = (runnerId: string, limit?: number): Promise<MergedLogLine[]> =>
+  call("logs_merged", { runnerId, limit: limit ?? null });
+
+export interface LogCleanup {
+  files: string[];
+  freed_bytes: number;
+}
+
+export const clearLogs = (runnerId: string, includeRotated = false): Promise<LogCleanup> =>
+  call("logs_clear", { runnerId, includeRotated });
+
+export const pruneDiagLogs = (runnerId: string, keepDays: number): Promise<LogCleanup> =>
+  call("logs_prune", { runnerId, keepDays });
+
+export const getLogLevel = (): Promise<string> => call("get_log_level");
 
-export const discoverScan = (): Promise<DiscoveryCandidate[]> => call("discover_scan");
+export const setLogLevel = (level: string): Promise<string> => call("set_log_level", { level });
+
+export const searchLogs = (
+  runnerId: string,
+  query: string,
+  isRegex = false,
+  maxResults?: number
+): Promise<LogMatch[]> =>
+  call("logs_search", { runnerId, query, isRegex, maxResults: maxResults ?? null });
+
+export type RunnerLocation = "install" | "work" | "logs";
+
+export interface IntegrityReport {
+  install_path: string;
+  ok: boolean;
+  checks: { name: string; ok: boolean; detail: string }[];
+}
+
+export const verifyInstallIntegrity = (runnerId: string): Promise<IntegrityReport> =>
+  call("verify_install_integrity", { runnerId });
+
+export const openRunnerLocation = (runnerId: string, which: RunnerLocation): Promise<void> =>
+  call("open_runner_location", { runnerId, which });
+
+export const diagnoseEnvironment = (): Promise<EnvironmentCheck[]> =>
+  call("diagnose_environment");
+
+export const exportDiagnostics = (
+  destination: string,
+  runnerId?: string | null
+): Promise<DiagnosticsExport> =>
+  call("diagnostics_export", { destination, runnerId: runnerId ?? null });
+
+export const followLogs = (runnerId: string, source: string): Promise<string> =>
+  call("logs_follow_start", { runnerId, source });
+
+export const stopFollowingLogs = (followId?: string): Promise<void> =>
+  call("logs_follow_stop", { followId: followId ?? null });
+
+export const discoverScan = (includeSizes = false): Promise<DiscoveryCandidate[]> =>
+  call("discover_scan", { includeSizes });
 
 export const discoverImport = (
   candidateId: string,
@@ -300,14 +772,24 @@ export const discoverImport = (
     move_install: boolean;
     verify_after_move?: boolean;
     delete_original_after_verify?: boolean;
+    verify_hashes?: boolean;
   }
 ): Promise<string> => call("discover_import", { candidateId, options });
 
+export const discoverOrphanedServices = (): Promise<OrphanedService[]> =>
+  call("discover_orphaned_services");
+
+export const discoverRemoveOrphanedService = (serviceId: string): Promise<void> =>
+  call("discover_remove_orphaned_service", { serviceId });
+
 export const discoverMigrateService = (
   runnerId: string,
   strategy: ServiceMigrationStrategy
 ): Promise<void> => call("discover_migrate_service", { runnerId, strategy });
 
+export const discoverRestoreExternalService = (runnerId: string): Promise<void> =>
+  call("discover_restore_external_service", { runnerId });
+
 export const discoverRemoveExternalArtifacts = (runnerId: string): Promise<void> =>
   call("discover_remove_external_artifacts", { runnerId });
 
@@ -321,8 +803,10 @@ export const discoverDeleteOriginalInstall = (runnerId: string): Promise<void> =
 
 export const discoverMoveInstall = (
   runnerId: string,
-  destination?: string
-): Promise<RunnerProfile> => call("discover_move_install", { runnerId, destination });
+  destination?: string,
+  verifyHashes?: boolean
+): Promise<RunnerProfile> =>
+  call("discover_move_install", { runnerId, destination, verifyHashes: verifyHashes ?? null });
 
 export const discoverRollbackMove = (runnerId: string): Promise<RunnerProfile> =>
   call("discover_rollback_move", { runnerId });